@@ -0,0 +1,53 @@
+//! Compares reading many small values one `copy_address` call at a time against reading them
+//! all with a single `copy_addresses` call.
+//!
+//! On Linux `copy_addresses` issues one `process_vm_readv` for the whole batch, so the batched
+//! read should perform `FIELDS` times fewer system calls per iteration. On other platforms both
+//! strategies currently make the same number of calls.
+
+use process_memory::{CopyAddress, Pid, TryIntoProcessHandle};
+use std::time::Instant;
+
+const FIELDS: usize = 50;
+const ITERATIONS: usize = 100_000;
+
+fn main() -> std::io::Result<()> {
+    let values: Vec<u32> = (0..FIELDS as u32).collect();
+    #[allow(clippy::cast_possible_wrap)]
+    let handle = (std::process::id() as Pid).try_into_process_handle()?;
+    let mut buffers = [[0_u8; 4]; FIELDS];
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for (value, buf) in values.iter().zip(buffers.iter_mut()) {
+            handle.copy_address(value as *const u32 as usize, buf)?;
+        }
+    }
+    let single = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut requests: Vec<(usize, &mut [u8])> = values
+            .iter()
+            .zip(buffers.iter_mut())
+            .map(|(value, buf)| (value as *const u32 as usize, &mut buf[..]))
+            .collect();
+        handle.copy_addresses(&mut requests)?;
+    }
+    let batched = start.elapsed();
+
+    for (value, buf) in values.iter().zip(buffers.iter()) {
+        assert_eq!(*value, u32::from_ne_bytes(*buf));
+    }
+
+    println!(
+        "copy_address:   {} calls per iteration, {:?} per iteration",
+        FIELDS,
+        single / ITERATIONS as u32
+    );
+    println!(
+        "copy_addresses: 1 call per iteration, {:?} per iteration",
+        batched / ITERATIONS as u32
+    );
+    Ok(())
+}
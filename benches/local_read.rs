@@ -0,0 +1,34 @@
+//! Compares reading a value in our own process through a `DataMember`, which makes a system call
+//! (such as `process_vm_readv`) for every read, against reading it through a `LocalMember`, which
+//! dereferences the pointer directly.
+
+use process_memory::{DataMember, LocalMember, Memory, Pid, TryIntoProcessHandle};
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn main() -> std::io::Result<()> {
+    let value = 0x1234_5678_u32;
+    let inner = &raw const value as usize;
+    let offsets = vec![&raw const inner as usize, 0];
+    #[allow(clippy::cast_possible_wrap)]
+    let handle = (std::process::id() as Pid).try_into_process_handle()?;
+    let remote = DataMember::<u32>::new_offset(handle, offsets.clone());
+    let local = LocalMember::<u32>::new_offset(offsets);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert_eq!(std::hint::black_box(&remote).read()?, value);
+    }
+    let syscall = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert_eq!(std::hint::black_box(&local).read()?, value);
+    }
+    let direct = start.elapsed();
+
+    println!("DataMember::read:  {:?} per read", syscall / ITERATIONS);
+    println!("LocalMember::read: {:?} per read", direct / ITERATIONS);
+    Ok(())
+}
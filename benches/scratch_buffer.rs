@@ -0,0 +1,82 @@
+//! Compares scanning every readable region of this process a page at a time with `copy_address`,
+//! which allocates a new `Vec` for every page, against `copy_address_buf`, which reuses one.
+//!
+//! Allocations are counted with a global allocator, so `copy_address_buf` should report a
+//! handful of allocations for the whole scan, while `copy_address` makes one per page.
+
+use process_memory::{
+    copy_address, copy_address_buf, EnumerateRegions, Pid, Protection, TryIntoProcessHandle,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const PAGE: usize = 4096;
+
+/// Counts every allocation, and otherwise just uses the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() -> std::io::Result<()> {
+    #[allow(clippy::cast_possible_wrap)]
+    let handle = (std::process::id() as Pid).try_into_process_handle()?;
+    let pages: Vec<usize> = handle
+        .regions()?
+        .into_iter()
+        .filter(|region| region.protection.contains(Protection::READ))
+        .flat_map(|region| (region.base..region.end()).step_by(PAGE))
+        .collect();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut read = 0;
+    for &page in &pages {
+        if let Ok(bytes) = copy_address(page, PAGE, &handle) {
+            read += bytes.len();
+        }
+    }
+    let allocating = (
+        start.elapsed(),
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+    );
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut buf = Vec::new();
+    let mut read_buf = 0;
+    for &page in &pages {
+        if copy_address_buf(page, PAGE, &handle, &mut buf).is_ok() {
+            read_buf += buf.len();
+        }
+    }
+    let reusing = (
+        start.elapsed(),
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+    );
+
+    println!("Scanned {} pages ({read} bytes read)", pages.len());
+    println!(
+        "copy_address:     {} allocations, {:?}",
+        allocating.1, allocating.0
+    );
+    println!(
+        "copy_address_buf: {} allocations, {:?} ({read_buf} bytes read)",
+        reusing.1, reusing.0
+    );
+    Ok(())
+}
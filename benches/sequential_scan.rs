@@ -0,0 +1,58 @@
+//! Compares reading a large buffer of this process from front to back through `/proc/<pid>/mem`
+//! with and without `ProcessHandleExt::set_scan_mode`, which hints to the kernel that the reads
+//! are sequential.
+//!
+//! The hint only applies to `/proc/<pid>/mem`, which is only on Linux. How much it helps depends
+//! on the kernel, so the two throughputs are printed for comparison rather than checked.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn main() -> std::io::Result<()> {
+    use process_memory::{CopyAddress, Pid, ProcessHandleExt, TryIntoProcessHandle};
+    use std::time::{Duration, Instant};
+
+    const SIZE: usize = 256 * 1024 * 1024;
+    const CHUNK: usize = 1024 * 1024;
+    const PASSES: u32 = 4;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let data: Vec<u8> = (0..SIZE).map(|i| i as u8).collect();
+    #[allow(clippy::cast_possible_wrap)]
+    let handle = (std::process::id() as Pid).try_into_process_handle()?;
+    handle.use_proc_mem(true);
+
+    let mut buf = vec![0_u8; CHUNK];
+    let mut scan = |enabled: bool| -> std::io::Result<Duration> {
+        handle.set_scan_mode(enabled);
+        let start = Instant::now();
+        for _ in 0..PASSES {
+            for offset in (0..SIZE).step_by(CHUNK) {
+                handle.copy_address(data.as_ptr() as usize + offset, &mut buf)?;
+            }
+        }
+        assert_eq!(buf[CHUNK - 1], data[SIZE - 1]);
+        Ok(start.elapsed())
+    };
+
+    // Read everything once first, so that neither run pays for faulting in the buffer.
+    let _ = scan(false)?;
+    let normal = scan(false)?;
+    let sequential = scan(true)?;
+
+    #[allow(clippy::cast_precision_loss)]
+    let throughput =
+        |elapsed: Duration| (SIZE as f64 * f64::from(PASSES)) / elapsed.as_secs_f64() / 1e9;
+    println!(
+        "default:   {normal:?} for {PASSES} passes ({:.2} GB/s)",
+        throughput(normal)
+    );
+    println!(
+        "scan mode: {sequential:?} for {PASSES} passes ({:.2} GB/s)",
+        throughput(sequential)
+    );
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn main() {
+    println!("The scan mode hint only has an effect on Linux");
+}
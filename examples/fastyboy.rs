@@ -39,7 +39,7 @@ pub fn get_pid(process_name: &str) -> process_memory::Pid {
         dwFlags: 0,
         szExeFile: [0; winapi::shared::minwindef::MAX_PATH],
     };
-    let snapshot: process_memory::ProcessHandle;
+    let snapshot: winapi::um::winnt::HANDLE;
     unsafe {
         snapshot = winapi::um::tlhelp32::CreateToolhelp32Snapshot(
             winapi::um::tlhelp32::TH32CS_SNAPPROCESS,
@@ -65,10 +65,10 @@ fn main() -> std::io::Result<()> {
     use process_memory::*;
     let process_handle = get_pid("MirrorsEdgeCatalyst.exe").try_into_process_handle()?;
 
-    let mut spawn_timer = DataMember::<f32>::new(process_handle);
+    let mut spawn_timer = DataMember::<f32>::new(process_handle.clone());
     spawn_timer.set_offset(vec![0x1_42_14_2a_d8, 0xac]);
 
-    let mut level_warmup = DataMember::<f32>::new(process_handle);
+    let mut level_warmup = DataMember::<f32>::new(process_handle.clone());
     level_warmup.set_offset(vec![0x1_42_14_2a_d8, 0x9c]);
 
     let mut emitters_enabled = DataMember::<bool>::new(process_handle);
@@ -0,0 +1,22 @@
+bitflags::bitflags! {
+    /// The access to a process that is requested by [`open_process`].
+    ///
+    /// Only Windows grants access per handle, so this is ignored on other platforms, where a
+    /// `ProcessHandle` always has whatever access the operating system allows us.
+    ///
+    /// [`open_process`]: fn.open_process.html
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct AccessMode: u8 {
+        /// Memory can be read with [`CopyAddress`].
+        ///
+        /// [`CopyAddress`]: trait.CopyAddress.html
+        const READ = 1;
+        /// Memory can be written with [`PutAddress`], and have its protection changed and be
+        /// allocated or freed.
+        ///
+        /// [`PutAddress`]: trait.PutAddress.html
+        const WRITE = 1 << 1;
+        /// Information about the process, such as its regions of memory, can be queried.
+        const QUERY = 1 << 2;
+    }
+}
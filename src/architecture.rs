@@ -1,7 +1,8 @@
 use std::convert::TryInto;
 
 /// Enum representing the architecture of a process
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Architecture {
     /// 8-bit architecture
@@ -54,6 +55,9 @@ impl Architecture {
 
     /// Convert bytes read from memory into a pointer in the
     /// current architecture.
+    ///
+    /// # Panics
+    /// Panics if `bytes` is not exactly as long as a pointer in this architecture.
     #[must_use]
     pub fn pointer_from_ne_bytes(self, bytes: &[u8]) -> usize {
         match self {
@@ -0,0 +1,184 @@
+use crate::{CopyAddress, DataMember, ProcessHandle};
+
+/// # A view of an array in the memory of another program
+/// An `ArrayMember` reads the elements of an array whose start is found by following a chain of
+/// offsets, just like a [`DataMember`]. The elements don't have to be tightly packed: element `i`
+/// is read from `base + i * stride`, so arrays of structures can be read one field at a time by
+/// pointing the offsets at the field in the first element and using the size of the structure as
+/// the stride.
+///
+/// The chain of offsets is resolved by a [`DataMember`], so everything that affects how it is
+/// resolved (such as the architecture, or offsets relative to a module) works the same way, and
+/// `i * stride` is only added once the chain has been followed.
+///
+/// Examples:
+/// ```rust
+/// # use process_memory::{ArrayMember, Pid, TryIntoProcessHandle};
+/// // An array of structures, where we only want the first field of each
+/// let entities = [[1_u32, 100], [2, 200], [3, 300]];
+///
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let ids = ArrayMember::<u32>::new(handle, vec![entities.as_ptr() as usize], 8, entities.len());
+/// assert_eq!(ids.get(1).unwrap(), 2);
+/// let ids: Vec<u32> = ids.iter().collect::<std::io::Result<_>>().unwrap();
+/// assert_eq!(ids, [1, 2, 3]);
+/// ```
+///
+/// Like a [`DataMember`], an `ArrayMember` can read from any handle that implements
+/// [`CopyAddress`], not just a [`ProcessHandle`].
+///
+/// [`DataMember`]: struct.DataMember.html
+/// [`CopyAddress`]: trait.CopyAddress.html
+/// [`ProcessHandle`]: struct.ProcessHandle.html
+#[derive(Clone, Debug)]
+pub struct ArrayMember<T, H = ProcessHandle> {
+    base: DataMember<T, H>,
+    stride: usize,
+    len: usize,
+}
+
+impl<T: Sized + Copy, H: CopyAddress> ArrayMember<T, H> {
+    /// Create a new `ArrayMember` of `len` elements, `stride` bytes apart, starting at the address
+    /// that `base_offsets` resolve to in the process of `handle`.
+    #[must_use]
+    pub fn new(handle: H, base_offsets: Vec<usize>, stride: usize, len: usize) -> Self {
+        Self::from_member(DataMember::new_offset(handle, base_offsets), stride, len)
+    }
+
+    /// Create a new `ArrayMember` of `len` elements, `stride` bytes apart, whose first element is
+    /// `base`. This lets the base be configured like any other [`DataMember`], such as with
+    /// [`DataMember::set_arch`] or [`DataMember::new_module_offset`].
+    ///
+    /// [`DataMember`]: struct.DataMember.html
+    /// [`DataMember::set_arch`]: struct.DataMember.html#method.set_arch
+    /// [`DataMember::new_module_offset`]: struct.DataMember.html#method.new_module_offset
+    #[must_use]
+    pub fn from_member(base: DataMember<T, H>, stride: usize, len: usize) -> Self {
+        Self { base, stride, len }
+    }
+
+    /// Get the number of elements in the array.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the array has no elements.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the distance in bytes between the start of one element and the start of the next.
+    #[must_use]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Reads element `index` of the array, resolving the offsets to the start of the array first.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `index` isn't less than
+    /// the length of the array, or an error if resolving the offsets or copying memory fails.
+    pub fn get(&self, index: usize) -> std::io::Result<T> {
+        if index >= self.len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Index {index} is out of bounds for an array of {}",
+                    self.len
+                ),
+            ));
+        }
+        self.base
+            .with_address(|base| self.base.read_at(self.element_address(base, index)?))
+    }
+
+    /// Reads every element of the array in turn. The offsets are only resolved once, when the
+    /// first element is read; if that fails, the error is the only item returned.
+    pub fn iter(&self) -> impl Iterator<Item = std::io::Result<T>> + '_ {
+        let mut base = None;
+        let mut index = 0;
+        std::iter::from_fn(move || {
+            if index >= self.len {
+                return None;
+            }
+            let base = match base {
+                Some(base) => base,
+                None => match self.base.with_address(Ok) {
+                    Ok(address) => *base.insert(address),
+                    Err(err) => {
+                        index = self.len;
+                        return Some(Err(err));
+                    }
+                },
+            };
+            let element = self
+                .element_address(base, index)
+                .and_then(|addr| self.base.read_at(addr));
+            index += 1;
+            Some(element)
+        })
+    }
+
+    /// The address of element `index` of an array starting at `base`, failing with a
+    /// `std::io::ErrorKind` of `InvalidInput` if it is past the end of the address space.
+    fn element_address(&self, base: usize, index: usize) -> std::io::Result<usize> {
+        index
+            .checked_mul(self.stride)
+            .and_then(|offset| base.checked_add(offset))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "Element {index} of the array at {base:#x} is past the end of the address \
+                         space"
+                    ),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TryIntoProcessHandle;
+    #[test]
+    fn read_strided_array() {
+        let entities = [[1_u32, 100, 0], [2, 200, 0], [3, 300, 0], [4, 400, 0]];
+        let table = entities.as_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        // Through a pointer to the array, reading the second field of each element.
+        let health = ArrayMember::<u32>::new(handle, vec![&raw const table as usize, 4], 12, 4);
+        assert_eq!(health.len(), 4);
+        assert_eq!(health.get(2).unwrap(), 300);
+        assert_eq!(
+            health.get(4).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        let values: Vec<u32> = health.iter().collect::<std::io::Result<_>>().unwrap();
+        assert_eq!(values, [100, 200, 300, 400]);
+        let huge =
+            ArrayMember::<u32>::new(health.base.handle().clone(), vec![table], usize::MAX / 2, 4);
+        assert_eq!(huge.get(0).unwrap(), 1);
+        assert_eq!(
+            huge.get(3).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        let null = 0_usize;
+        let broken = ArrayMember::<u32>::from_member(
+            DataMember::new_offset(
+                health.base.handle().clone(),
+                vec![&raw const null as usize, 0],
+            ),
+            12,
+            4,
+        );
+        assert_eq!(broken.iter().filter(Result::is_err).count(), 1);
+        assert_eq!(broken.iter().count(), 1);
+    }
+}
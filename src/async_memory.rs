@@ -0,0 +1,170 @@
+//! Asynchronous reads and writes, for use from inside a `tokio` runtime, when the `async` feature
+//! is enabled.
+//!
+//! None of the platforms this crate supports have asynchronous system calls for reading another
+//! process's memory, so each operation is run on `tokio`'s blocking thread pool with
+//! `tokio::task::spawn_blocking`. This doesn't make reads any faster, but it stops them from
+//! blocking the executor that awaits them.
+//!
+//! ```rust
+//! # use process_memory::{DataMember, Pid, TryIntoProcessHandle};
+//! # use process_memory::async_memory::AsyncMemory;
+//! # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! # runtime.block_on(async {
+//! let x = 4_u32;
+//! let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+//! let member = DataMember::<u32>::new_offset(handle, vec![&x as *const _ as usize]);
+//! // The read happens on the blocking thread pool, while this task is suspended
+//! assert_eq!(AsyncMemory::read(&member).await.unwrap(), 4);
+//! # });
+//! ```
+//!
+//! The methods of [`AsyncMemory`] have the same names as those of [`Memory`], so when both traits
+//! are in scope they need to be called as `AsyncMemory::read(&member)`.
+//!
+//! [`AsyncMemory`]: trait.AsyncMemory.html
+//! [`Memory`]: ../trait.Memory.html
+
+use std::future::Future;
+
+use crate::CopyAddress;
+
+/// Run `f` on the blocking thread pool, turning a panic or cancellation of the task into an
+/// error.
+pub(crate) async fn blocking<R: Send + 'static>(
+    f: impl FnOnce() -> std::io::Result<R> + Send + 'static,
+) -> std::io::Result<R> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// A trait for copying memory without blocking the calling task, by running
+/// [`CopyAddress::copy_address`] on `tokio`'s blocking thread pool.
+///
+/// It is implemented for every type that can be copied from and shared with another thread, which
+/// includes [`ProcessHandle`].
+///
+/// [`CopyAddress::copy_address`]: ../trait.CopyAddress.html#tymethod.copy_address
+/// [`ProcessHandle`]: ../struct.ProcessHandle.html
+pub trait CopyAddressAsync {
+    /// Copy `buf.len()` bytes from `addr` into `buf`, like [`CopyAddress::copy_address`].
+    ///
+    /// As `buf` can't be handed to another thread, the bytes are read into a temporary buffer on
+    /// the blocking thread pool, and copied into `buf` once the read has finished.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails, or if the blocking task panics.
+    ///
+    /// [`CopyAddress::copy_address`]: ../trait.CopyAddress.html#tymethod.copy_address
+    fn copy_address_async<'a>(
+        &self,
+        addr: usize,
+        buf: &'a mut [u8],
+    ) -> impl Future<Output = std::io::Result<()>> + Send + 'a;
+}
+
+impl<T: CopyAddress + Clone + Send + 'static> CopyAddressAsync for T {
+    fn copy_address_async<'a>(
+        &self,
+        addr: usize,
+        buf: &'a mut [u8],
+    ) -> impl Future<Output = std::io::Result<()>> + Send + 'a {
+        let source = self.clone();
+        let len = buf.len();
+        async move {
+            let bytes = blocking(move || {
+                let mut bytes = vec![0_u8; len];
+                source.copy_address(addr, &mut bytes)?;
+                Ok(bytes)
+            })
+            .await?;
+            buf.copy_from_slice(&bytes);
+            Ok(())
+        }
+    }
+}
+
+/// The asynchronous counterpart of [`Memory`], for reading and writing a value without blocking
+/// the calling task.
+///
+/// Everything that is needed is copied out of `self` before the returned future is created, so
+/// the futures are `Send` and `'static` and can be spawned onto any executor, even though the
+/// types implementing `Memory` can't be shared between threads themselves.
+///
+/// [`Memory`]: ../trait.Memory.html
+pub trait AsyncMemory<T> {
+    /// Read the value from the location given by the offsets, like [`Memory::read`].
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or copying memory fails, or if the blocking task
+    /// panics.
+    ///
+    /// [`Memory::read`]: ../trait.Memory.html#tymethod.read
+    fn read(&self) -> impl Future<Output = std::io::Result<T>> + Send + 'static;
+
+    /// Write `value` to the location given by the offsets, like [`Memory::write`].
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or writing memory fails, or if the blocking task
+    /// panics.
+    ///
+    /// [`Memory::write`]: ../trait.Memory.html#tymethod.write
+    fn write(&self, value: &T) -> impl Future<Output = std::io::Result<()>> + Send + 'static;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{DataMember, Pid, TryIntoProcessHandle};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn copy_address_without_blocking() {
+        let test = [1_u8, 2, 3, 4];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut out = [0_u8; 4];
+        block_on(handle.copy_address_async(test.as_ptr() as usize, &mut out)).unwrap();
+        assert_eq!(test, out);
+    }
+
+    #[test]
+    fn modify_remote_async() {
+        let test = 4_u32;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle, vec![&raw const test as usize]);
+        assert_eq!(block_on(AsyncMemory::read(&member)).unwrap(), 4);
+        block_on(AsyncMemory::write(&member, &5)).unwrap();
+        assert_eq!(unsafe { (&raw const test).read_volatile() }, 5);
+    }
+
+    #[test]
+    fn write_module_relative_async() {
+        use crate::FindModule;
+
+        static VALUE: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(4);
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let module = exe.file_name().unwrap().to_str().unwrap();
+        let offset = VALUE.as_ptr() as usize - handle.get_module_base(module).unwrap();
+        let member = DataMember::<u32>::new_module_offset(handle, module, vec![offset]);
+        assert_eq!(block_on(AsyncMemory::read(&member)).unwrap(), 4);
+        block_on(AsyncMemory::write(&member, &5)).unwrap();
+        assert_eq!(VALUE.load(std::sync::atomic::Ordering::SeqCst), 5);
+    }
+}
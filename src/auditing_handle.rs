@@ -0,0 +1,158 @@
+use crate::{Architecture, CopyAddress, ProcessHandle, PutAddress};
+
+/// The address and bytes of every write, in the order they were made.
+type AuditLog = Vec<(usize, Vec<u8>)>;
+
+/// A handle that keeps a record of every write made through it, for reviewing afterwards exactly
+/// what was changed in the target.
+///
+/// An `AuditingHandle` wraps another handle, normally a [`ProcessHandle`], and implements
+/// [`CopyAddress`] and [`PutAddress`] itself, so it can be used anywhere the handle it wraps can,
+/// such as in a [`DataMember`]. Reads are passed straight on. Every write is added to the log, as
+/// the address and the bytes written, before it is passed on, so writes that go on to fail are
+/// logged as well. The log is shared between the handle and all of its clones.
+///
+/// ```rust
+/// # use process_memory::{AuditingHandle, DataMember, Memory, Pid, TryIntoProcessHandle};
+/// let value = 1_u32;
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let audited = AuditingHandle::new(handle);
+/// let addr = &value as *const u32 as usize;
+/// let member = DataMember::<u32, _>::new_offset(audited.clone(), vec![addr]);
+/// member.write(&2).unwrap();
+/// assert_eq!(audited.audit_log(), vec![(addr, 2_u32.to_ne_bytes().to_vec())]);
+/// ```
+///
+/// [`ProcessHandle`]: struct.ProcessHandle.html
+/// [`CopyAddress`]: trait.CopyAddress.html
+/// [`PutAddress`]: trait.PutAddress.html
+/// [`DataMember`]: struct.DataMember.html
+#[derive(Clone, Debug)]
+pub struct AuditingHandle<H = ProcessHandle> {
+    inner: H,
+    log: std::sync::Arc<std::sync::Mutex<AuditLog>>,
+}
+
+impl<H: CopyAddress + PutAddress> AuditingHandle<H> {
+    /// Wrap `inner`, starting with an empty log.
+    #[must_use]
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            log: std::sync::Arc::default(),
+        }
+    }
+
+    /// Get a copy of the log, as the address and bytes of every write in the order they were made.
+    #[must_use]
+    pub fn audit_log(&self) -> Vec<(usize, Vec<u8>)> {
+        self.lock().clone()
+    }
+
+    /// Empty the log, returning everything that was in it.
+    pub fn take_audit_log(&self) -> Vec<(usize, Vec<u8>)> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Get the handle that reads and writes are passed on to.
+    #[must_use]
+    pub fn inner(&self) -> &H {
+        &self.inner
+    }
+
+    /// Consume the `AuditingHandle`, returning the handle that it wrapped.
+    #[must_use]
+    pub fn into_inner(self) -> H {
+        self.inner
+    }
+
+    /// Lock the log. A panic while it was locked can't have left it half-written, so a poisoned
+    /// lock is still used.
+    fn lock(&self) -> std::sync::MutexGuard<'_, AuditLog> {
+        self.log
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Every read is passed on to the wrapped handle.
+impl<H: CopyAddress + PutAddress> CopyAddress for AuditingHandle<H> {
+    fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+        self.inner.copy_address(addr, buf)
+    }
+
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.copy_address_partial(addr, buf)
+    }
+
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        self.inner.copy_address_uninit(addr, buf)
+    }
+
+    fn copy_addresses(&self, requests: &mut [(usize, &mut [u8])]) -> std::io::Result<()> {
+        self.inner.copy_addresses(requests)
+    }
+
+    fn get_pointer_width(&self) -> Architecture {
+        self.inner.get_pointer_width()
+    }
+}
+
+/// Every write is logged, and then passed on to the wrapped handle.
+impl<H: CopyAddress + PutAddress> PutAddress for AuditingHandle<H> {
+    fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        self.lock().push((addr, buf.to_vec()));
+        self.inner.put_address(addr, buf)
+    }
+
+    /// The whole batch is logged at once, so it is passed on to the wrapped handle as one batch.
+    fn put_addresses(&self, writes: &[(usize, &[u8])]) -> std::io::Result<()> {
+        self.lock()
+            .extend(writes.iter().map(|&(addr, buf)| (addr, buf.to_vec())));
+        self.inner.put_addresses(writes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TryIntoProcessHandle;
+
+    #[test]
+    fn log_every_write() {
+        let mut values = [1_u8, 2, 3, 4];
+        let addr = values.as_mut_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let audited = AuditingHandle::new(handle);
+        let clone = audited.clone();
+
+        let mut read = [0_u8; 2];
+        audited.copy_address(addr, &mut read).unwrap();
+        assert_eq!(read, [1, 2]);
+        assert!(audited.audit_log().is_empty());
+
+        audited.put_address(addr, &[9]).unwrap();
+        clone
+            .put_addresses(&[(addr + 2, &[8, 7][..]), (addr + 1, &[6][..])])
+            .unwrap();
+        assert!(audited.put_address(0, &[5]).is_err());
+        assert_eq!(std::hint::black_box(&mut values), &[9, 6, 8, 7]);
+        assert_eq!(
+            clone.take_audit_log(),
+            vec![
+                (addr, vec![9]),
+                (addr + 2, vec![8, 7]),
+                (addr + 1, vec![6]),
+                (0, vec![5])
+            ]
+        );
+        assert!(audited.audit_log().is_empty());
+    }
+}
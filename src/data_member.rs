@@ -1,4 +1,7 @@
-use crate::{Architecture, CopyAddress, Memory, ProcessHandle, PutAddress};
+use crate::{
+    Architecture, CopyAddress, EnumerateRegions, FindModule, Memory, PointerPath, ProcessHandle,
+    Protection, PutAddress, ReadStruct,
+};
 
 /// # Tools for working with memory of other programs
 /// This module provides functions for modifying the memory of a program from outside of the
@@ -46,34 +49,87 @@ use crate::{Architecture, CopyAddress, Memory, ProcessHandle, PutAddress};
 /// println!("Current value: {}", member.read().unwrap());
 /// member.write(&123_u32).unwrap();
 /// ```
+///
+/// A `DataMember` normally reads from a [`ProcessHandle`], but it can read from anything that
+/// implements [`CopyAddress`], such as your own type that reads memory over a debug probe or from
+/// a crash dump. Writing needs [`PutAddress`] as well, offsets relative to a module need
+/// [`FindModule`], and [`DataMember::validate`] needs [`EnumerateRegions`].
+///
+/// ```rust
+/// # use process_memory::{Architecture, CopyAddress, DataMember};
+/// /// Reads from a copy of the target's memory that starts at address 0x1000.
+/// struct Image(Vec<u8>);
+///
+/// impl CopyAddress for Image {
+///     fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+///         let start = addr.checked_sub(0x1000).filter(|start| start + buf.len() <= self.0.len());
+///         let start = start.ok_or(std::io::ErrorKind::InvalidInput)?;
+///         buf.copy_from_slice(&self.0[start..start + buf.len()]);
+///         Ok(())
+///     }
+///
+///     fn get_pointer_width(&self) -> Architecture {
+///         Architecture::Arch32Bit
+///     }
+/// }
+///
+/// let image = Image(vec![0x08, 0x10, 0, 0, 0, 0, 0, 0, 42, 0, 0, 0]);
+/// let member = DataMember::<u32, _>::new_offset(image, vec![0x1000, 0]);
+/// assert_eq!(member.read_cached().unwrap(), 42);
+/// ```
+///
+/// [`ProcessHandle`]: struct.ProcessHandle.html
+/// [`CopyAddress`]: trait.CopyAddress.html
+/// [`PutAddress`]: trait.PutAddress.html
+/// [`FindModule`]: trait.FindModule.html
+/// [`EnumerateRegions`]: trait.EnumerateRegions.html
+/// [`DataMember::validate`]: struct.DataMember.html#method.validate
 #[derive(Clone, Debug)]
-pub struct DataMember<T> {
+pub struct DataMember<T, H = ProcessHandle> {
     offsets: Vec<usize>,
-    process: ProcessHandle,
+    process: H,
     arch: Architecture,
+    offset_widths: Vec<Architecture>,
+    cache_enabled: bool,
+    cached_offset: std::cell::Cell<Option<usize>>,
+    module: Option<(String, FindBase<H>)>,
+    module_base: std::cell::Cell<Option<usize>>,
     _phantom: std::marker::PhantomData<*mut T>,
 }
 
-impl<T: Sized + Copy> DataMember<T> {
-    /// Create a new `DataMember` from a [`ProcessHandle`]. You must remember to call
-    /// [`try_into_process_handle`] on a [`Pid`], because the types may have the same backing type,
-    /// resulting in errors when called with the wrong value.
+/// Looks up the base address of a module, which is [`FindModule::get_module_base`] for the handle
+/// of a `DataMember` whose offsets are relative to a module.
+///
+/// [`FindModule::get_module_base`]: trait.FindModule.html#tymethod.get_module_base
+type FindBase<H> = fn(&H, &str) -> std::io::Result<usize>;
+
+impl<T: Sized + Copy, H: CopyAddress> DataMember<T, H> {
+    /// Create a new `DataMember` from a [`ProcessHandle`], or any other handle that implements
+    /// [`CopyAddress`]. You must remember to call [`try_into_process_handle`] on a [`Pid`], because
+    /// the types may have the same backing type, resulting in errors when called with the wrong
+    /// value.
     ///
     /// By default, there will be no offsets, leading to an error when attempting to call
     /// [`Memory::read`], so you will likely need to call [`Memory::set_offset`] before attempting
     /// any reads.
     ///
     /// [`try_into_process_handle`]: trait.TryIntoProcessHandle.html#tymethod.try_into_process_handle
-    /// [`ProcessHandle`]: type.ProcessHandle.html
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
     /// [`Pid`]: type.Pid.html
     /// [`Memory::read`]: trait.Memory.html#tymethod.read
     /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
+    /// [`CopyAddress`]: trait.CopyAddress.html
     #[must_use]
-    pub fn new(handle: ProcessHandle) -> Self {
+    pub fn new(handle: H) -> Self {
         Self {
             offsets: Vec::new(),
+            arch: handle.get_pointer_width(),
             process: handle,
-            arch: Architecture::from_native(),
+            offset_widths: Vec::new(),
+            cache_enabled: false,
+            cached_offset: std::cell::Cell::new(None),
+            module: None,
+            module_base: std::cell::Cell::new(None),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -83,14 +139,19 @@ impl<T: Sized + Copy> DataMember<T> {
     /// the same backing type as a [`ProcessHandle`], resulting in an error.
     ///
     /// [`try_into_process_handle`]: trait.TryIntoProcessHandle.html#tymethod.try_into_process_handle
-    /// [`ProcessHandle`]: type.ProcessHandle.html
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
     /// [`Pid`]: type.Pid.html
     #[must_use]
-    pub fn new_offset(handle: ProcessHandle, offsets: Vec<usize>) -> Self {
+    pub fn new_offset(handle: H, offsets: Vec<usize>) -> Self {
         Self {
             offsets,
+            arch: handle.get_pointer_width(),
             process: handle,
-            arch: Architecture::from_native(),
+            offset_widths: Vec::new(),
+            cache_enabled: false,
+            cached_offset: std::cell::Cell::new(None),
+            module: None,
+            module_base: std::cell::Cell::new(None),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -99,45 +160,714 @@ impl<T: Sized + Copy> DataMember<T> {
     ///
     /// This can be used for reading memory offsets of programs that are of
     /// different architectures to the host program.
-    /// This defaults to the architecture of the host program.
+    /// This defaults to the pointer width of the handle from [`CopyAddress::get_pointer_width`],
+    /// which for a [`ProcessHandle`] is its architecture: that of the host program unless it has
+    /// been changed with [`ProcessHandleExt::set_arch`].
+    ///
+    /// [`CopyAddress::get_pointer_width`]: trait.CopyAddress.html#method.get_pointer_width
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
+    /// [`ProcessHandleExt::set_arch`]: trait.ProcessHandleExt.html#tymethod.set_arch
     #[must_use]
     pub fn set_arch(mut self, arch: Architecture) -> Self {
         self.arch = arch;
         self
     }
+
+    /// Sets the width of each pointer in the chain separately, for chains that mix pointers of
+    /// different sizes, such as a 32-bit handle table embedded in a 64-bit process.
+    ///
+    /// `widths[i]` is the width of the pointer that is read after adding the offset at index `i`,
+    /// so the last offset, which is only ever added, has no width. Any pointer without a width is
+    /// read with the architecture of the `DataMember`, so passing an empty slice goes back to
+    /// reading every pointer with it.
+    ///
+    /// ```no_run
+    /// # use process_memory::{Memory, DataMember, Pid, TryIntoProcessHandle, Architecture};
+    /// # let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+    /// let mut member = DataMember::<u32>::new_offset(handle, vec![0x1000, 0x20, 0x8]);
+    /// // The pointer at 0x1000 is 64 bits wide, but the one it leads to is only 32 bits wide
+    /// member.set_offset_widths(&[Architecture::Arch64Bit, Architecture::Arch32Bit]);
+    /// println!("Value: {}", member.read().unwrap());
+    /// ```
+    pub fn set_offset_widths(&mut self, widths: &[Architecture]) {
+        self.offset_widths = widths.to_vec();
+        self.invalidate_cache();
+    }
+
+    /// Point the `DataMember` at `handle`, keeping its offsets, such as after the target process
+    /// has been restarted and opened again.
+    ///
+    /// The architecture of the `DataMember` is left as it is, as the new process will normally be
+    /// the same program as the old one, and the cached address (and module base address) is
+    /// invalidated.
+    pub fn set_handle(&mut self, handle: H) {
+        self.process = handle;
+        self.invalidate_cache();
+        self.module_base.set(None);
+    }
+
+    /// Get the handle, normally a [`ProcessHandle`], that the `DataMember` reads from and writes
+    /// to.
+    ///
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
+    #[must_use]
+    pub fn handle(&self) -> &H {
+        &self.process
+    }
+
+    /// Append `offset` to the end of the offsets, for building up a chain of offsets one level at
+    /// a time. Use [`Memory::set_offset`] to replace all of the offsets at once.
+    ///
+    /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
+    pub fn push_offset(&mut self, offset: usize) -> &mut Self {
+        self.offsets.push(offset);
+        self.invalidate_cache();
+        self
+    }
+
+    /// Append `offset` to the end of the offsets, returning the `DataMember` so that calls can be
+    /// chained.
+    #[must_use]
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offsets.push(offset);
+        self.invalidate_cache();
+        self
+    }
+
+    /// Get the offsets of the `DataMember`.
+    #[must_use]
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Enables caching of the address that the offsets resolve to, for use by
+    /// [`DataMember::read_cached`].
+    ///
+    /// The cache is never checked against the offsets, so it is only correct for as long as the
+    /// pointers in the chain don't change. If the target relocates the object (or any object in
+    /// the chain), or the process is restarted and the `DataMember` is pointed at the new process,
+    /// [`DataMember::read_cached`] will keep reading from the old address for as long as that
+    /// address is still readable. Call [`DataMember::invalidate_cache`] whenever that may have
+    /// happened. Changing the offsets invalidates the cache automatically.
+    ///
+    /// [`DataMember::read_cached`]: struct.DataMember.html#method.read_cached
+    /// [`DataMember::invalidate_cache`]: struct.DataMember.html#method.invalidate_cache
+    #[must_use]
+    pub fn enable_cache(mut self) -> Self {
+        self.cache_enabled = true;
+        self
+    }
+
+    /// Forget the cached address, so that the next call to [`DataMember::read_cached`] resolves
+    /// the offsets again.
+    ///
+    /// [`DataMember::read_cached`]: struct.DataMember.html#method.read_cached
+    pub fn invalidate_cache(&self) {
+        self.cached_offset.set(None);
+    }
+
+    /// Reads the value like [`Memory::read`], but reuses the address that the offsets resolved to
+    /// last time if caching has been enabled with [`DataMember::enable_cache`], saving a read
+    /// for each level of the chain. If reading from the cached address fails, the offsets are
+    /// resolved again. Without caching enabled, this is the same as [`Memory::read`].
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or copying memory fails.
+    ///
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    /// [`DataMember::enable_cache`]: struct.DataMember.html#method.enable_cache
+    pub fn read_cached(&self) -> std::io::Result<T> {
+        if let Some(offset) = self.cached_offset.get() {
+            if let Ok(value) = self.read_at(offset) {
+                return Ok(value);
+            }
+            self.invalidate_cache();
+        }
+        let (offset, value) = self.with_address(|offset| Ok((offset, self.read_at(offset)?)))?;
+        if self.cache_enabled {
+            self.cached_offset.set(Some(offset));
+        }
+        Ok(value)
+    }
+
+    /// Resolve the offsets and call `f` with the address they lead to.
+    ///
+    /// If the offsets are relative to a module and the module's cached base address was used,
+    /// but resolving the offsets or `f` failed, the base address is looked up again and both are
+    /// tried once more.
+    pub(crate) fn with_address<R>(
+        &self,
+        mut f: impl FnMut(usize) -> std::io::Result<R>,
+    ) -> std::io::Result<R> {
+        let Some((module, find_base)) = &self.module else {
+            return f(self.resolve(&self.offsets)?);
+        };
+        if let Some(base) = self.module_base.get() {
+            if let Ok(result) = self.resolve_from(base).and_then(&mut f) {
+                return Ok(result);
+            }
+        }
+        let base = find_base(&self.process, module)?;
+        self.module_base.set(Some(base));
+        f(self.resolve_from(base)?)
+    }
+
+    /// Resolve the offsets after adding the module base address `base` to the first one.
+    fn resolve_from(&self, base: usize) -> std::io::Result<usize> {
+        self.resolve(&self.offsets_from(base)?)
+    }
+
+    /// The offsets with the module base address `base` added to the first one.
+    fn offsets_from(&self, base: usize) -> std::io::Result<Vec<usize>> {
+        let mut offsets = self.offsets.clone();
+        match offsets.first_mut() {
+            Some(first) => *first = crate::add_offset(base, *first, 0)?,
+            None => offsets.push(base),
+        }
+        Ok(offsets)
+    }
+
+    /// Resolve `offsets`, reading each pointer with its width from
+    /// [`DataMember::set_offset_widths`].
+    ///
+    /// [`DataMember::set_offset_widths`]: struct.DataMember.html#method.set_offset_widths
+    fn resolve(&self, offsets: &[usize]) -> std::io::Result<usize> {
+        if self.offset_widths.is_empty() {
+            return self.process.get_offset(offsets, self.arch);
+        }
+        crate::follow_offsets(&self.process, offsets, |index| {
+            self.offset_widths.get(index).copied().unwrap_or(self.arch)
+        })
+    }
+
+    /// Read a `T` from `offset`. The value is read straight into place, so nothing is allocated
+    /// however large `T` is.
+    pub(crate) fn read_at(&self, offset: usize) -> std::io::Result<T> {
+        let mut value = std::mem::MaybeUninit::<T>::zeroed();
+        let buffer: &mut [u8] = unsafe {
+            std::slice::from_raw_parts_mut(value.as_mut_ptr().cast(), std::mem::size_of::<T>())
+        };
+        self.copy_checked(offset, buffer)?;
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Copy `buffer.len()` bytes from `offset`, after checking that they don't run past the end
+    /// of the address space, which would otherwise wrap around to the start of it.
+    fn copy_checked(&self, offset: usize, buffer: &mut [u8]) -> std::io::Result<()> {
+        crate::check_span(offset, buffer.len())?;
+        self.process.copy_address(offset, buffer)
+    }
+
+    /// Reads the value at the location given by the offsets without tearing, for a struct that the
+    /// target may be writing to at the same time, without having to suspend the process.
+    ///
+    /// Like a seqlock reader, the value is read until two reads in a row match byte for byte, and
+    /// that value is returned. The offsets are resolved again for each attempt, and after the
+    /// first read each attempt is one more read, so at most `attempts + 1` reads are made. This
+    /// only guards against a write happening during a read; a value that is always changing
+    /// faster than it can be read twice will never stabilise, so keep `T` small.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `WouldBlock` if no two reads in a row
+    /// matched within `attempts` attempts, an error with a `std::io::ErrorKind` of `InvalidInput`
+    /// without reading anything if `attempts` is 0, as no two reads could be compared, or an error
+    /// if resolving the offsets or copying memory fails.
+    pub fn read_stable(&self, attempts: usize) -> std::io::Result<T> {
+        if attempts == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "A stable read needs at least one attempt",
+            ));
+        }
+        let size = std::mem::size_of::<T>();
+        let read = |value: &mut std::mem::MaybeUninit<T>| -> std::io::Result<()> {
+            let buffer: &mut [u8] =
+                unsafe { std::slice::from_raw_parts_mut(value.as_mut_ptr().cast(), size) };
+            self.with_address(|offset| self.copy_checked(offset, buffer))
+        };
+        let bytes = |value: &std::mem::MaybeUninit<T>| -> &[u8] {
+            unsafe { std::slice::from_raw_parts(value.as_ptr().cast(), size) }
+        };
+        let mut values = [std::mem::MaybeUninit::<T>::zeroed(); 2];
+        read(&mut values[0])?;
+        for attempt in 1..=attempts {
+            read(&mut values[attempt % 2])?;
+            if bytes(&values[0]) == bytes(&values[1]) {
+                return Ok(unsafe { values[0].assume_init() });
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::WouldBlock,
+            format!("The value kept changing while it was read, {attempts} times in a row"),
+        ))
+    }
+
+    /// Reads `out.len()` consecutive values starting at the location given by the offsets into
+    /// `out`, resolving the offsets once and copying every value with a single read.
+    ///
+    /// `T` should be a plain-old-data type, such as an integer, a float or an array or
+    /// `#[repr(C)]` struct of them, as the bytes are copied into `out` without checking that they
+    /// form valid values of `T`. The values are assumed to be packed one after the other in the
+    /// target, `std::mem::size_of::<T>()` bytes apart, including any padding. The location being
+    /// read from doesn't need to be aligned, as the bytes are copied straight into `out`.
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or copying memory fails.
+    pub fn read_slice(&self, out: &mut [T]) -> std::io::Result<()> {
+        let buffer: &mut [u8] = unsafe {
+            std::slice::from_raw_parts_mut(out.as_mut_ptr().cast(), std::mem::size_of_val(out))
+        };
+        self.with_address(|offset| self.copy_checked(offset, buffer))
+    }
+
+    /// Reads `count` consecutive values starting at the location given by the offsets, such as
+    /// the elements of a dynamic array whose length has just been read, with a single read.
+    ///
+    /// As `count` normally comes from the target itself, it is checked against `max` before
+    /// anything is allocated, so that a corrupt or stale count can't cause a huge allocation. The
+    /// same requirements on `T` apply as for [`DataMember::read_slice`].
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `count` is greater than
+    /// `max`, or an error if resolving the offsets or copying memory fails.
+    ///
+    /// [`DataMember::read_slice`]: struct.DataMember.html#method.read_slice
+    pub fn read_vec(&self, count: usize, max: usize) -> std::io::Result<Vec<T>> {
+        if count > max {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Refusing to read {count} values, which is more than the maximum of {max}"),
+            ));
+        }
+        let mut values = Vec::<T>::with_capacity(count);
+        // The buffer is zeroed first so that the bytes handed to `copy_address` are initialised.
+        let buffer: &mut [u8] = unsafe {
+            std::ptr::write_bytes(values.as_mut_ptr(), 0, count);
+            std::slice::from_raw_parts_mut(
+                values.as_mut_ptr().cast(),
+                count * std::mem::size_of::<T>(),
+            )
+        };
+        self.with_address(|offset| self.copy_checked(offset, buffer))?;
+        // Every value has now been read from the target.
+        unsafe { values.set_len(count) };
+        Ok(values)
+    }
+
+    /// Reads a `#[repr(C)]` struct `S` from the location given by the offsets, after checking that
+    /// its layout matches the size and alignment it has in the target, as described by its
+    /// [`ReadStruct`] implementation. Exactly `S::SIZE` bytes are read.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if the size or alignment of
+    /// `S` doesn't match the target, or an error if resolving the offsets or copying memory fails.
+    ///
+    /// [`ReadStruct`]: trait.ReadStruct.html
+    pub fn read_struct<S: ReadStruct>(&self) -> std::io::Result<S> {
+        crate::read_struct::check_layout::<S>()?;
+        let mut value = std::mem::MaybeUninit::<S>::zeroed();
+        let buffer: &mut [u8] =
+            unsafe { std::slice::from_raw_parts_mut(value.as_mut_ptr().cast(), S::SIZE) };
+        self.with_address(|offset| self.copy_checked(offset, buffer))?;
+        // Every byte has been read from the target, and `ReadStruct` promises that any bytes
+        // form a valid `S`.
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Reads the `size_of::<T>()` bytes of the value at the location given by the offsets, and
+    /// turns them into a `U` with `decode`, keeping reading a value and interpreting it, such as
+    /// a `u32` of bit flags or a fixed-point coordinate, together in one step.
+    ///
+    /// ```rust
+    /// # use process_memory::{DataMember, Pid, TryIntoProcessHandle};
+    /// // A 16.16 fixed-point number.
+    /// let x = 0x0001_8000_i32;
+    /// # let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+    /// let member = DataMember::<i32>::new_offset(handle, vec![&x as *const i32 as usize]);
+    /// let x = member
+    ///     .transmute_read(|bytes: [u8; 4]| f64::from(i32::from_ne_bytes(bytes)) / 65536.0)
+    ///     .unwrap();
+    /// assert_eq!(x, 1.5);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `N` isn't the size of
+    /// `T`, or an error if resolving the offsets or copying memory fails.
+    pub fn transmute_read<U, const N: usize>(
+        &self,
+        decode: impl FnOnce([u8; N]) -> U,
+    ) -> std::io::Result<U> {
+        if N != std::mem::size_of::<T>() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Can't decode {N} bytes from a {}, which is {} bytes",
+                    std::any::type_name::<T>(),
+                    std::mem::size_of::<T>()
+                ),
+            ));
+        }
+        let mut bytes = [0_u8; N];
+        self.with_address(|offset| self.copy_checked(offset, &mut bytes))?;
+        Ok(decode(bytes))
+    }
+
+    /// Reads `bit_len` bits starting `bit_offset` bits after the location given by the offsets,
+    /// such as a flag or small field packed into a larger word, reading only the bytes that
+    /// contain them. The bits may straddle byte boundaries, and `bit_offset` may be larger than
+    /// the size of `T`.
+    ///
+    /// Bits are numbered little-endian: bit 0 is the least significant bit of the first byte, and
+    /// bit 8 is the least significant bit of the second byte. The first bit read ends up as the
+    /// least significant bit of the result, so the bits of a `u32` at the location are the same
+    /// as those of `(value >> bit_offset) & mask` on a little-endian target.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `bit_len` is greater than
+    /// 64, or an error if resolving the offsets or copying memory fails.
+    pub fn read_bits(&self, bit_offset: usize, bit_len: usize) -> std::io::Result<u64> {
+        if bit_len > 64 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Can't read {bit_len} bits into a u64"),
+            ));
+        }
+        if bit_len == 0 {
+            return Ok(0);
+        }
+        let start = bit_offset / 8;
+        let shift = bit_offset % 8;
+        // At most 9 bytes, when 64 bits start partway through a byte.
+        let mut buffer = [0_u8; 9];
+        let buffer = &mut buffer[..(shift + bit_len).div_ceil(8)];
+        self.with_address(|offset| {
+            crate::check_span(offset, start + buffer.len())?;
+            self.process.copy_address(offset + start, buffer)
+        })?;
+        Ok(extract_bits(buffer, shift, bit_len))
+    }
+
+    /// Read the pointer `offset` bytes after the location given by the offsets, such as a pointer
+    /// field of a struct, and return a new `DataMember` for the `U` that it points to, using the
+    /// same handle and architecture.
+    ///
+    /// The pointer is read once, now, so the new `DataMember` has it as its only offset, and
+    /// keeps reading from the same address even if the pointer changes later. Fields of the `U`
+    /// that are themselves pointers can be followed in turn.
+    ///
+    /// ```rust
+    /// # use process_memory::{DataMember, Memory, Pid, TryIntoProcessHandle};
+    /// #[derive(Clone, Copy)]
+    /// #[repr(C)]
+    /// struct Node {
+    ///     value: u64,
+    ///     next: *const Node,
+    /// }
+    ///
+    /// let last = Node { value: 2, next: std::ptr::null() };
+    /// let first = Node { value: 1, next: &last };
+    /// # let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+    /// let member = DataMember::<Node>::new_offset(handle, vec![&first as *const Node as usize]);
+    /// let next = member.follow::<Node>(std::mem::offset_of!(Node, next)).unwrap();
+    /// assert_eq!(next.read().unwrap().value, 2);
+    /// // The last node's `next` is null.
+    /// assert!(next.follow::<Node>(std::mem::offset_of!(Node, next)).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or reading the pointer fails, or with a
+    /// `std::io::ErrorKind` of `NotFound`, classified as `MemoryError::NullInChain`, if the
+    /// pointer is null.
+    pub fn follow<U: Sized + Copy>(&self, offset: usize) -> std::io::Result<DataMember<U, H>>
+    where
+        H: Clone,
+    {
+        let pointer = self.with_address(|addr| {
+            let field = crate::add_offset(addr, offset, self.offsets.len())?;
+            let mut bytes = vec![0; self.arch as usize];
+            self.copy_checked(field, &mut bytes)?;
+            match self.arch.pointer_from_ne_bytes(&bytes) {
+                0 => Err(crate::memory_error::classified_error(
+                    std::io::ErrorKind::NotFound,
+                    crate::memory_error::ErrorClass::NullInChain,
+                    format!("The pointer at {field:#x} is null"),
+                )),
+                pointer => Ok(pointer),
+            }
+        })?;
+        Ok(DataMember::new_offset(self.process.clone(), vec![pointer]).set_arch(self.arch))
+    }
+}
+
+impl<T: Sized + Copy, H: CopyAddress + FindModule> DataMember<T, H> {
+    /// Create a new `DataMember` from a [`ProcessHandle`] and a [`PointerPath`], by looking up the
+    /// base address of the path's module with [`FindModule::get_module_base`] and adding it to the
+    /// first offset.
+    ///
+    /// The module is only looked up once, so if it is reloaded somewhere else the `DataMember`
+    /// needs to be created again.
+    ///
+    /// # Errors
+    /// Returns an error if the module can't be found, or an error with a `std::io::ErrorKind` of
+    /// `InvalidInput` if adding the first offset to the base address would overflow.
+    ///
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
+    /// [`PointerPath`]: struct.PointerPath.html
+    /// [`FindModule::get_module_base`]: trait.FindModule.html#tymethod.get_module_base
+    pub fn from_path(handle: H, path: &PointerPath) -> std::io::Result<Self> {
+        let base = handle.get_module_base(&path.module)?;
+        let mut offsets = path.offsets.clone();
+        match offsets.first_mut() {
+            Some(first) => *first = crate::add_offset(base, *first, 0)?,
+            None => offsets.push(base),
+        }
+        Ok(Self::new_offset(handle, offsets))
+    }
+
+    /// Create a new `DataMember` whose offsets are relative to the module `module`, such as
+    /// `client.dll + 0x1A2B3C`. Whenever the offsets are resolved, the base address of the module
+    /// is added to the first offset.
+    ///
+    /// The base address is looked up with [`FindModule::get_module_base`] the first time the
+    /// offsets are resolved, and cached. If resolving the offsets, or reading or writing through
+    /// them, fails while using the cached base address, the module may have been unloaded and
+    /// loaded again somewhere else, so it is looked up again and the operation is tried once
+    /// more.
+    ///
+    /// ```no_run
+    /// # use process_memory::{Memory, DataMember, Pid, TryIntoProcessHandle};
+    /// # let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+    /// let health =
+    ///     DataMember::<u32>::new_module_offset(handle, "client.dll", vec![0x1A2B3C, 0x10]);
+    /// println!("Health: {}", health.read().unwrap());
+    /// ```
+    ///
+    /// [`FindModule::get_module_base`]: trait.FindModule.html#tymethod.get_module_base
+    #[must_use]
+    pub fn new_module_offset(handle: H, module: &str, offsets: Vec<usize>) -> Self {
+        let mut member = Self::new_offset(handle, offsets);
+        member.module = Some((module.to_owned(), H::get_module_base));
+        member
+    }
+}
+
+impl<T: Sized + Copy, H: CopyAddress + EnumerateRegions> DataMember<T, H> {
+    /// Check that every address in the chain of offsets is readable, by following the chain like
+    /// [`Memory::get_offset`] but first checking that each address is in a readable region, like
+    /// [`ProcessHandleExt::is_readable`], up to and including the final address of the value.
+    /// This is useful to check offsets that have just been loaded, such as from a config file,
+    /// and report which of them is wrong.
+    ///
+    /// Only the first byte at each address is checked, and the chain may of course change after
+    /// it has been checked.
+    ///
+    /// # Errors
+    /// Returns an error that names the index of the first offset that leads to a bad address:
+    /// one with a `std::io::ErrorKind` of `InvalidInput`, classified as
+    /// `MemoryError::AddressUnreadable`, if the address isn't readable, or one with a
+    /// `std::io::ErrorKind` of `NotFound`, classified as `MemoryError::NullInChain`, if the
+    /// pointer read from it is null. Also returns an error if there are no offsets, or if the
+    /// base address of the module that the offsets are relative to can't be found.
+    ///
+    /// [`Memory::get_offset`]: trait.Memory.html#tymethod.get_offset
+    /// [`ProcessHandleExt::is_readable`]: trait.ProcessHandleExt.html#method.is_readable
+    pub fn validate(&self) -> std::io::Result<()> {
+        let offsets = match &self.module {
+            Some((module, find_base)) => self.offsets_from(find_base(&self.process, module)?)?,
+            None => self.offsets.clone(),
+        };
+        if offsets.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No offsets to resolve",
+            ));
+        }
+        let mut addr = 0_usize;
+        let mut pointer = Vec::new();
+        for (index, offset) in offsets.iter().enumerate() {
+            addr = crate::add_offset(addr, *offset, index)?;
+            if !crate::has_protection(&self.process, addr, Protection::READ) {
+                return Err(crate::memory_error::classified_error(
+                    std::io::ErrorKind::InvalidInput,
+                    crate::memory_error::ErrorClass::AddressUnreadable,
+                    format!("Offset {index} leads to {addr:#x}, which isn't readable"),
+                ));
+            }
+            if index + 1 == offsets.len() {
+                break;
+            }
+            let arch = self.offset_widths.get(index).copied().unwrap_or(self.arch);
+            pointer.resize(arch as usize, 0);
+            self.process
+                .copy_address(addr, &mut pointer)
+                .map_err(|err| {
+                    crate::memory_error::classified_error(
+                        err.kind(),
+                        crate::memory_error::ErrorClass::AddressUnreadable,
+                        format!("Offset {index} leads to {addr:#x}, which can't be read: {err}"),
+                    )
+                })?;
+            addr = arch.pointer_from_ne_bytes(&pointer);
+            if addr == 0 {
+                return Err(crate::memory_error::classified_error(
+                    std::io::ErrorKind::NotFound,
+                    crate::memory_error::ErrorClass::NullInChain,
+                    format!("Offset {index} leads to a null pointer"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Sized + Copy, H: CopyAddress + PutAddress> DataMember<T, H> {
+    /// Write `value` to `offset`.
+    fn write_at(&self, offset: usize, value: &T) -> std::io::Result<()> {
+        let buffer: &[u8] = unsafe {
+            std::slice::from_raw_parts(std::ptr::from_ref(value).cast(), std::mem::size_of::<T>())
+        };
+        self.put_checked(offset, buffer)
+    }
+
+    /// Put `buffer` at `offset`, after checking that it doesn't run past the end of the address
+    /// space.
+    fn put_checked(&self, offset: usize, buffer: &[u8]) -> std::io::Result<()> {
+        crate::check_span(offset, buffer.len())?;
+        self.process.put_address(offset, buffer)
+    }
+
+    /// Writes every value in `values` one after the other, starting at the location given by the
+    /// offsets, resolving the offsets once and writing all of them with a single write. This is
+    /// the counterpart of [`DataMember::read_slice`], and the values are laid out in the same way:
+    /// `std::mem::size_of::<T>()` bytes apart, including any padding.
+    ///
+    /// # Errors
+    /// Returns an error if resolving the offsets or writing memory fails, in which case some of
+    /// the values may already have been written.
+    ///
+    /// [`DataMember::read_slice`]: struct.DataMember.html#method.read_slice
+    pub fn write_slice(&self, values: &[T]) -> std::io::Result<()> {
+        let buffer: &[u8] = unsafe {
+            std::slice::from_raw_parts(values.as_ptr().cast(), std::mem::size_of_val(values))
+        };
+        self.with_address(|offset| self.put_checked(offset, buffer))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: Sized + Copy, H: CopyAddress + Clone + Send + 'static> DataMember<T, H> {
+    /// Copy everything needed to make a copy of the `DataMember` on another thread, as a
+    /// `DataMember` can't be sent between threads itself. The copy doesn't use caching.
+    fn detached(&self) -> impl FnOnce() -> Self + Send + 'static {
+        let (process, offsets, arch) = (self.process.clone(), self.offsets.clone(), self.arch);
+        let (module, module_base) = (self.module.clone(), self.module_base.get());
+        let offset_widths = self.offset_widths.clone();
+        move || {
+            let mut member = Self::new_offset(process, offsets).set_arch(arch);
+            member.offset_widths = offset_widths;
+            member.module = module;
+            member.module_base.set(module_base);
+            member
+        }
+    }
 }
 
-impl<T: Sized + Copy> Memory<T> for DataMember<T> {
+/// Extracts `bit_len` bits, starting at bit `shift` of the first byte, from `bytes` in
+/// little-endian bit order. `bytes` must hold no more than 16 bytes.
+fn extract_bits(bytes: &[u8], shift: usize, bit_len: usize) -> u64 {
+    let mut word = [0_u8; 16];
+    word[..bytes.len()].copy_from_slice(bytes);
+    let bits = u128::from_le_bytes(word) >> shift;
+    let mask = if bit_len >= 64 {
+        u64::MAX
+    } else {
+        (1_u64 << bit_len) - 1
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let bits = bits as u64;
+    bits & mask
+}
+
+impl<T: Sized + Copy, H: CopyAddress + PutAddress> Memory<T> for DataMember<T, H> {
     fn set_offset(&mut self, new_offsets: Vec<usize>) {
         self.offsets = new_offsets;
+        self.invalidate_cache();
     }
 
     fn get_offset(&self) -> std::io::Result<usize> {
-        self.process.get_offset(&self.offsets, self.arch)
+        self.with_address(Ok)
     }
 
     fn read(&self) -> std::io::Result<T> {
-        let offset = self.process.get_offset(&self.offsets, self.arch)?;
-        // This can't be [0_u8;size_of::<T>()] because no const generics.
-        // It will be freed at the end of the function because no references are held to it.
-        let mut buffer = vec![0_u8; std::mem::size_of::<T>()];
-        self.process.copy_address(offset, &mut buffer)?;
-        Ok(unsafe { (buffer.as_ptr() as *const T).read_unaligned() })
+        self.with_address(|offset| self.read_at(offset))
+    }
+
+    /// This copies the value straight into `out`, without an intermediate buffer.
+    fn read_into(&self, out: &mut T) -> std::io::Result<()> {
+        let buffer: &mut [u8] = unsafe {
+            std::slice::from_raw_parts_mut(std::ptr::from_mut(out).cast(), std::mem::size_of::<T>())
+        };
+        self.with_address(|offset| self.copy_checked(offset, buffer))
     }
 
     fn write(&self, value: &T) -> std::io::Result<()> {
-        use std::slice;
-        let offset = self.process.get_offset(&self.offsets, self.arch)?;
-        let buffer: &[u8] =
-            unsafe { slice::from_raw_parts(value as *const _ as _, std::mem::size_of::<T>()) };
-        self.process.put_address(offset, &buffer)
+        self.with_address(|offset| self.write_at(offset, value))
+    }
+
+    /// This resolves the offsets only once.
+    fn write_and_resolve(&self, value: &T) -> std::io::Result<usize> {
+        self.with_address(|offset| {
+            self.write_at(offset, value)?;
+            Ok(offset)
+        })
+    }
+
+    /// This resolves the offsets only once, and reads back from the address that was written to.
+    fn write_verified(&self, value: &T) -> std::io::Result<bool> {
+        self.with_address(|offset| {
+            self.write_at(offset, value)?;
+            let mut read = vec![0_u8; std::mem::size_of::<T>()];
+            self.process.copy_address(offset, &mut read)?;
+            Ok(read == crate::bytes_of(value))
+        })
+    }
+}
+
+/// Each operation resolves the offsets and copies memory on the blocking thread pool, using a copy
+/// of the `DataMember`'s handle and offsets. Like [`Memory::read`], the cached address isn't used,
+/// and a module base address that has to be looked up again isn't cached.
+///
+/// [`Memory::read`]: trait.Memory.html#tymethod.read
+#[cfg(feature = "async")]
+impl<T, H> crate::async_memory::AsyncMemory<T> for DataMember<T, H>
+where
+    T: Sized + Copy + Send + 'static,
+    H: CopyAddress + PutAddress + Clone + Send + 'static,
+{
+    fn read(&self) -> impl std::future::Future<Output = std::io::Result<T>> + Send + 'static {
+        let member = self.detached();
+        crate::async_memory::blocking(move || Memory::read(&member()))
+    }
+
+    fn write(
+        &self,
+        value: &T,
+    ) -> impl std::future::Future<Output = std::io::Result<()>> + Send + 'static {
+        let member = self.detached();
+        let value = *value;
+        crate::async_memory::blocking(move || Memory::write(&member(), &value))
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::borrow_as_ptr,
+    clippy::ref_as_ptr,
+    clippy::uninlined_format_args
+)]
 mod test {
     use super::*;
-    use crate::TryIntoProcessHandle;
+    use crate::{ProcessHandleExt, TryIntoProcessHandle};
     #[test]
     fn modify_remote_i32() {
         let test = 4_i32;
@@ -167,6 +897,18 @@ mod test {
         assert_eq!(test, -1);
     }
     #[test]
+    fn resolve_address_through_pointer() {
+        let target = 7_u32;
+        let pointer = &raw const target as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle, vec![&raw const pointer as usize, 0]);
+        assert_eq!(member.address().unwrap(), &raw const target as usize);
+        assert_eq!(member.address().unwrap(), member.get_offset().unwrap());
+    }
+    #[test]
     fn modify_remote_usize() {
         let test = 0_usize;
         #[allow(clippy::cast_possible_wrap)]
@@ -180,4 +922,490 @@ mod test {
         member.write(&0xffff).unwrap();
         assert_eq!(test, 0xffff);
     }
+    #[test]
+    fn read_remote_array_into() {
+        let mut test = [0_u8; 4096];
+        for (i, byte) in test.iter_mut().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let value = i as u8;
+            *byte = value;
+        }
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<[u8; 4096]>::new_offset(handle, vec![&raw const test as usize]);
+        let mut out = [0_u8; 4096];
+        member.read_into(&mut out).unwrap();
+        assert_eq!(test, out);
+    }
+    #[test]
+    fn read_remote_slice() {
+        let test = [1_u16, 0xbeef, 3, 0, 0xffff];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u16>::new_offset(handle, vec![test.as_ptr() as usize]);
+        let mut out = [0_u16; 5];
+        member.read_slice(&mut out).unwrap();
+        assert_eq!(test, out);
+    }
+    #[test]
+    fn write_remote_slice() {
+        let test = [0_u32; 6];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle, vec![test.as_ptr() as usize + 4]);
+        member.write_slice(&[7, 0xdead_beef, 9]).unwrap();
+        assert_eq!(test, [0, 7, 0xdead_beef, 9, 0, 0]);
+        member.write_slice(&[]).unwrap();
+    }
+    #[test]
+    fn write_and_resolve_remote() {
+        let test = 1_u64;
+        let pointer = &raw const test as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u64>::new_offset(handle, vec![&raw const pointer as usize, 0]);
+        assert_eq!(member.write_and_resolve(&9).unwrap(), pointer);
+        assert_eq!(test, 9);
+    }
+    #[test]
+    fn write_verified_remote() {
+        let test = 0_i32;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<i32>::new_offset(handle, vec![&raw const test as usize]);
+        assert!(member.write_verified(&-7).unwrap());
+        assert_eq!(test, -7);
+    }
+    #[test]
+    fn read_remote_vec() {
+        let test = vec![3_u32, 1, 4, 1, 5];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle, vec![test.as_ptr() as usize]);
+        assert_eq!(member.read_vec(test.len(), 16).unwrap(), test);
+        assert!(member.read_vec(0, 16).unwrap().is_empty());
+        assert_eq!(
+            member.read_vec(17, 16).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn extract_straddled_bits() {
+        assert_eq!(extract_bits(&[0b1010_0110], 1, 3), 0b011);
+        assert_eq!(extract_bits(&[0b1100_0000, 0b0000_0101], 6, 5), 0b1_0111);
+        assert_eq!(extract_bits(&[0xff; 9], 7, 64), u64::MAX);
+        assert_eq!(
+            extract_bits(&[0x80, 0x02, 0, 0, 0, 0, 0, 0, 0x01], 7, 64),
+            0x0200_0000_0000_0005
+        );
+    }
+    #[test]
+    fn read_remote_bits() {
+        let test = [0x1234_5678_9abc_def0_u64, 0x0fed_cba9_8765_4321];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u64>::new_offset(handle, vec![test.as_ptr() as usize]);
+        assert_eq!(member.read_bits(0, 64).unwrap(), test[0]);
+        assert_eq!(member.read_bits(4, 8).unwrap(), 0xef);
+        assert_eq!(member.read_bits(12, 12).unwrap(), 0xbcd);
+        assert_eq!(member.read_bits(60, 8).unwrap(), 0x11);
+        assert_eq!(member.read_bits(124, 4).unwrap(), 0x0);
+        assert_eq!(
+            member.read_bits(36, 64).unwrap(),
+            (test[0] >> 36) | (test[1] << 28)
+        );
+        assert_eq!(member.read_bits(3, 0).unwrap(), 0);
+        assert_eq!(
+            member.read_bits(0, 65).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn read_checked_struct() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C)]
+        struct Entity {
+            id: u16,
+            flags: u8,
+            position: [f32; 3],
+        }
+        crate::read_struct_layout! {
+            unsafe impl ReadStruct for Entity {
+                size: 16,
+                align: 4,
+                flags: 2,
+                position: 4,
+            }
+        }
+        /// Claims to be larger in the target than it is here.
+        #[derive(Clone, Copy, Debug)]
+        #[repr(C)]
+        struct Truncated(u32);
+        unsafe impl ReadStruct for Truncated {
+            const SIZE: usize = 8;
+            const ALIGN: usize = 4;
+        }
+
+        let test = Entity {
+            id: 7,
+            flags: 0b101,
+            position: [1.0, -2.5, 3.25],
+        };
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u8>::new_offset(handle, vec![&raw const test as usize]);
+        assert_eq!(member.read_struct::<Entity>().unwrap(), test);
+        assert_eq!(
+            member.read_struct::<Truncated>().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn mix_pointer_widths() {
+        let table = [0x1234_5678_u32, 0x0abc_def0, 0x1111_2222, 0x3333_4444];
+        let pointer = table.as_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut member =
+            DataMember::<u8>::new_offset(handle, vec![&raw const pointer as usize, 4, 0x10]);
+        member.set_offset_widths(&[Architecture::from_native(), Architecture::Arch32Bit]);
+        assert_eq!(member.get_offset().unwrap(), 0x0abc_def0 + 0x10);
+
+        // The second pointer is read with the architecture of the `DataMember` without a width.
+        member.set_offset_widths(&[Architecture::from_native()]);
+        let expected = member
+            .handle()
+            .get_offset(&[pointer + 4, 0x10], Architecture::from_native())
+            .unwrap();
+        assert_eq!(member.get_offset().unwrap(), expected);
+    }
+    #[test]
+    fn build_offset_chain() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut member = DataMember::<u32>::new(handle)
+            .with_offset(0x1234)
+            .with_offset(0x10);
+        let _ = member.push_offset(0x8).push_offset(0x4);
+        assert_eq!(member.offsets(), &[0x1234, 0x10, 0x8, 0x4]);
+        member.set_offset(vec![0x20]);
+        assert_eq!(member.offsets(), &[0x20]);
+    }
+    #[test]
+    fn point_at_new_handle() {
+        let test = 5_u16;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut member = DataMember::<u16>::new_offset(
+            crate::ProcessHandle::null_type(),
+            vec![&raw const test as usize],
+        );
+        assert!(member.read().is_err());
+        member.set_handle(handle.clone());
+        assert_eq!(member.handle(), &handle);
+        assert_eq!(member.read().unwrap(), 5);
+    }
+    #[test]
+    fn read_cached_reuses_address() {
+        let first = 1_u32;
+        let second = 2_u32;
+        let mut pointer = &raw const first as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle, vec![&raw const pointer as usize, 0])
+            .enable_cache();
+        assert_eq!(member.read_cached().unwrap(), 1);
+
+        // The cached address is still used after the pointer moves...
+        pointer = &raw const second as usize;
+        assert_eq!(member.read_cached().unwrap(), 1);
+        assert_eq!(member.read().unwrap(), 2);
+        // ...until the cache is invalidated.
+        member.invalidate_cache();
+        assert_eq!(member.read_cached().unwrap(), 2);
+        assert_eq!(pointer, &raw const second as usize);
+    }
+    #[test]
+    fn from_module_path() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let module = exe.file_name().unwrap().to_str().unwrap();
+        let base = handle.get_module_base(module).unwrap();
+        let path = PointerPath {
+            module: module.to_owned(),
+            offsets: vec![0x10],
+        };
+        let member = DataMember::<u8>::from_path(handle.clone(), &path).unwrap();
+        assert_eq!(member.get_offset().unwrap(), base + 0x10);
+
+        let path = PointerPath {
+            module: module.to_owned(),
+            offsets: vec![usize::MAX],
+        };
+        assert_eq!(
+            DataMember::<u8>::from_path(handle, &path)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn validate_offset_chain() {
+        let target = 7_u32;
+        let pointer = &raw const target as usize;
+        let null = 0_usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let valid =
+            DataMember::<u32>::new_offset(handle.clone(), vec![&raw const pointer as usize, 0]);
+        valid.validate().unwrap();
+
+        let null_pointer =
+            DataMember::<u32>::new_offset(handle.clone(), vec![&raw const null as usize, 0]);
+        let err = null_pointer.validate().unwrap_err();
+        assert!(err.to_string().contains("Offset 0"));
+        assert!(matches!(
+            crate::MemoryError::from(err),
+            crate::MemoryError::NullInChain(_)
+        ));
+
+        // The pointer is fine, but the value is far past it, at an address that isn't mapped.
+        let unmapped = DataMember::<u32>::new_offset(
+            handle,
+            vec![&raw const pointer as usize, usize::MAX / 2],
+        );
+        let err = unmapped.validate().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("Offset 1"));
+        assert!(matches!(
+            crate::MemoryError::from(err),
+            crate::MemoryError::AddressUnreadable(_)
+        ));
+    }
+    #[test]
+    fn refuse_overflowing_addresses() {
+        let target = 7_u64;
+        let pointer = &raw const target as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        // The last offset takes the address past the end of the address space.
+        let past_end = DataMember::<u64>::new_offset(
+            handle.clone(),
+            vec![&raw const pointer as usize, usize::MAX],
+        );
+        let err = past_end.get_offset().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("hop 1"));
+        assert_eq!(
+            past_end.read().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            past_end.validate().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        // The address itself is fine, but the value would run past the end of the address space.
+        let straddling = DataMember::<u64>::new_offset(handle, vec![usize::MAX - 3]);
+        assert_eq!(straddling.get_offset().unwrap(), usize::MAX - 3);
+        assert_eq!(
+            straddling.read().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            straddling.write(&0).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        let mut values = [0_u64; 2];
+        assert_eq!(
+            straddling.read_slice(&mut values).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn retry_torn_reads() {
+        /// A value that changes on each of its first `changes` reads, and then stays the same.
+        #[derive(Debug)]
+        struct Changing {
+            reads: std::cell::Cell<u8>,
+            changes: u8,
+        }
+
+        impl CopyAddress for Changing {
+            fn copy_address(&self, _addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+                let reads = self.reads.get();
+                self.reads.set(reads + 1);
+                buf.fill(reads.min(self.changes));
+                Ok(())
+            }
+
+            fn get_pointer_width(&self) -> Architecture {
+                Architecture::from_native()
+            }
+        }
+
+        let changing = |changes| Changing {
+            reads: std::cell::Cell::new(0),
+            changes,
+        };
+        let member = DataMember::<[u16; 2], _>::new_offset(changing(3), vec![0x1000]);
+        assert_eq!(member.read_stable(4).unwrap(), [0x0303; 2]);
+        assert_eq!(member.handle().reads.get(), 5);
+
+        let member = DataMember::<[u16; 2], _>::new_offset(changing(5), vec![0x1000]);
+        assert_eq!(
+            member.read_stable(3).unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+        assert_eq!(member.handle().reads.get(), 4);
+
+        let member = DataMember::<[u16; 2], _>::new_offset(changing(0), vec![0x1000]);
+        assert_eq!(
+            member.read_stable(0).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(member.handle().reads.get(), 0);
+
+        let value = 7_u64;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u64>::new_offset(handle, vec![&raw const value as usize]);
+        assert_eq!(member.read_stable(1).unwrap(), 7);
+    }
+
+    #[test]
+    fn decode_flags_and_fixed_point() {
+        bitflags::bitflags! {
+            #[derive(Debug, PartialEq, Eq)]
+            struct Flags: u32 {
+                const ALIVE = 1 << 1;
+                const VISIBLE = 1 << 3;
+            }
+        }
+        let flags = 0b1010_u32;
+        let position = [-0x0002_4000_i32, 0x0000_0010];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<u32>::new_offset(handle.clone(), vec![&raw const flags as usize]);
+        assert_eq!(
+            member
+                .transmute_read(|bytes| Flags::from_bits_truncate(u32::from_ne_bytes(bytes)))
+                .unwrap(),
+            Flags::ALIVE | Flags::VISIBLE
+        );
+        assert_eq!(
+            member
+                .transmute_read(|bytes: [u8; 8]| bytes)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        let fixed = |bytes: [u8; 4]| f64::from(i32::from_ne_bytes(bytes)) / 65536.0;
+        let x = DataMember::<i32>::new_offset(handle.clone(), vec![position.as_ptr() as usize]);
+        let y = DataMember::<i32>::new_offset(handle, vec![position.as_ptr() as usize + 4]);
+        assert!((x.transmute_read(fixed).unwrap() + 2.25).abs() < f64::EPSILON);
+        assert!((y.transmute_read(fixed).unwrap() - 0.000_244_140_625).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn follow_pointer_fields() {
+        #[derive(Clone, Copy, Debug)]
+        #[repr(C)]
+        struct Inner {
+            value: u64,
+            next: *const Inner,
+        }
+        #[derive(Clone, Copy, Debug)]
+        #[repr(C)]
+        struct Outer {
+            id: u32,
+            inner: *const Inner,
+        }
+        let second = Inner {
+            value: 2,
+            next: std::ptr::null(),
+        };
+        let first = Inner {
+            value: 1,
+            next: &raw const second,
+        };
+        let outer = Outer {
+            id: 7,
+            inner: &raw const first,
+        };
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member = DataMember::<Outer>::new_offset(handle, vec![&raw const outer as usize]);
+        assert_eq!(member.read().unwrap().id, 7);
+
+        let inner_offset = std::mem::offset_of!(Outer, inner);
+        let next_offset = std::mem::offset_of!(Inner, next);
+        let inner = member.follow::<Inner>(inner_offset).unwrap();
+        assert_eq!(inner.offsets(), &[&raw const first as usize]);
+        assert_eq!(inner.read().unwrap().value, 1);
+        let next = inner.follow::<Inner>(next_offset).unwrap();
+        assert_eq!(next.read().unwrap().value, 2);
+        assert!(matches!(
+            crate::MemoryError::from(next.follow::<Inner>(next_offset).unwrap_err()),
+            crate::MemoryError::NullInChain(_)
+        ));
+    }
+
+    #[test]
+    fn rebase_on_module() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let module = exe.file_name().unwrap().to_str().unwrap();
+        let base = handle.get_module_base(module).unwrap();
+        let member = DataMember::<u8>::new_module_offset(handle, module, vec![0x10]);
+        assert_eq!(member.get_offset().unwrap(), base + 0x10);
+        assert_eq!(member.module_base.get(), Some(base));
+
+        // A stale base address is looked up again once reading through it fails.
+        member.module_base.set(Some(0));
+        let expected = unsafe { ((base + 0x10) as *const u8).read() };
+        assert_eq!(member.read().unwrap(), expected);
+        assert_eq!(member.module_base.get(), Some(base));
+    }
 }
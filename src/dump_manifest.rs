@@ -0,0 +1,68 @@
+/// Where each region of memory written by [`EnumerateRegions::dump_to`] landed in the output.
+///
+/// The regions are written one after the other in the order they appear in `regions`, so the
+/// first region starts at offset 0 of the output, and each following region starts where the
+/// previous one ended.
+///
+/// [`EnumerateRegions::dump_to`]: trait.EnumerateRegions.html#method.dump_to
+///
+/// With the `serde` feature enabled it implements `Serialize` and `Deserialize`, so that it can be
+/// saved alongside the output, to read it back later with a [`SnapshotSource`].
+///
+/// [`SnapshotSource`]: struct.SnapshotSource.html
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DumpManifest {
+    /// The base address and size in bytes of each region that was written.
+    pub regions: Vec<(usize, usize)>,
+}
+
+impl DumpManifest {
+    /// The total number of bytes that were written.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.regions.iter().map(|&(_, size)| size as u64).sum()
+    }
+
+    /// Returns `true` if nothing was written.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Get the address of the memory that was written at `offset` in the output, or `None` if
+    /// `offset` is past the end of the output.
+    #[must_use]
+    pub fn address_of(&self, offset: u64) -> Option<usize> {
+        let mut start = 0_u64;
+        for &(base, size) in &self.regions {
+            let end = start + size as u64;
+            if offset < end {
+                #[allow(clippy::cast_possible_truncation)]
+                return Some(base + (offset - start) as usize);
+            }
+            start = end;
+        }
+        None
+    }
+
+    /// Get the offset in the output that the memory at `addr` was written at, or `None` if `addr`
+    /// wasn't written.
+    #[must_use]
+    pub fn offset_of(&self, addr: usize) -> Option<u64> {
+        self.locate(addr).map(|(offset, _)| offset)
+    }
+
+    /// Get the offset in the output that the memory at `addr` was written at, along with how
+    /// many bytes of the region it is in were written from `addr` onwards.
+    pub(crate) fn locate(&self, addr: usize) -> Option<(u64, usize)> {
+        let mut start = 0_u64;
+        for &(base, size) in &self.regions {
+            if addr >= base && addr - base < size {
+                return Some((start + (addr - base) as u64, size - (addr - base)));
+            }
+            start += size as u64;
+        }
+        None
+    }
+}
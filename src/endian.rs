@@ -0,0 +1,130 @@
+use std::convert::TryInto;
+
+use crate::CopyAddress;
+
+/// A number that can be read from memory in a byte order other than the host's, with
+/// [`read_int_be`] or [`read_int_le`].
+///
+/// This is implemented for every integer and floating point type, and can't be implemented
+/// outside this crate.
+///
+/// [`read_int_be`]: fn.read_int_be.html
+/// [`read_int_le`]: fn.read_int_le.html
+pub trait FromBytes: sealed::Sealed + Sized + Copy {
+    /// The size of the number in bytes.
+    const SIZE: usize;
+
+    /// Create the number from its big-endian representation.
+    ///
+    /// # Panics
+    /// Panics if `bytes` is not exactly `SIZE` bytes long.
+    fn from_be_slice(bytes: &[u8]) -> Self;
+
+    /// Create the number from its little-endian representation.
+    ///
+    /// # Panics
+    /// Panics if `bytes` is not exactly `SIZE` bytes long.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+/// Keeps `FromBytes` from being implemented outside this crate, as reading a number assumes that
+/// it is no more than 16 bytes.
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_from_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl FromBytes for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn from_be_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_be_bytes(bytes.try_into().unwrap())
+                }
+
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    <$ty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_bytes!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// Read a big-endian number at `addr` from `source`, such as from an emulated big-endian target.
+///
+/// Everything else in this crate, such as [`Memory::read`], reads values in the byte order of the
+/// host, which is unchanged by this.
+///
+/// ```rust
+/// # use process_memory::{read_int_be, Pid, TryIntoProcessHandle};
+/// let bytes = [0x12_u8, 0x34, 0x56, 0x78];
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let value: u32 = read_int_be(&handle, bytes.as_ptr() as usize).unwrap();
+/// assert_eq!(value, 0x1234_5678);
+/// ```
+///
+/// # Errors
+/// Returns an error if copying memory fails.
+///
+/// [`Memory::read`]: trait.Memory.html#tymethod.read
+pub fn read_int_be<T, S>(source: &S, addr: usize) -> std::io::Result<T>
+where
+    T: FromBytes,
+    S: CopyAddress,
+{
+    read_int(source, addr, T::from_be_slice)
+}
+
+/// Read a little-endian number at `addr` from `source`, regardless of the byte order of the host.
+///
+/// # Errors
+/// Returns an error if copying memory fails.
+pub fn read_int_le<T, S>(source: &S, addr: usize) -> std::io::Result<T>
+where
+    T: FromBytes,
+    S: CopyAddress,
+{
+    read_int(source, addr, T::from_le_slice)
+}
+
+/// Read `T::SIZE` bytes at `addr` and turn them into a `T` with `convert`.
+fn read_int<T, S>(source: &S, addr: usize, convert: fn(&[u8]) -> T) -> std::io::Result<T>
+where
+    T: FromBytes,
+    S: CopyAddress,
+{
+    // Numbers are at most 16 bytes, so we never need to allocate.
+    let mut buf = [0_u8; 16];
+    let bytes = &mut buf[..T::SIZE];
+    source.copy_address(addr, bytes)?;
+    Ok(convert(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Pid, TryIntoProcessHandle};
+
+    #[test]
+    fn read_both_byte_orders() {
+        let bytes = [0x01_u8, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let addr = bytes.as_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_eq!(read_int_be::<u16, _>(&handle, addr).unwrap(), 0x0102);
+        assert_eq!(read_int_le::<u16, _>(&handle, addr).unwrap(), 0x0201);
+        assert_eq!(
+            read_int_be::<u64, _>(&handle, addr).unwrap(),
+            0x0102_0304_0506_0708
+        );
+        assert_eq!(read_int_le::<i32, _>(&handle, addr).unwrap(), 0x0403_0201);
+        assert_eq!(read_int_be::<u8, _>(&handle, addr + 7).unwrap(), 8);
+    }
+}
@@ -0,0 +1,556 @@
+use libc::{c_char, c_int, c_void, pid_t, ptrace_io_desc, PIOD_READ_D, PIOD_WRITE_D, PT_IO};
+use std::process::Child;
+
+use crate::memory_error::{classified_error, ErrorClass};
+use crate::trace::{trace_read, trace_write};
+
+use super::{
+    AccessMode, Allocate, Architecture, ControlProcess, CopyAddress, EnumerateRegions,
+    EnumerateThreads, FindModule, ProcessHandleExt, ProtectMemory, Protection, PutAddress, Region,
+    ThreadContext, ThreadInfo, TryIntoProcessHandle,
+};
+
+/// On FreeBSD a `Pid` is just a `libc::pid_t`.
+pub type Pid = pid_t;
+/// On FreeBSD a `Tid` is a `libc::lwpid_t`, the id of a kernel thread.
+pub type Tid = libc::lwpid_t;
+/// On FreeBSD a `ProcessHandle` is just a `libc::pid_t`, along with the `Architecture` of the
+/// process, as `ptrace` works on pids.
+///
+/// As a pid is just a number, a `ProcessHandle` is `Send` and `Sync`. However, as each read and
+/// write attaches to the process with `ptrace`, using it from several threads at once can fail
+/// with `EBUSY` while another thread is attached, so threads should take turns.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessHandle {
+    pid: Pid,
+    arch: Architecture,
+}
+
+/// On FreeBSD the raw form of a `ProcessHandle` is the `Pid`.
+pub type RawProcessHandle = Pid;
+
+/// On FreeBSD the registers of a thread are a `libc::reg`, as read with `ptrace(PT_GETREGS)`.
+#[cfg(target_arch = "x86_64")]
+pub type RawThreadContext = libc::reg;
+/// Registers can't be read on this architecture, so there are none.
+#[cfg(not(target_arch = "x86_64"))]
+pub type RawThreadContext = ();
+
+impl ProcessHandle {
+    /// Make a `ProcessHandle` for the process `pid`, with the `Architecture` of the host. As a
+    /// pid isn't owned by anything, this is the same as calling `try_into_process_handle` on it.
+    #[must_use]
+    pub fn from_raw_handle(pid: RawProcessHandle) -> ProcessHandle {
+        ProcessHandle {
+            pid,
+            arch: Architecture::from_native(),
+        }
+    }
+
+    /// Get the `Pid` of the process that the `ProcessHandle` refers to.
+    #[must_use]
+    pub fn into_raw_handle(self) -> RawProcessHandle {
+        self.pid
+    }
+}
+
+impl ProcessHandleExt for ProcessHandle {
+    fn check_handle(&self) -> bool {
+        self.pid != 0
+    }
+    fn null_type() -> ProcessHandle {
+        ProcessHandle {
+            pid: 0,
+            arch: Architecture::from_native(),
+        }
+    }
+    /// On FreeBSD a `ProcessHandle` is the `Pid`.
+    fn get_pid(&self) -> std::io::Result<Pid> {
+        Ok(self.pid)
+    }
+    fn set_arch(mut self, arch: Architecture) -> Self {
+        self.arch = arch;
+        self
+    }
+    fn get_arch(&self) -> Architecture {
+        self.arch
+    }
+    fn page_size(&self) -> usize {
+        page_size()
+    }
+    /// `kill` with a signal of 0 checks whether the process exists, and fails with `EPERM` for a
+    /// process we can't signal, which still exists. Zombies still exist, so they count as alive.
+    fn is_alive(&self) -> bool {
+        self.pid > 0
+            && (unsafe { libc::kill(self.pid, 0) } == 0
+                || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+    }
+    /// The path comes from the `kern.proc.pathname` sysctl.
+    fn exe_path(&self) -> std::io::Result<std::path::PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+
+        let mut path = sysctl(&[
+            libc::CTL_KERN,
+            libc::KERN_PROC,
+            libc::KERN_PROC_PATHNAME,
+            self.pid,
+        ])?;
+        if let Some(len) = path.iter().position(|&byte| byte == 0) {
+            path.truncate(len);
+        }
+        Ok(std::ffi::OsString::from_vec(path).into())
+    }
+    /// The arguments come from the `kern.proc.args` sysctl, which reflects any changes the
+    /// process has made to them.
+    fn cmdline(&self) -> std::io::Result<Vec<String>> {
+        sysctl(&[
+            libc::CTL_KERN,
+            libc::KERN_PROC,
+            libc::KERN_PROC_ARGS,
+            self.pid,
+        ])
+        .map(|args| crate::split_args(&args))
+    }
+}
+
+/// On FreeBSD there is nothing to open, so any `Pid` can be turned into a `ProcessHandle`.
+impl TryIntoProcessHandle for Pid {
+    fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
+        Ok(ProcessHandle {
+            pid: *self,
+            arch: Architecture::from_native(),
+        })
+    }
+}
+
+/// Access isn't granted per handle on FreeBSD, so `access` is ignored.
+pub(crate) fn open_process(pid: Pid, _access: AccessMode) -> std::io::Result<ProcessHandle> {
+    pid.try_into_process_handle()
+}
+
+/// The size of a page of memory on this system, from `sysconf`.
+pub(crate) fn page_size() -> usize {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    size
+}
+
+/// A `Child` always has a pid, which is all we need on FreeBSD.
+impl TryIntoProcessHandle for Child {
+    fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
+        #[allow(clippy::cast_possible_wrap)]
+        Pid::try_into_process_handle(&(self.id() as Pid))
+    }
+}
+
+/// Memory is read with `ptrace(PT_IO)`, which only works on a process we are tracing, so the
+/// process is attached to (and stopped) for the duration of each read, unless it is already being
+/// traced.
+impl CopyAddress for ProcessHandle {
+    fn get_pointer_width(&self) -> Architecture {
+        self.arch
+    }
+
+    fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+        let result = match self.copy_address_partial(addr, buf) {
+            Ok(read) if read == buf.len() => Ok(()),
+            Ok(read) => Err(classified_error(
+                std::io::ErrorKind::InvalidInput,
+                ErrorClass::AddressUnreadable,
+                format!(
+                    "Unable to read {} bytes at {:#x} (bad address)",
+                    buf.len() - read,
+                    addr + read
+                ),
+            )),
+            Err(err) => Err(err),
+        };
+        trace_read(self, addr, buf.len(), &result);
+        result
+    }
+
+    /// `PT_IO` reports how many bytes it transferred, and stops early when it reaches memory it
+    /// can't read.
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.copy_address_uninit(addr, crate::as_uninit_mut(buf))
+    }
+
+    /// `PT_IO` reads straight into `buf`, so it doesn't need to be zeroed first.
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        let mut desc = ptrace_io_desc {
+            piod_op: PIOD_READ_D,
+            piod_offs: addr as *mut c_void,
+            piod_addr: buf.as_mut_ptr().cast(),
+            piod_len: buf.len(),
+        };
+        while_attached(self.pid, || ptrace_io(self.pid, &mut desc))
+    }
+}
+
+/// Memory is written with `ptrace(PT_IO)`, attaching to the process in the same way as reading.
+impl PutAddress for ProcessHandle {
+    fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        let mut desc = ptrace_io_desc {
+            piod_op: PIOD_WRITE_D,
+            piod_offs: addr as *mut c_void,
+            piod_addr: buf.as_ptr() as *mut c_void,
+            piod_len: buf.len(),
+        };
+        let result = match while_attached(self.pid, || ptrace_io(self.pid, &mut desc)) {
+            Ok(written) if written == buf.len() => Ok(()),
+            Ok(written) => Err(classified_error(
+                std::io::ErrorKind::InvalidInput,
+                ErrorClass::AddressUnreadable,
+                format!(
+                    "Unable to write {} bytes at {:#x} (bad address)",
+                    buf.len() - written,
+                    addr + written
+                ),
+            )),
+            Err(err) => Err(err),
+        };
+        trace_write(self, addr, buf.len(), &result);
+        result
+    }
+}
+
+/// Perform the transfer described by `desc` with `PT_IO`, returning the number of bytes
+/// transferred.
+fn ptrace_io(pid: Pid, desc: &mut ptrace_io_desc) -> std::io::Result<usize> {
+    let result = unsafe { libc::ptrace(PT_IO, pid, std::ptr::from_mut(desc).cast::<c_char>(), 0) };
+    if result == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(desc.piod_len)
+    }
+}
+
+/// Attach to `pid` with `PT_ATTACH`, wait for it to stop, call `f` and detach again. If `pid` is
+/// already being traced (by us or anyone else), `f` is just called directly.
+fn while_attached<R>(pid: Pid, f: impl FnOnce() -> std::io::Result<R>) -> std::io::Result<R> {
+    if unsafe { libc::ptrace(libc::PT_ATTACH, pid, std::ptr::null_mut(), 0) } == -1 {
+        let err = std::io::Error::last_os_error();
+        return if err.raw_os_error() == Some(libc::EBUSY) {
+            f()
+        } else {
+            Err(err)
+        };
+    }
+    let mut status: c_int = 0;
+    if unsafe { libc::waitpid(pid, &raw mut status, 0) } == -1 {
+        let err = std::io::Error::last_os_error();
+        detach(pid);
+        return Err(err);
+    }
+    let result = f();
+    detach(pid);
+    result
+}
+
+/// Detach from `pid` with `PT_DETACH`, letting it continue from where it stopped.
+fn detach(pid: Pid) {
+    // An address of 1 tells `PT_DETACH` to continue from where the process stopped.
+    unsafe {
+        let _ = libc::ptrace(libc::PT_DETACH, pid, 1 as *mut c_char, 0);
+    }
+}
+
+/// Read the value of the sysctl `mib` as bytes.
+fn sysctl(mib: &[c_int]) -> std::io::Result<Vec<u8>> {
+    let mut size: libc::size_t = 0;
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_ptr(),
+            mib.len() as _,
+            std::ptr::null_mut(),
+            &raw mut size,
+            std::ptr::null(),
+            0,
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut value = vec![0_u8; size];
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_ptr(),
+            mib.len() as _,
+            value.as_mut_ptr().cast(),
+            &raw mut size,
+            std::ptr::null(),
+            0,
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    value.truncate(size);
+    Ok(value)
+}
+
+/// Find the pid of every process called `name`, by listing every process with the
+/// `kern.proc.proc` sysctl and comparing their `ki_comm`.
+pub(crate) fn processes_named(name: &str) -> std::io::Result<Vec<Pid>> {
+    let mib = [libc::CTL_KERN, libc::KERN_PROC, libc::KERN_PROC_PROC, 0];
+    let mut size: libc::size_t = 0;
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_ptr(),
+            mib.len() as _,
+            std::ptr::null_mut(),
+            &raw mut size,
+            std::ptr::null(),
+            0,
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Leave room for processes that start between the two calls.
+    let mut procs =
+        Vec::<libc::kinfo_proc>::with_capacity(size / std::mem::size_of::<libc::kinfo_proc>() + 16);
+    size = procs.capacity() * std::mem::size_of::<libc::kinfo_proc>();
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        libc::sysctl(
+            mib.as_ptr(),
+            mib.len() as _,
+            procs.as_mut_ptr().cast(),
+            &raw mut size,
+            std::ptr::null(),
+            0,
+        )
+    };
+    if result == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    unsafe { procs.set_len(size / std::mem::size_of::<libc::kinfo_proc>()) };
+    Ok(procs
+        .iter()
+        .filter(|proc| {
+            let comm = unsafe { std::ffi::CStr::from_ptr(proc.ki_comm.as_ptr()) };
+            crate::comm_matches(&comm.to_string_lossy(), name, libc::COMMLEN)
+        })
+        .map(|proc| proc.ki_pid)
+        .collect())
+}
+
+/// FreeBSD has no way to call `mprotect` on behalf of another process without using `ptrace` to
+/// make the process run the system call itself, so this always returns an `Unsupported` error.
+impl ProtectMemory for ProcessHandle {
+    fn set_protection(
+        &self,
+        _addr: usize,
+        _size: usize,
+        _prot: Protection,
+    ) -> std::io::Result<Protection> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Changing the protection of another process's memory is not supported on FreeBSD",
+        ))
+    }
+}
+
+/// Like changing memory protection, FreeBSD has no way to `mmap` memory into another process
+/// without using `ptrace` to make the process do it itself, so this always returns an
+/// `Unsupported` error.
+impl Allocate for ProcessHandle {
+    fn allocate(&self, _size: usize, _prot: Protection) -> std::io::Result<usize> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Allocating memory in another process is not supported on FreeBSD",
+        ))
+    }
+
+    fn free(&self, _addr: usize, _size: usize) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Freeing memory in another process is not supported on FreeBSD",
+        ))
+    }
+}
+
+/// On FreeBSD the memory map of a process is available from `kinfo_getvmmap`. As that returns
+/// every region at once, `regions_iter` just iterates over the result of `regions`.
+impl EnumerateRegions for ProcessHandle {
+    fn regions(&self) -> std::io::Result<Vec<Region>> {
+        Ok(read_vmmap(self.pid)?
+            .into_iter()
+            .map(|(region, _)| region)
+            .collect())
+    }
+
+    /// Only the regions that map files are labelled, with the file's path, so there are no special
+    /// names such as `[heap]`.
+    fn find_region_by_name(&self, name: &str) -> std::io::Result<Option<Region>> {
+        crate::region_named(read_vmmap(self.pid)?.into_iter().map(Ok), name)
+    }
+}
+
+/// Modules are found by looking for the mappings whose path matches the module's file name, or
+/// its file stem.
+impl FindModule for ProcessHandle {
+    fn get_module_base(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self.pid, name).map(|(base, _)| base)
+    }
+
+    fn get_module_size(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self.pid, name).map(|(_, size)| size)
+    }
+
+    /// The executable is the file given by `KERN_PROC_PATHNAME`, and its base address is the
+    /// start of its first mapping.
+    fn main_module_base(&self) -> std::io::Result<usize> {
+        let exe = self.exe_path()?;
+        read_vmmap(self.pid)?
+            .into_iter()
+            .filter(|(_, path)| std::path::Path::new(path) == exe)
+            .map(|(region, _)| region.base)
+            .min()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("The executable of process {} isn't mapped", self.pid),
+                )
+            })
+    }
+
+    /// Only mappings of files count as modules, so this returns `None` for anonymous memory.
+    fn to_module_relative(&self, addr: usize) -> std::io::Result<Option<(String, usize)>> {
+        Ok(crate::module_relative(&read_vmmap(self.pid)?, addr))
+    }
+}
+
+/// The process is stopped and continued with `SIGSTOP` and `SIGCONT`, just like job control in a
+/// shell.
+impl ControlProcess for ProcessHandle {
+    fn suspend(&self) -> std::io::Result<()> {
+        send_signal(self.pid, libc::SIGSTOP)
+    }
+
+    fn resume(&self) -> std::io::Result<()> {
+        send_signal(self.pid, libc::SIGCONT)
+    }
+}
+
+/// FreeBSD can only list the threads of a process with `ptrace(PT_GETLWPLIST)`, which would stop
+/// the process to do so, so this always returns an `Unsupported` error.
+impl EnumerateThreads for ProcessHandle {
+    fn threads(&self) -> std::io::Result<Vec<ThreadInfo>> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Listing the threads of another process is not supported on FreeBSD",
+        ))
+    }
+
+    /// The registers are read with `ptrace(PT_GETREGS)`, which accepts the id of a thread in
+    /// place of a pid, while attached to the process.
+    fn thread_context(&self, tid: Tid) -> std::io::Result<ThreadContext> {
+        while_attached(self.pid, || thread_registers(tid))
+    }
+}
+
+/// Read the registers of the thread `tid`, whose process we must be attached to.
+#[cfg(target_arch = "x86_64")]
+fn thread_registers(tid: Tid) -> std::io::Result<ThreadContext> {
+    let mut regs: libc::reg = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ptrace(libc::PT_GETREGS, tid, (&raw mut regs).cast::<c_char>(), 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok(ThreadContext {
+        instruction_pointer: regs.r_rip as usize,
+        stack_pointer: regs.r_rsp as usize,
+        raw: regs,
+    })
+}
+
+/// Registers can't be read on this architecture.
+#[cfg(not(target_arch = "x86_64"))]
+fn thread_registers(_tid: Tid) -> std::io::Result<ThreadContext> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Reading the registers of a thread isn't supported on this architecture",
+    ))
+}
+
+/// Send `signal` to `pid` with `kill`.
+fn send_signal(pid: Pid, signal: c_int) -> std::io::Result<()> {
+    if unsafe { libc::kill(pid, signal) } == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Find the base address and size of the module `name` from all of the mappings of its file. The
+/// size covers everything from the first mapping to the end of the last.
+fn find_module(pid: Pid, name: &str) -> std::io::Result<(usize, usize)> {
+    let (base, end) = read_vmmap(pid)?
+        .into_iter()
+        .filter(|(_, path)| crate::module_name_matches(path, name))
+        .fold(None, |bounds, (region, _)| match bounds {
+            None => Some((region.base, region.end())),
+            Some((base, end)) => Some((region.base.min(base), region.end().max(end))),
+        })
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No module named {name:?} is mapped into process {pid}"),
+            )
+        })?;
+    Ok((base, end - base))
+}
+
+/// Get every mapping of `pid` from `kinfo_getvmmap`, along with the path of the file it maps
+/// (which is empty for anonymous mappings).
+fn read_vmmap(pid: Pid) -> std::io::Result<Vec<(Region, String)>> {
+    let mut count: c_int = 0;
+    let entries = unsafe { libc::kinfo_getvmmap(pid, &raw mut count) };
+    if entries.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::cast_sign_loss)]
+    let vmmap = unsafe { std::slice::from_raw_parts(entries, count as usize) }
+        .iter()
+        .map(|entry| {
+            let mut protection = Protection::empty();
+            protection.set(
+                Protection::READ,
+                entry.kve_protection & libc::KVME_PROT_READ != 0,
+            );
+            protection.set(
+                Protection::WRITE,
+                entry.kve_protection & libc::KVME_PROT_WRITE != 0,
+            );
+            protection.set(
+                Protection::EXECUTE,
+                entry.kve_protection & libc::KVME_PROT_EXEC != 0,
+            );
+            // `kve_path` is a `PATH_MAX` buffer, which `libc` splits into chunks.
+            let path = unsafe { std::ffi::CStr::from_ptr(entry.kve_path.as_ptr().cast()) };
+            #[allow(clippy::cast_possible_truncation)]
+            let region = Region {
+                base: entry.kve_start as usize,
+                size: (entry.kve_end - entry.kve_start) as usize,
+                protection,
+                is_private: entry.kve_flags & libc::KVME_FLAG_COW != 0
+                    || matches!(
+                        entry.kve_type,
+                        libc::KVME_TYPE_DEFAULT | libc::KVME_TYPE_SWAP
+                    ),
+            };
+            (region, path.to_string_lossy().into_owned())
+        })
+        .collect();
+    unsafe { libc::free(entries.cast()) };
+    Ok(vmmap)
+}
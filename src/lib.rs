@@ -72,15 +72,49 @@
 #![deny(unused)]
 #![deny(clippy::pedantic)]
 
+use std::ffi::CString;
+
+mod access_mode;
 mod architecture;
+mod array_member;
+#[cfg(feature = "async")]
+pub mod async_memory;
+mod auditing_handle;
 mod data_member;
+mod dump_manifest;
+mod endian;
 mod local_member;
+mod memory_error;
+mod memory_reader;
+mod pointer_path;
+mod protection;
+mod read_struct;
+mod region;
+pub mod scan;
+mod snapshot_source;
+mod thread_context;
+mod thread_info;
+mod trace;
 
+pub use access_mode::AccessMode;
 pub use architecture::Architecture;
+pub use array_member::ArrayMember;
+pub use auditing_handle::AuditingHandle;
 pub use data_member::DataMember;
+pub use dump_manifest::DumpManifest;
+pub use endian::{read_int_be, read_int_le, FromBytes};
 pub use local_member::LocalMember;
+pub use memory_error::{ChainError, MemoryError};
+pub use memory_reader::ProcessMemoryReader;
+pub use pointer_path::PointerPath;
+pub use protection::Protection;
+pub use read_struct::ReadStruct;
+pub use region::Region;
+pub use snapshot_source::{diff_snapshots, SnapshotSource};
+pub use thread_context::ThreadContext;
+pub use thread_info::ThreadInfo;
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[path = "linux.rs"]
 mod platform;
 #[cfg(target_os = "macos")]
@@ -89,6 +123,9 @@ mod platform;
 #[cfg(windows)]
 #[path = "windows.rs"]
 mod platform;
+#[cfg(target_os = "freebsd")]
+#[path = "freebsd.rs"]
+mod platform;
 
 /// A trait that defines that it is possible to copy some memory from something represented by a
 /// type into a buffer.
@@ -99,26 +136,239 @@ pub trait CopyAddress {
     /// `std::io::Error` if an error occurs copying the address.
     fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()>;
 
+    /// Copy as much of an address as possible into a user-defined buffer, returning the number of
+    /// bytes that were copied.
+    ///
+    /// Unlike [`copy_address`], reaching memory that can't be read part of the way through the
+    /// buffer is not an error; the bytes before it are still copied into the start of `buf`, and
+    /// the rest of `buf` is left untouched. By default this calls [`copy_address`], so it will
+    /// either copy the whole buffer or fail.
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the address, such as when not even the first
+    /// byte can be read.
+    ///
+    /// [`copy_address`]: trait.CopyAddress.html#tymethod.copy_address
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.copy_address(addr, buf)?;
+        Ok(buf.len())
+    }
+
+    /// Copy as much of an address as possible into a buffer that doesn't need to be initialised
+    /// first, returning the number of bytes that were copied. This saves zeroing a buffer that is
+    /// about to be overwritten, such as the spare capacity of a `Vec`.
+    ///
+    /// When this returns `Ok(n)`, the first `n` bytes of `buf` have been initialised with the
+    /// memory at `addr`, and nothing may be assumed about the rest of `buf`, which may or may not
+    /// have been written to. Reading stops early in the same cases as [`copy_address_partial`].
+    /// Use [`copy_address_into_uninit`] to get the initialised bytes without any `unsafe` code.
+    ///
+    /// By default this zeroes `buf` and then calls [`copy_address_partial`]; the platform
+    /// handles override it to read straight into `buf`. An implementation that overrides this
+    /// must initialise the first `n` bytes of `buf` before returning `Ok(n)`, as callers rely on
+    /// that to treat them as initialised.
+    ///
+    /// ```rust
+    /// # use process_memory::{CopyAddress, Pid, TryIntoProcessHandle};
+    /// let source = [7_u8; 64];
+    /// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+    /// let mut buf = Vec::with_capacity(source.len());
+    /// let read = handle
+    ///     .copy_address_uninit(source.as_ptr() as usize, buf.spare_capacity_mut())
+    ///     .unwrap();
+    /// // Safety: `copy_address_uninit` has initialised the first `read` bytes.
+    /// unsafe { buf.set_len(read) };
+    /// assert_eq!(buf, source);
+    /// ```
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the address, such as when not even the first
+    /// byte can be read.
+    ///
+    /// [`copy_address_partial`]: trait.CopyAddress.html#method.copy_address_partial
+    /// [`copy_address_into_uninit`]: trait.CopyAddress.html#method.copy_address_into_uninit
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        self.copy_address_partial(addr, init_zeroed(buf))
+    }
+
+    /// Copy as much of an address as possible into a buffer that doesn't need to be initialised
+    /// first, with [`copy_address_uninit`], returning the part of `buf` that was copied into.
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the address, such as when not even the first
+    /// byte can be read.
+    ///
+    /// [`copy_address_uninit`]: trait.CopyAddress.html#method.copy_address_uninit
+    fn copy_address_into_uninit<'a>(
+        &self,
+        addr: usize,
+        buf: &'a mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<&'a mut [u8]> {
+        let read = self.copy_address_uninit(addr, buf)?.min(buf.len());
+        // `copy_address_uninit` initialised the first `read` bytes.
+        Ok(unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), read) })
+    }
+
+    /// Copy an address into a user-defined buffer, stopping at the end of the region of memory
+    /// that contains `addr`, and returning the number of bytes that were copied.
+    ///
+    /// This lets speculative reads, such as while scanning, be made without looking up the region
+    /// first to avoid reading past its end into memory that isn't mapped. The region is found with
+    /// [`EnumerateRegions::region_containing`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if the regions can't be retrieved or copying the address fails, or an error
+    /// with a `std::io::ErrorKind` of `NotFound` if `addr` isn't in any mapped region.
+    ///
+    /// [`EnumerateRegions::region_containing`]: trait.EnumerateRegions.html#method.region_containing
+    fn copy_address_clamped(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize>
+    where
+        Self: EnumerateRegions,
+    {
+        let region = self.region_containing(addr)?.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No region of memory contains {addr:#x}"),
+            )
+        })?;
+        let len = buf.len().min(region.end() - addr);
+        self.copy_address(addr, &mut buf[..len])?;
+        Ok(len)
+    }
+
+    /// Read the whole of `region`, such as one returned by [`EnumerateRegions::regions`], into a
+    /// new `Vec`.
+    ///
+    /// The region is read with [`copy_address_partial`], so if some of it has been unmapped since
+    /// it was listed, everything up to the first byte that can't be read is still returned, and
+    /// the `Vec` is shorter than `region.size`.
+    ///
+    /// # Errors
+    /// `std::io::Error` if not even the first byte of the region can be read.
+    ///
+    /// [`EnumerateRegions::regions`]: trait.EnumerateRegions.html#tymethod.regions
+    /// [`copy_address_partial`]: trait.CopyAddress.html#method.copy_address_partial
+    fn read_region(&self, region: &Region) -> std::io::Result<Vec<u8>> {
+        let mut buffer = vec![0_u8; region.size];
+        let len = self.copy_address_partial(region.base, &mut buffer)?;
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+
+    /// Copy a batch of addresses into user-defined buffers, where each request is an address and
+    /// the buffer to copy it into.
+    ///
+    /// By default this simply calls [`copy_address`] for each request in order, but platforms
+    /// that support scatter/gather reads (such as Linux with `process_vm_readv`) override it to
+    /// read every request with a single system call.
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying any of the addresses. Reading stops at the
+    /// first request that fails, so the buffers of any requests before it will already have been
+    /// filled, while the buffers of the failing request and those after it are left in an
+    /// unspecified state.
+    ///
+    /// [`copy_address`]: trait.CopyAddress.html#tymethod.copy_address
+    fn copy_addresses(&self, requests: &mut [(usize, &mut [u8])]) -> std::io::Result<()> {
+        for (addr, buf) in requests.iter_mut() {
+            self.copy_address(*addr, buf)?;
+        }
+        Ok(())
+    }
+
+    /// Get the `Architecture` of the pointers in the memory being copied from, which is used to
+    /// read pointers while following a chain of offsets.
+    ///
+    /// By default this is the architecture of the host process.
+    fn get_pointer_width(&self) -> Architecture {
+        Architecture::from_native()
+    }
+
     /// Get the actual memory location from a set of offsets.
     ///
     /// If [`copy_address`] is already defined, then we can provide a standard implementation that
     /// will work across all operating systems.
     ///
+    /// Every offset but the last is added to the current address, and the pointer at that address
+    /// is read to become the new current address. The last offset is only added to the current
+    /// address, without reading from it.
+    ///
     /// # Errors
-    /// `std::io::Error` if an error occurs copying the address.
+    /// `std::io::Error` if an error occurs copying the address, an error with a
+    /// `std::io::ErrorKind` of `NotFound` if one of the pointers read is null (for instance if
+    /// the object it points to hasn't been created yet), or an error with a `std::io::ErrorKind`
+    /// of `InvalidInput` if `offsets` is empty or adding one of them overflows the address space.
     fn get_offset(&self, offsets: &[usize], arch: Architecture) -> std::io::Result<usize> {
-        // Look ma! No unsafes!
-        let mut offset: usize = 0;
-        let noffsets: usize = offsets.len();
-        let mut copy = vec![0_u8; arch as usize];
-        for next_offset in offsets.iter().take(noffsets - 1) {
-            offset += next_offset;
-            self.copy_address(offset, &mut copy)?;
-            offset = arch.pointer_from_ne_bytes(&copy);
+        follow_offsets(self, offsets, |_| arch)
+    }
+
+    /// Read the pointer at `addr`, with the width given by [`get_pointer_width`], and return the
+    /// address it points to. This is a single step of [`get_offset`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the address, or an error with a
+    /// `std::io::ErrorKind` of `NotFound`, classified as `MemoryError::NullInChain`, if the
+    /// pointer is null.
+    ///
+    /// [`get_pointer_width`]: trait.CopyAddress.html#method.get_pointer_width
+    /// [`get_offset`]: trait.CopyAddress.html#method.get_offset
+    fn deref(&self, addr: usize) -> std::io::Result<usize> {
+        let arch = self.get_pointer_width();
+        let mut bytes = vec![0; arch as usize];
+        self.copy_address(addr, &mut bytes)?;
+        match arch.pointer_from_ne_bytes(&bytes) {
+            0 => Err(memory_error::classified_error(
+                std::io::ErrorKind::NotFound,
+                memory_error::ErrorClass::NullInChain,
+                format!("The pointer at {addr:#x} is null"),
+            )),
+            pointer => Ok(pointer),
         }
+    }
 
-        offset += offsets[noffsets - 1];
-        Ok(offset)
+    /// Resolve the target of an x86-64 instruction with a RIP-relative operand, such as
+    /// `lea rax, [rip + 0x1234]`, which is commonly found by signature scanning. The signed 32-bit
+    /// displacement at `instr_addr + disp_offset` is read and added to the address of the next
+    /// instruction, `instr_addr + instr_len`.
+    ///
+    /// ```rust
+    /// # use process_memory::CopyAddress;
+    /// # fn example(handle: &impl CopyAddress, instr_addr: usize) -> std::io::Result<()> {
+    /// // 48 8D 05 xx xx xx xx is `lea rax, [rip + disp32]`, 7 bytes long with the displacement
+    /// // starting at its fourth byte.
+    /// let target = handle.resolve_rip_relative(instr_addr, 3, 7)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the displacement, or an error with a
+    /// `std::io::ErrorKind` of `InvalidInput` if the target would be outside of the address space.
+    fn resolve_rip_relative(
+        &self,
+        instr_addr: usize,
+        disp_offset: usize,
+        instr_len: usize,
+    ) -> std::io::Result<usize> {
+        use std::convert::TryFrom;
+
+        let mut disp = [0_u8; 4];
+        self.copy_address(add_offset(instr_addr, disp_offset, 0)?, &mut disp)?;
+        let disp = i32::from_le_bytes(disp);
+        let next = add_offset(instr_addr, instr_len, 0)?;
+        isize::try_from(disp)
+            .ok()
+            .and_then(|disp| next.checked_add_signed(disp))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{next:#x} {disp:+#x} is outside of the address space"),
+                )
+            })
     }
 }
 
@@ -130,23 +380,482 @@ pub trait PutAddress {
     /// # Errors
     /// `std::io::Error` if an error occurs copying the address.
     fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()>;
+
+    /// Put a batch of user-defined buffers into memory, where each write is an address and the
+    /// buffer to put there.
+    ///
+    /// By default this simply calls [`put_address`] for each write in order, but platforms that
+    /// support scatter/gather writes (such as Linux with `process_vm_writev`) override it to
+    /// perform every write with a single system call.
+    ///
+    /// The writes are not atomic: the target can observe some of them before others have landed.
+    /// Callers that need the target to see all of the writes at once should suspend it first, for
+    /// instance with [`ControlProcess::suspended`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs putting any of the buffers. Writing stops at the first
+    /// write that fails, so the writes before it will already have landed, the failing write may
+    /// have partially landed, and the writes after it won't have been attempted.
+    ///
+    /// [`put_address`]: trait.PutAddress.html#tymethod.put_address
+    /// [`ControlProcess::suspended`]: trait.ControlProcess.html#method.suspended
+    fn put_addresses(&self, writes: &[(usize, &[u8])]) -> std::io::Result<()> {
+        for (addr, buf) in writes {
+            self.put_address(*addr, buf)?;
+        }
+        Ok(())
+    }
+}
+
+/// A trait that defines that it is possible to change the access protection of memory in something
+/// represented by a type.
+pub trait ProtectMemory {
+    /// Set the protection of the `size` bytes of memory starting at `addr` to `prot`, returning
+    /// the protection the memory had beforehand so that it can later be restored.
+    ///
+    /// If the memory spans several regions with different protections, the returned protection is
+    /// that of the region containing `addr`.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the protection can't be changed, or if the platform doesn't support
+    /// changing the protection of another process's memory.
+    fn set_protection(
+        &self,
+        addr: usize,
+        size: usize,
+        prot: Protection,
+    ) -> std::io::Result<Protection>;
+
+    /// Write `buf` to `addr` regardless of the protection of the memory there, such as to patch
+    /// code. The memory is made readable, writable and executable, written to, and then given back
+    /// the protection it had before. The old protection is restored even if the write fails.
+    ///
+    /// As with [`ProtectMemory::set_protection`], if `buf` spans several regions with different
+    /// protections, they are all given the protection of the region containing `addr` afterwards.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the protection can't be changed or restored, or the write fails.
+    ///
+    /// [`ProtectMemory::set_protection`]: trait.ProtectMemory.html#tymethod.set_protection
+    fn write_protected(&self, addr: usize, buf: &[u8]) -> std::io::Result<()>
+    where
+        Self: PutAddress,
+    {
+        /// Restores the old protection when dropped, which only happens if the write fails.
+        struct RestoreOnDrop<'a, T: ProtectMemory + ?Sized> {
+            target: &'a T,
+            addr: usize,
+            size: usize,
+            prot: Protection,
+        }
+        impl<T: ProtectMemory + ?Sized> Drop for RestoreOnDrop<'_, T> {
+            fn drop(&mut self) {
+                let _ = self.target.set_protection(self.addr, self.size, self.prot);
+            }
+        }
+
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let old = self.set_protection(
+            addr,
+            buf.len(),
+            Protection::READ | Protection::WRITE | Protection::EXECUTE,
+        )?;
+        let guard = RestoreOnDrop {
+            target: self,
+            addr,
+            size: buf.len(),
+            prot: old,
+        };
+        self.put_address(addr, buf)?;
+        std::mem::forget(guard);
+        let _ = self.set_protection(addr, buf.len(), old)?;
+        Ok(())
+    }
+}
+
+/// A trait that defines that it is possible to allocate and free memory in something represented
+/// by a type.
+pub trait Allocate {
+    /// Allocate at least `size` bytes of memory with the protection `prot`, returning the address
+    /// of the start of the allocation. The allocation is rounded up to a whole number of pages.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory can't be allocated, or if the platform doesn't support
+    /// allocating memory in another process.
+    fn allocate(&self, size: usize, prot: Protection) -> std::io::Result<usize>;
+
+    /// Free memory previously returned by [`allocate`]. `size` must be the same size that was
+    /// passed to [`allocate`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory can't be freed.
+    ///
+    /// [`allocate`]: trait.Allocate.html#tymethod.allocate
+    fn free(&self, addr: usize, size: usize) -> std::io::Result<()>;
+}
+
+/// A trait that defines that it is possible to list the regions of memory that are mapped into
+/// something represented by a type.
+pub trait EnumerateRegions {
+    /// Get every region of memory that is currently mapped, in ascending order of address.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory map can't be retrieved.
+    fn regions(&self) -> std::io::Result<Vec<Region>>;
+
+    /// Iterate over every region of memory that is currently mapped, in ascending order of
+    /// address, without collecting them all first.
+    ///
+    /// Implementations may query the regions lazily as the iterator is advanced, so that the
+    /// first region is available straight away. Each item is a `std::io::Result`, so a region
+    /// that can't be queried or parsed can be skipped while still carrying on to the next one.
+    /// As the memory map may change while it is being iterated over, the regions aren't
+    /// guaranteed to form a consistent snapshot.
+    ///
+    /// By default this just iterates over the result of [`EnumerateRegions::regions`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory map can't be retrieved at all.
+    ///
+    /// [`EnumerateRegions::regions`]: trait.EnumerateRegions.html#tymethod.regions
+    fn regions_iter(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<Region>> + '_> {
+        Ok(self.regions()?.into_iter().map(Ok))
+    }
+
+    /// Get the region of memory that contains `addr`, or `None` if `addr` isn't mapped.
+    ///
+    /// By default this searches through [`EnumerateRegions::regions_iter`], but platforms that
+    /// can look up a single region directly (such as Windows with `VirtualQueryEx`) override it
+    /// to do so.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory map can't be retrieved.
+    ///
+    /// [`EnumerateRegions::regions_iter`]: trait.EnumerateRegions.html#method.regions_iter
+    fn region_containing(&self, addr: usize) -> std::io::Result<Option<Region>> {
+        for region in self.regions_iter()? {
+            let region = region?;
+            if region.contains(addr) {
+                return Ok(Some(region));
+            } else if region.base > addr {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the first region of memory labelled `name`, or `None` if no region is, such as to scope
+    /// a scan to the heap alone.
+    ///
+    /// Where the memory map labels regions (as `/proc/<pid>/maps` does on Linux), `name` is
+    /// compared to the whole label, so the special names `[heap]`, `[stack]` and `[vdso]` can be
+    /// used, and to the file name and file stem of mapped files, so `libc.so.6` finds the first
+    /// mapping of libc. Where it doesn't, platforms approximate this with the first region of the
+    /// module called `name`. By default no region has a name, and this returns `None`.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory map can't be retrieved.
+    fn find_region_by_name(&self, name: &str) -> std::io::Result<Option<Region>> {
+        let _ = name;
+        Ok(None)
+    }
+
+    /// Write the contents of every readable region of memory to `out`, one after the other, such
+    /// as to save a snapshot of the process to a file for offline analysis. The returned
+    /// [`DumpManifest`] records which regions were written, so that offsets in the output can be
+    /// mapped back to addresses.
+    ///
+    /// Regions are read a chunk at a time, so memory is never held for more than one chunk.
+    /// Regions that can't be read are skipped, and a region that stops being readable partway
+    /// through (for instance because it was unmapped) is cut short at the last chunk that could
+    /// be read, so the manifest always describes exactly what was written.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the memory map can't be retrieved, or writing to `out` fails.
+    ///
+    /// [`DumpManifest`]: struct.DumpManifest.html
+    fn dump_to(&self, out: &mut impl std::io::Write) -> std::io::Result<DumpManifest>
+    where
+        Self: CopyAddress,
+    {
+        /// The size of the chunks that regions are read and written in.
+        const CHUNK_SIZE: usize = 0x10_0000;
+
+        let mut manifest = DumpManifest::default();
+        let mut buffer = Vec::new();
+        for region in self.regions_iter()? {
+            let Ok(region) = region else { continue };
+            if !region.protection.contains(Protection::READ) {
+                continue;
+            }
+            let mut written = 0;
+            while written < region.size {
+                let len = CHUNK_SIZE.min(region.size - written);
+                if copy_address_buf(region.base + written, len, self, &mut buffer).is_err() {
+                    break;
+                }
+                out.write_all(&buffer)?;
+                written += len;
+            }
+            if written > 0 {
+                manifest.regions.push((region.base, written));
+            }
+        }
+        Ok(manifest)
+    }
 }
 
+/// A trait that defines that it is possible to look up the modules (the executable and the shared
+/// libraries it has loaded) of something represented by a type.
+///
+/// Module names are matched case-insensitively on Windows, and case-sensitively elsewhere. On other
+/// platforms a module may be named by either its file name (`libGame.so`) or its file stem
+/// (`libGame`).
+pub trait FindModule {
+    /// Get the address that the module `name` is loaded at.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the modules can't be retrieved, or an error with a
+    /// `std::io::ErrorKind` of `NotFound` if no module is called `name`.
+    fn get_module_base(&self, name: &str) -> std::io::Result<usize>;
+
+    /// Get the size in bytes of the module `name` in memory.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the modules can't be retrieved, or an error with a
+    /// `std::io::ErrorKind` of `NotFound` if no module is called `name`.
+    fn get_module_size(&self, name: &str) -> std::io::Result<usize>;
+
+    /// Get the address that the executable of the process (rather than any of the shared
+    /// libraries it has loaded) is loaded at, without needing to know what it is called.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the modules can't be retrieved, or an error with a
+    /// `std::io::ErrorKind` of `NotFound` if the executable can't be found among them.
+    fn main_module_base(&self) -> std::io::Result<usize>;
+
+    /// Find the module that contains `addr`, returning its name along with how far `addr` is
+    /// from the base of the module. Unlike an absolute address, this stays the same each time the
+    /// process is run, even when the module is loaded somewhere else.
+    ///
+    /// Returns `None` if `addr` isn't inside a module, such as if it is on the heap or stack.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the modules can't be retrieved.
+    fn to_module_relative(&self, addr: usize) -> std::io::Result<Option<(String, usize)>>;
+
+    /// Turn an address relative to the base of the module `module`, such as one returned by
+    /// [`to_module_relative`], back into an absolute address.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the modules can't be retrieved, an error with a
+    /// `std::io::ErrorKind` of `NotFound` if no module is called `module`, or one of
+    /// `InvalidInput` if `offset` takes the address past the end of the address space.
+    ///
+    /// [`to_module_relative`]: trait.FindModule.html#tymethod.to_module_relative
+    #[allow(clippy::wrong_self_convention)]
+    fn from_module_relative(&self, module: &str, offset: usize) -> std::io::Result<usize> {
+        add_offset(self.get_module_base(module)?, offset, 0)
+    }
+
+    /// Wait for the module `name` to be loaded, such as when attaching to a process while it is
+    /// still starting up, returning its base address once it is. [`get_module_base`] is polled
+    /// every 50 milliseconds until it finds the module or `timeout` has elapsed.
+    ///
+    /// # Errors
+    /// An error with a `std::io::ErrorKind` of `TimedOut` if the module still hasn't been loaded
+    /// after `timeout`, or any error from [`get_module_base`] other than the module not being
+    /// found, such as the process having exited, straight away.
+    ///
+    /// [`get_module_base`]: trait.FindModule.html#tymethod.get_module_base
+    fn wait_for_module(&self, name: &str, timeout: std::time::Duration) -> std::io::Result<usize> {
+        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+        let start = std::time::Instant::now();
+        loop {
+            match self.get_module_base(name) {
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                result => return result,
+            }
+            match timeout.checked_sub(start.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => {
+                    std::thread::sleep(POLL_INTERVAL.min(remaining));
+                }
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("The module {name} wasn't loaded within {timeout:?}"),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// A trait that defines that it is possible to pause and continue the execution of something
+/// represented by a type, to stop it changing its memory while it is being read or written.
+pub trait ControlProcess {
+    /// Suspend every thread of the process.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the process can't be suspended.
+    fn suspend(&self) -> std::io::Result<()>;
+
+    /// Resume the threads of the process after a call to [`ControlProcess::suspend`].
+    ///
+    /// # Errors
+    /// `std::io::Error` if the process can't be resumed.
+    ///
+    /// [`ControlProcess::suspend`]: trait.ControlProcess.html#tymethod.suspend
+    fn resume(&self) -> std::io::Result<()>;
+
+    /// Suspend the process, call `f`, and resume the process, so that it can't observe the
+    /// memory `f` writes to it half-written. The process is resumed even if `f` panics.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the process can't be suspended or resumed. `f` isn't called if the
+    /// process can't be suspended.
+    fn suspended<R>(&self, f: impl FnOnce() -> R) -> std::io::Result<R> {
+        /// Resumes the process when dropped, which only happens if `f` panics.
+        struct ResumeOnDrop<'a, T: ControlProcess + ?Sized>(&'a T);
+        impl<T: ControlProcess + ?Sized> Drop for ResumeOnDrop<'_, T> {
+            fn drop(&mut self) {
+                let _ = self.0.resume();
+            }
+        }
+
+        self.suspend()?;
+        let guard = ResumeOnDrop(self);
+        let result = f();
+        std::mem::forget(guard);
+        self.resume()?;
+        Ok(result)
+    }
+}
+
+/// A trait that defines that it is possible to list the threads of something represented by a
+/// type.
+pub trait EnumerateThreads {
+    /// Get every thread that is currently running in the process. The threads may have changed by
+    /// the time this returns, unless the process has been suspended.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the threads can't be retrieved, such as if the process has exited.
+    fn threads(&self) -> std::io::Result<Vec<ThreadInfo>>;
+
+    /// Read the registers of the thread `tid` of the process, such as to find out what it is
+    /// executing. The thread is stopped while its registers are read, and carries on afterwards.
+    ///
+    /// # Errors
+    /// `std::io::Error` if the registers can't be read, such as if the thread has exited. The
+    /// error has a `std::io::ErrorKind` of `NotFound` if the process has no thread `tid`, and of
+    /// `Unsupported` if registers can't be read on this architecture.
+    fn thread_context(&self, tid: Tid) -> std::io::Result<ThreadContext>;
+}
+
+/// Check whether the file at `path` is the module `name`, by comparing `name` to its file name
+/// and file stem.
+#[cfg(not(windows))]
+fn module_name_matches(path: &str, name: &str) -> bool {
+    let path = std::path::Path::new(path);
+    path.file_name().is_some_and(|file_name| file_name == name)
+        || path.file_stem().is_some_and(|file_stem| file_stem == name)
+}
+
+/// Find the first of `regions` whose pathname is `name`, or that maps a file that is the module
+/// `name`.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn region_named(
+    regions: impl IntoIterator<Item = std::io::Result<(Region, String)>>,
+    name: &str,
+) -> std::io::Result<Option<Region>> {
+    for entry in regions {
+        let (region, path) = entry?;
+        if path == name || (path.starts_with('/') && module_name_matches(&path, name)) {
+            return Ok(Some(region));
+        }
+    }
+    Ok(None)
+}
+
+/// Get the region that holds the start of the module `name`, or `None` if there is no such
+/// module, for platforms whose memory maps don't label regions.
+#[cfg(any(windows, target_os = "macos"))]
+fn module_region<H: FindModule + EnumerateRegions>(
+    handle: &H,
+    name: &str,
+) -> std::io::Result<Option<Region>> {
+    match handle.get_module_base(name) {
+        Ok(base) => handle.region_containing(base),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find the file-backed mapping in `maps` that contains `addr`, returning the file name of the
+/// module it belongs to along with how far `addr` is from the start of the module's first mapping.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn module_relative(maps: &[(Region, String)], addr: usize) -> Option<(String, usize)> {
+    let (_, path) = maps
+        .iter()
+        .find(|(region, path)| path.starts_with('/') && region.contains(addr))?;
+    let base = maps
+        .iter()
+        .filter(|(_, other)| other == path)
+        .map(|(region, _)| region.base)
+        .min()?;
+    let name = std::path::Path::new(path).file_name()?.to_string_lossy();
+    Some((name.into_owned(), addr - base))
+}
+
+#[cfg(all(target_os = "macos", target_arch = "aarch64"))]
+pub use platform::ArmThreadState64;
+#[cfg(windows)]
+pub use platform::OwnedProcessHandle;
 /// A `Pid` is a "process id". Each different platform has a different method for uniquely
 /// identifying a process. You can see what the Rust standard library uses for your platform by
 /// looking at `std::process::id`.
 pub use platform::Pid;
 /// A `ProcessHandle` is a variable type that allows for access to functions that can manipulate
-/// other processes. On platforms other than Linux, this is typically a different type than
-/// [`Pid`], and thus it is a distinct type here.
+/// other processes. It holds whatever the platform uses to refer to a process (a [`Pid`] on Linux,
+/// a task port on macOS and a `HANDLE` on Windows), along with the `Architecture` of the process.
 ///
 /// [`Pid`]: type.Pid.html
 pub use platform::ProcessHandle;
+/// A `RawProcessHandle` is what the platform itself uses to refer to a process, which a
+/// [`ProcessHandle`] can be made from with `ProcessHandle::from_raw_handle` and turned back into
+/// with `ProcessHandle::into_raw_handle`, to share it with other code: a [`Pid`] on Linux and
+/// FreeBSD, a task port on macOS and a `HANDLE` on Windows. Whether the `ProcessHandle` takes
+/// ownership of it differs between platforms, and is described by `from_raw_handle`.
+///
+/// [`ProcessHandle`]: struct.ProcessHandle.html
+/// [`Pid`]: type.Pid.html
+pub use platform::RawProcessHandle;
+/// A `RawThreadContext` holds every register of a thread in a [`ThreadContext`], in the format
+/// used by the platform: a `libc::user_regs_struct` on Linux, a `libc::reg` on FreeBSD, a
+/// `CONTEXT` on Windows and an `x86_thread_state64_t` or [`ArmThreadState64`] on macOS. On
+/// architectures where registers can't be read it is `()`.
+///
+/// [`ThreadContext`]: struct.ThreadContext.html
+/// [`ArmThreadState64`]: struct.ArmThreadState64.html
+pub use platform::RawThreadContext;
+/// A `Tid` is a "thread id", which identifies a thread in a [`ThreadInfo`]. Like a [`Pid`], each
+/// platform has its own type for it.
+///
+/// [`ThreadInfo`]: struct.ThreadInfo.html
+/// [`Pid`]: type.Pid.html
+pub use platform::Tid;
+#[cfg(target_os = "macos")]
+pub use platform::{send_task_port_to_parent, CommandExt, MachError};
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use platform::{WatchHit, WatchId, WatchKind, WatchSize};
 
 /// A trait that attempts to turn some type into a [`ProcessHandle`] so memory can be either copied
 /// or placed into it.
 ///
-/// [`ProcessHandle`]: type.ProcessHandle.html
+/// [`ProcessHandle`]: struct.ProcessHandle.html
 pub trait TryIntoProcessHandle {
     /// Attempt to turn a type into a [`ProcessHandle`]. Whilst Linux provides the same type for
     /// [`Pid`]s and [`ProcessHandle`]s, Windows and macOS do not. As such, you need to ensure that
@@ -155,24 +864,158 @@ pub trait TryIntoProcessHandle {
     /// # Errors
     /// Returns an error if the type cannot be turned into a [`ProcessHandle`]
     ///
-    /// [`ProcessHandle`]: type.ProcessHandle.html
+    /// [`ProcessHandle`]: struct.ProcessHandle.html
     /// [`Pid`]: type.Pid.html
     fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle>;
 }
 
 impl TryIntoProcessHandle for ProcessHandle {
     fn try_into_process_handle(&self) -> std::io::Result<platform::ProcessHandle> {
-        Ok(*self)
+        Ok(self.clone())
     }
 }
 
-/// A trait to check that a `ProcessHandle` it valid on various platforms.
-pub trait HandleChecker {
+/// Additional functions on a `ProcessHandle` that are implemented differently on each platform.
+pub trait ProcessHandleExt {
     /// Returns `true` if the `ProcessHandle` is not null, and `false` otherwise.
     fn check_handle(&self) -> bool;
     /// Return the null equivalent of a `ProcessHandle`.
     #[must_use]
     fn null_type() -> ProcessHandle;
+    /// Get the [`Pid`] of the process that the `ProcessHandle` refers to.
+    ///
+    /// # Errors
+    /// Returns an error if the `ProcessHandle` is invalid or the [`Pid`] can't be retrieved.
+    ///
+    /// [`Pid`]: type.Pid.html
+    fn get_pid(&self) -> std::io::Result<Pid>;
+    /// Set the `Architecture` of the process that the `ProcessHandle` refers to, which is used as
+    /// the size of the pointers read while following a chain of offsets. This defaults to the
+    /// architecture of the host process.
+    #[must_use]
+    fn set_arch(self, arch: Architecture) -> Self;
+    /// Get the `Architecture` of the process that the `ProcessHandle` refers to.
+    fn get_arch(&self) -> Architecture;
+    /// Get the size of a page of memory in the process, which is the granularity that memory is
+    /// mapped and protected in. Every process on a system has the same page size, but systems
+    /// differ: it is 4096 bytes on most, but 16384 on Apple Silicon.
+    fn page_size(&self) -> usize;
+    /// Returns `true` if the process that the `ProcessHandle` refers to is still running, such as
+    /// for deciding whether to reconnect after a read fails. A process that has been suspended or
+    /// stopped is still running, but one that has exited and not yet been reaped by its parent
+    /// (a zombie) is not.
+    fn is_alive(&self) -> bool;
+    /// Get the path of the executable that the process was started from, such as to check that
+    /// the right program was attached to.
+    ///
+    /// # Errors
+    /// Returns an error if the `ProcessHandle` is invalid, or the path can't be retrieved (for
+    /// instance if the process has exited, or we aren't allowed to inspect it).
+    fn exe_path(&self) -> std::io::Result<std::path::PathBuf>;
+    /// Get the command line arguments that the process was started with, including the program
+    /// name as the first argument. Arguments that aren't valid unicode are converted lossily.
+    ///
+    /// A process is free to change its arguments in its own memory after it starts, and on some
+    /// platforms these changes are what is returned.
+    ///
+    /// # Errors
+    /// Returns an error if the `ProcessHandle` is invalid, or the arguments can't be retrieved
+    /// (for instance if the process has exited, or we aren't allowed to inspect it).
+    fn cmdline(&self) -> std::io::Result<Vec<String>>;
+
+    /// Returns `true` if `addr` is in a region of memory that can be read, without reading it.
+    ///
+    /// The protection of the region is looked up with [`EnumerateRegions::region_containing`],
+    /// and an address that isn't mapped, or whose region can't be looked up, is simply not
+    /// readable.
+    ///
+    /// [`EnumerateRegions::region_containing`]: trait.EnumerateRegions.html#method.region_containing
+    fn is_readable(&self, addr: usize) -> bool
+    where
+        Self: EnumerateRegions,
+    {
+        has_protection(self, addr, Protection::READ)
+    }
+
+    /// Returns `true` if `addr` is in a region of memory that can be written to, without writing
+    /// to it.
+    ///
+    /// Like [`ProcessHandleExt::is_readable`], an address that isn't mapped, or whose region can't
+    /// be looked up, is simply not writable.
+    ///
+    /// [`ProcessHandleExt::is_readable`]: trait.ProcessHandleExt.html#method.is_readable
+    fn is_writable(&self, addr: usize) -> bool
+    where
+        Self: EnumerateRegions,
+    {
+        has_protection(self, addr, Protection::WRITE)
+    }
+
+    /// Hint that memory is about to be read from front to back, such as when scanning large
+    /// regions, so that the operating system can read ahead. The hint is shared by every clone of
+    /// the handle, and `false` goes back to the default.
+    ///
+    /// This only has an effect on Linux when memory is read through `/proc/<pid>/mem`, where it
+    /// is passed on to the kernel with `posix_fadvise`, and the kernel is free to ignore it.
+    /// Everywhere else it does nothing.
+    fn set_scan_mode(&self, enabled: bool) {
+        let _ = enabled;
+    }
+}
+
+/// Check whether the region containing `addr` has all of the flags in `protection`.
+fn has_protection<T>(regions: &T, addr: usize, protection: Protection) -> bool
+where
+    T: EnumerateRegions + ?Sized,
+{
+    matches!(
+        regions.region_containing(addr),
+        Ok(Some(region)) if region.protection.contains(protection)
+    )
+}
+
+/// Convenient functions for reading and writing raw bytes, for one-off reads and patches that don't
+/// need a [`DataMember`].
+///
+/// This is implemented for everything that is both [`CopyAddress`] and [`PutAddress`], such as a
+/// `ProcessHandle` on every platform.
+///
+/// ```rust
+/// # use process_memory::{Pid, ProcessHandleIoExt, TryIntoProcessHandle};
+/// let mut bytes = [1_u8, 2, 3, 4];
+/// let addr = bytes.as_mut_ptr() as usize;
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// handle.write_bytes(addr + 1, &[0x90, 0x90]).unwrap();
+/// assert_eq!(handle.read_bytes(addr, 4).unwrap(), [1, 0x90, 0x90, 4]);
+/// ```
+///
+/// [`DataMember`]: struct.DataMember.html
+/// [`CopyAddress`]: trait.CopyAddress.html
+/// [`PutAddress`]: trait.PutAddress.html
+pub trait ProcessHandleIoExt {
+    /// Read `len` bytes at `addr`.
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs copying the address.
+    fn read_bytes(&self, addr: usize, len: usize) -> std::io::Result<Vec<u8>>;
+
+    /// Write `bytes` to `addr`.
+    ///
+    /// # Errors
+    /// `std::io::Error` if an error occurs writing to the address.
+    fn write_bytes(&self, addr: usize, bytes: &[u8]) -> std::io::Result<()>;
+}
+
+impl<T: CopyAddress + PutAddress + ?Sized> ProcessHandleIoExt for T {
+    fn read_bytes(&self, addr: usize, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        self.copy_address(addr, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write_bytes(&self, addr: usize, bytes: &[u8]) -> std::io::Result<()> {
+        self.put_address(addr, bytes)
+    }
 }
 
 /// A trait that refers to and allows writing to a region of memory in a running program.
@@ -189,7 +1032,7 @@ pub trait Memory<T> {
     ///
     /// This function is safe because it should never internally allow for a null pointer
     /// deference, and instead should return a `std::io::Error` with a `std::io::ErrorKind` of
-    /// `Other`.
+    /// `NotFound`.
     ///
     /// # Errors
     /// Returns an error if copying memory fails or if a null pointer dereference would
@@ -198,11 +1041,30 @@ pub trait Memory<T> {
     /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
     fn get_offset(&self) -> std::io::Result<usize>;
 
+    /// Returns the address that the offsets given by [`Memory::set_offset`] resolve to, without
+    /// reading the value there. This is useful for handing the address to something else, such
+    /// as [`ProtectMemory::set_protection`] before patching it.
+    ///
+    /// This is the same as [`Memory::get_offset`]: the whole chain of pointers is followed again
+    /// on every call, so for a [`DataMember`] each pointer in the chain is read from the process.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur.
+    ///
+    /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
+    /// [`Memory::get_offset`]: trait.Memory.html#tymethod.get_offset
+    /// [`ProtectMemory::set_protection`]: trait.ProtectMemory.html#tymethod.set_protection
+    /// [`DataMember`]: struct.DataMember.html
+    fn address(&self) -> std::io::Result<usize> {
+        self.get_offset()
+    }
+
     /// Reads the value of the pointer from the offsets given by [`Memory::set_offset`].
     ///
     /// This function is safe because it should never internally allow for a null pointer
     /// deference, and instead should return a `std::io::Error` with a `std::io::ErrorKind` of
-    /// `Other`.
+    /// `NotFound`.
     ///
     /// # Errors
     /// Returns an error if copying memory fails or if a null pointer dereference would
@@ -211,11 +1073,28 @@ pub trait Memory<T> {
     /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
     fn read(&self) -> std::io::Result<T>;
 
+    /// Reads the value of the pointer from the offsets given by [`Memory::set_offset`] into
+    /// `out`, which lets large values be read into a buffer owned by the caller.
+    ///
+    /// By default this just calls [`Memory::read`] and assigns the result to `out`, but
+    /// implementations may override it to copy the value directly into `out` instead.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur. `out` may have been partially overwritten if this fails.
+    ///
+    /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    fn read_into(&self, out: &mut T) -> std::io::Result<()> {
+        *out = self.read()?;
+        Ok(())
+    }
+
     /// Writes `value` to the pointer from the offsets given by [`Memory::set_offset`].
     ///
     /// This function is safe because it should never internally allow for a null pointer
     /// deference, and instead should return a `std::io::Error` with a `std::io::ErrorKind` of
-    /// `Other`.
+    /// `NotFound`.
     ///
     /// This function takes a reference instead of taking ownership so if the caller passes in a
     /// `String` or a `Vec`, it does not have to be cloned.
@@ -226,6 +1105,293 @@ pub trait Memory<T> {
     ///
     /// [`Memory::set_offset`]: trait.Memory.html#tymethod.set_offset
     fn write(&self, value: &T) -> std::io::Result<()>;
+
+    /// Writes `value` like [`Memory::write`], and returns the address that the offsets resolved
+    /// to, so that it can be used for later reads and writes without resolving the offsets again.
+    ///
+    /// The returned address is only valid for as long as the pointers in the chain don't change,
+    /// so it will be stale once the target relocates the object (or any object in the chain).
+    ///
+    /// By default this resolves the offsets with [`Memory::get_offset`] and then calls
+    /// [`Memory::write`], resolving them twice, but implementations may override it to resolve
+    /// them only once.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur.
+    ///
+    /// [`Memory::write`]: trait.Memory.html#tymethod.write
+    /// [`Memory::get_offset`]: trait.Memory.html#tymethod.get_offset
+    fn write_and_resolve(&self, value: &T) -> std::io::Result<usize> {
+        let offset = self.get_offset()?;
+        self.write(value)?;
+        Ok(offset)
+    }
+
+    /// Writes `value` like [`Memory::write`], then reads it back and returns whether the bytes
+    /// read back are the same as those written, such as to detect writes that the target
+    /// immediately reverts.
+    ///
+    /// The bytes of `value` are compared, rather than using `PartialEq`, so `T` should be a
+    /// plain-old-data type without any padding. A `false` result only means that the value had
+    /// changed by the time it was read back, which may also be because the target legitimately
+    /// wrote to it in between.
+    ///
+    /// By default this calls [`Memory::write`] and then [`Memory::read`], resolving the offsets
+    /// twice, but implementations may override it to resolve them only once and use the same
+    /// address for both.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur.
+    ///
+    /// [`Memory::write`]: trait.Memory.html#tymethod.write
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    fn write_verified(&self, value: &T) -> std::io::Result<bool> {
+        self.write(value)?;
+        let read = self.read()?;
+        Ok(bytes_of(&read) == bytes_of(value))
+    }
+
+    /// Reads the value like [`Memory::read`], but classifies any error as a [`MemoryError`], so
+    /// that a process that has gone away can be told apart from an address that can't be read.
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur.
+    ///
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    /// [`MemoryError`]: enum.MemoryError.html
+    fn try_read(&self) -> Result<T, MemoryError> {
+        self.read().map_err(MemoryError::from)
+    }
+
+    /// Writes `value` like [`Memory::write`], but classifies any error as a [`MemoryError`].
+    ///
+    /// # Errors
+    /// Returns an error if copying memory fails or if a null pointer dereference would
+    /// otherwise occur.
+    ///
+    /// [`Memory::write`]: trait.Memory.html#tymethod.write
+    /// [`MemoryError`]: enum.MemoryError.html
+    fn try_write(&self, value: &T) -> Result<(), MemoryError> {
+        self.write(value).map_err(MemoryError::from)
+    }
+
+    /// Reads the value like [`Memory::read`], trying again up to `attempts` times in total,
+    /// waiting `backoff` between attempts, while the error is one that may go away by itself.
+    /// This is useful for pointers that are briefly invalid, such as during a level transition.
+    ///
+    /// Only errors that are classified as [`MemoryError::NullInChain`] or
+    /// [`MemoryError::AddressUnreadable`] are retried. Any other error, such as the process having
+    /// gone away or permission being denied, won't be resolved by waiting, so it is returned
+    /// straight away. The value is always read at least once, even if `attempts` is 0.
+    ///
+    /// # Errors
+    /// Returns the last error if every attempt fails, or the first error that isn't retried.
+    ///
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    /// [`MemoryError::NullInChain`]: enum.MemoryError.html#variant.NullInChain
+    /// [`MemoryError::AddressUnreadable`]: enum.MemoryError.html#variant.AddressUnreadable
+    fn read_retry(&self, attempts: usize, backoff: std::time::Duration) -> std::io::Result<T> {
+        let mut attempt = 1;
+        loop {
+            match self.read().map_err(MemoryError::from) {
+                Ok(value) => return Ok(value),
+                Err(MemoryError::NullInChain(err) | MemoryError::AddressUnreadable(err))
+                    if attempt >= attempts =>
+                {
+                    return Err(err)
+                }
+                Err(MemoryError::NullInChain(_) | MemoryError::AddressUnreadable(_)) => {
+                    std::thread::sleep(backoff);
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+/// Zero `buf`, returning it as the initialised bytes it now holds.
+fn init_zeroed(buf: &mut [std::mem::MaybeUninit<u8>]) -> &mut [u8] {
+    buf.fill(std::mem::MaybeUninit::new(0));
+    unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), buf.len()) }
+}
+
+/// View the initialised bytes of `buf` as possibly uninitialised ones, so that they can be read
+/// into by a platform's `copy_address_uninit`, which only ever writes initialised bytes to them.
+fn as_uninit_mut(buf: &mut [u8]) -> &mut [std::mem::MaybeUninit<u8>] {
+    unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), buf.len()) }
+}
+
+/// View the bytes of `value`.
+fn bytes_of<T>(value: &T) -> &[u8] {
+    unsafe {
+        std::slice::from_raw_parts(std::ptr::from_ref(value).cast(), std::mem::size_of::<T>())
+    }
+}
+
+/// Open the process `pid`, requesting only the access given by `access`.
+///
+/// Asking for less access needs fewer privileges, but a `ProcessHandle` without an access will
+/// fail with a `PermissionDenied` error when it is used for something that needs it. Access is
+/// only granted per handle on Windows, where `access` is turned into the access rights passed to
+/// `OpenProcess`. Elsewhere `access` is ignored and this is the same as
+/// [`try_into_process_handle`].
+///
+/// ```rust
+/// # use process_memory::{open_process, AccessMode, CopyAddress, Pid};
+/// let value = 7_u8;
+/// let handle = open_process(std::process::id() as Pid, AccessMode::READ).unwrap();
+/// let mut buf = [0_u8];
+/// handle.copy_address(&value as *const _ as usize, &mut buf).unwrap();
+/// assert_eq!(value, buf[0]);
+/// ```
+///
+/// # Errors
+/// Returns an error if the process can't be opened with the requested access.
+///
+/// [`try_into_process_handle`]: trait.TryIntoProcessHandle.html#tymethod.try_into_process_handle
+pub fn open_process(pid: Pid, access: AccessMode) -> std::io::Result<ProcessHandle> {
+    platform::open_process(pid, access)
+}
+
+/// Find a running process by the name of its executable, and open it with [`open_process`],
+/// returning its [`Pid`] along with the handle.
+///
+/// Like module names, `name` may be the file name (`notepad.exe`, `game`) or the file stem
+/// (`notepad`) of the executable. It is matched case-insensitively on Windows, and
+/// case-sensitively elsewhere. Where the full path of a process's executable can't be found (such
+/// as for another user's process on Linux, or on FreeBSD) its short name is used instead, which
+/// the kernel truncates to 15 characters on Linux and 19 on FreeBSD, so a longer `name` matches if
+/// it starts with the truncated name.
+///
+/// If several processes match, the one with the lowest pid is opened, which for several copies of
+/// the same program is usually the oldest. If it can't be opened, the error is returned, rather
+/// than trying the next one.
+///
+/// ```rust
+/// # use process_memory::{open_process_by_name, AccessMode};
+/// let exe = std::env::current_exe().unwrap();
+/// let name = exe.file_name().unwrap().to_str().unwrap();
+/// let (pid, handle) = open_process_by_name(name, AccessMode::READ).unwrap();
+/// println!("Opened {name} (pid {pid}): {handle:?}");
+/// ```
+///
+/// # Errors
+/// Returns an error with a `std::io::ErrorKind` of `NotFound` if no process matches, or an error
+/// if the processes can't be listed or the process can't be opened.
+///
+/// [`open_process`]: fn.open_process.html
+/// [`Pid`]: type.Pid.html
+pub fn open_process_by_name(
+    name: &str,
+    access: AccessMode,
+) -> std::io::Result<(Pid, ProcessHandle)> {
+    let pid = platform::processes_named(name)?
+        .into_iter()
+        .min()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No process named {name:?} is running"),
+            )
+        })?;
+    Ok((pid, open_process(pid, access)?))
+}
+
+/// Check whether the short name `comm` that the kernel keeps for a process, which is truncated to
+/// `max_len` bytes, could be the name `name`.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn comm_matches(comm: &str, name: &str, max_len: usize) -> bool {
+    comm == name || (comm.len() == max_len && name.starts_with(comm))
+}
+
+/// Follow a chain of offsets like [`CopyAddress::get_offset`], reading the pointer after adding
+/// offset `index` with a width of `width(index)`.
+///
+/// [`CopyAddress::get_offset`]: trait.CopyAddress.html#method.get_offset
+fn follow_offsets<T: CopyAddress + ?Sized>(
+    source: &T,
+    offsets: &[usize],
+    width: impl Fn(usize) -> Architecture,
+) -> std::io::Result<usize> {
+    // Look ma! No unsafes!
+    let mut offset: usize = 0;
+    let noffsets: usize = offsets.len();
+    if noffsets == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "No offsets to resolve",
+        ));
+    }
+    let mut copy = Vec::new();
+    for (index, &next_offset) in offsets.iter().take(noffsets - 1).enumerate() {
+        offset = add_offset(offset, next_offset, index)?;
+        let arch = width(index);
+        copy.resize(arch as usize, 0);
+        source.copy_address(offset, &mut copy)?;
+        let pointer_addr = offset;
+        offset = arch.pointer_from_ne_bytes(&copy);
+        if offset == 0 {
+            return Err(memory_error::classified_error(
+                std::io::ErrorKind::NotFound,
+                memory_error::ErrorClass::NullInChain,
+                format!("The pointer at offset index {index} ({pointer_addr:#x}) is null"),
+            ));
+        }
+    }
+
+    add_offset(offset, offsets[noffsets - 1], noffsets - 1)
+}
+
+/// Add the offset at index `index` of a chain of offsets, `offset`, to `addr`, failing with a
+/// `std::io::ErrorKind` of `InvalidInput` rather than wrapping around if that overflows.
+fn add_offset(addr: usize, offset: usize, index: usize) -> std::io::Result<usize> {
+    addr.checked_add(offset).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Offset overflow at hop {index}: adding {offset:#x} to {addr:#x} would wrap around \
+                 the address space"
+            ),
+        )
+    })
+}
+
+/// Check that the `size` bytes at `addr` don't run past the end of the address space, failing
+/// with a `std::io::ErrorKind` of `InvalidInput` if they do, before anything is read or written.
+fn check_span(addr: usize, size: usize) -> std::io::Result<()> {
+    match addr.checked_add(size) {
+        Some(_) => Ok(()),
+        None => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("The {size} bytes at {addr:#x} run past the end of the address space"),
+        )),
+    }
+}
+
+/// The error for a string at `addr` that runs past the end of the address space before its
+/// terminator, with a `std::io::ErrorKind` of `InvalidInput`.
+fn string_overflow(addr: usize) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("The string at {addr:#x} runs past the end of the address space"),
+    )
+}
+
+/// Split a buffer of null-terminated arguments, as kept by the kernel for a process, into
+/// `String`s. A missing terminator after the last argument is allowed.
+#[cfg(unix)]
+fn split_args(args: &[u8]) -> Vec<String> {
+    let args = args.strip_suffix(&[0]).unwrap_or(args);
+    if args.is_empty() {
+        return Vec::new();
+    }
+    args.split(|&byte| byte == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
 }
 
 /// Copy `length` bytes of memory at `addr` from `source`.
@@ -239,8 +1405,907 @@ pub fn copy_address<T>(addr: usize, length: usize, source: &T) -> std::io::Resul
 where
     T: CopyAddress,
 {
-    let mut copy = vec![0; length];
+    let mut copy = Vec::new();
+    copy_address_buf(addr, length, source, &mut copy)?;
+    Ok(copy)
+}
 
+/// Copy `len` bytes of memory at `addr` from `source` into `buf`, which is resized to `len` bytes.
+///
+/// This is like [`copy_address`], but reuses a buffer owned by the caller, so that calling it in
+/// a loop (such as while scanning memory) only allocates when `buf` has to grow. If copying fails,
+/// the contents of `buf` are unspecified, but it is still `len` bytes long.
+///
+/// ```rust
+/// # use std::convert::TryInto;
+/// # use process_memory::{copy_address_buf, Pid, TryIntoProcessHandle};
+/// let values = [1_u32, 2, 3];
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let mut buf = Vec::new();
+/// for value in &values {
+///     copy_address_buf(value as *const _ as usize, 4, &handle, &mut buf).unwrap();
+///     assert_eq!(*value, u32::from_ne_bytes(buf[..].try_into().unwrap()));
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if copying memory fails
+///
+/// [`copy_address`]: fn.copy_address.html
+pub fn copy_address_buf(
+    addr: usize,
+    len: usize,
+    source: &(impl CopyAddress + ?Sized),
+    buf: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    // Any bytes left over from a previous read are overwritten, so they don't need zeroing.
+    buf.resize(len, 0);
+    source.copy_address(addr, buf)
+}
+
+/// Copy `N` bytes of memory at `addr` from `source` into an array.
+///
+/// This is like [`copy_address`], but as the length is known at compile time the bytes are read
+/// into an array on the stack instead of a `Vec`, so nothing is allocated. The array is zeroed
+/// before it is read into, as `CopyAddress::copy_address` takes an initialised buffer.
+///
+/// ```rust
+/// # use process_memory::{copy_address_array, Pid, TryIntoProcessHandle};
+/// let value = 0x1234_5678_u32;
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let bytes = copy_address_array::<4>(&value as *const _ as usize, &handle).unwrap();
+/// assert_eq!(value, u32::from_ne_bytes(bytes));
+/// ```
+///
+/// # Errors
+/// Returns an error if copying memory fails
+///
+/// [`copy_address`]: fn.copy_address.html
+pub fn copy_address_array<const N: usize>(
+    addr: usize,
+    source: &impl CopyAddress,
+) -> std::io::Result<[u8; N]> {
+    let mut copy = [0; N];
     source.copy_address(addr, &mut copy)?;
     Ok(copy)
 }
+
+/// Follow a chain of `offsets` from `base` in `source`, like [`CopyAddress::get_offset`], but
+/// return every address along the way instead of just the last, for finding out where a chain
+/// broke.
+///
+/// The first address is `base`. Each offset is then added to the current address in turn, and the
+/// result is pushed, so there is one more address than there are offsets. For every offset but the
+/// last, the pointer at the new address is read (using the width from
+/// [`CopyAddress::get_pointer_width`]) and becomes the current address. The last address is the
+/// one that [`CopyAddress::get_offset`] would return for the same chain, with `base` added to the
+/// first offset. As a broken chain may well contain garbage pointers, every addition is checked,
+/// and one that would wrap around the address space stops the chain with an "Offset overflow at
+/// hop N" error instead.
+///
+/// ```rust
+/// # use process_memory::{resolve_chain, Pid, TryIntoProcessHandle};
+/// let value = 7_u32;
+/// let pointer = &value as *const u32 as usize;
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let base = &pointer as *const usize as usize;
+/// assert_eq!(resolve_chain(&handle, base, &[0, 0]).unwrap(), vec![base, base, pointer]);
+/// ```
+///
+/// # Errors
+/// Returns a [`ChainError`] if a pointer can't be read or is null, or adding an offset overflows
+/// the address space. It holds the error, which is classified as `MemoryError::NullInChain` for
+/// a null pointer and has a `std::io::ErrorKind` of `InvalidInput` for an overflow, and the
+/// addresses that were resolved up to and including the one that was being read. For an
+/// overflow, that is every address before the offset that couldn't be added.
+///
+/// [`CopyAddress::get_offset`]: trait.CopyAddress.html#method.get_offset
+/// [`CopyAddress::get_pointer_width`]: trait.CopyAddress.html#method.get_pointer_width
+/// [`ChainError`]: struct.ChainError.html
+pub fn resolve_chain<T>(
+    source: &T,
+    base: usize,
+    offsets: &[usize],
+) -> Result<Vec<usize>, ChainError>
+where
+    T: CopyAddress + ?Sized,
+{
+    let arch = source.get_pointer_width();
+    let mut resolved = Vec::with_capacity(offsets.len() + 1);
+    resolved.push(base);
+    let mut address = base;
+    let mut copy = vec![0_u8; arch as usize];
+    for (index, &offset) in offsets.iter().enumerate() {
+        address = match add_offset(address, offset, index) {
+            Ok(address) => address,
+            Err(error) => return Err(ChainError { resolved, error }),
+        };
+        resolved.push(address);
+        if index + 1 == offsets.len() {
+            break;
+        }
+        if let Err(error) = source.copy_address(address, &mut copy) {
+            return Err(ChainError { resolved, error });
+        }
+        let pointer = arch.pointer_from_ne_bytes(&copy);
+        if pointer == 0 {
+            let error = memory_error::classified_error(
+                std::io::ErrorKind::NotFound,
+                memory_error::ErrorClass::NullInChain,
+                format!("The pointer at offset index {index} ({address:#x}) is null"),
+            );
+            return Err(ChainError { resolved, error });
+        }
+        address = pointer;
+    }
+    Ok(resolved)
+}
+
+/// Read a null-terminated string of at most `max_len` bytes (including the terminator) at `addr`
+/// from `source`.
+///
+/// The string is read in small chunks that never cross a page boundary, so reading a short string
+/// that sits at the end of a page won't fail because the next page isn't mapped.
+///
+/// # Errors
+/// Returns an error if copying memory fails, an error with a `std::io::ErrorKind` of
+/// `InvalidData` if no null terminator is found within `max_len` bytes, or one of `InvalidInput`
+/// if the string would run past the end of the address space before a terminator is found.
+pub fn read_cstring<T>(source: &T, addr: usize, max_len: usize) -> std::io::Result<CString>
+where
+    T: CopyAddress,
+{
+    const CHUNK_SIZE: usize = 32;
+    let mut bytes = Vec::new();
+    let mut chunk = [0_u8; CHUNK_SIZE];
+    while bytes.len() < max_len {
+        let chunk_addr = addr
+            .checked_add(bytes.len())
+            .ok_or_else(|| string_overflow(addr))?;
+        // Keeping each chunk aligned to its size means it can never straddle two pages.
+        let len = (CHUNK_SIZE - chunk_addr % CHUNK_SIZE).min(max_len - bytes.len());
+        source.copy_address(chunk_addr, &mut chunk[..len])?;
+        if let Some(nul) = chunk[..len].iter().position(|&byte| byte == 0) {
+            bytes.extend_from_slice(&chunk[..=nul]);
+            return CString::from_vec_with_nul(bytes)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+        }
+        bytes.extend_from_slice(&chunk[..len]);
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("No null terminator found within {max_len} bytes of {addr:#x}"),
+    ))
+}
+
+/// Read a UTF-16LE string of at most `max_chars` code units (not including the terminator) at
+/// `addr` from `source`, stopping at the first null code unit.
+///
+/// This is how Windows stores wide strings, but may also be useful for emulated or cross-compiled
+/// programs on other platforms. The string is read one code unit at a time, so it never reads
+/// past the terminator.
+///
+/// # Errors
+/// Returns an error if copying memory fails, an error with a `std::io::ErrorKind` of
+/// `InvalidData` if no null terminator is found within `max_chars` code units or the string isn't
+/// valid UTF-16, or one of `InvalidInput` if the string would run past the end of the address space
+/// before a terminator is found.
+pub fn read_wide_string<T>(source: &T, addr: usize, max_chars: usize) -> std::io::Result<String>
+where
+    T: CopyAddress,
+{
+    let mut units = Vec::new();
+    let mut unit = [0_u8; 2];
+    while units.len() <= max_chars {
+        let unit_addr = units
+            .len()
+            .checked_mul(2)
+            .and_then(|offset| addr.checked_add(offset))
+            .ok_or_else(|| string_overflow(addr))?;
+        source.copy_address(unit_addr, &mut unit)?;
+        match u16::from_le_bytes(unit) {
+            0 => {
+                return String::from_utf16(&units)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+            }
+            unit => units.push(unit),
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("No null terminator found within {max_chars} characters of {addr:#x}"),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn get_offset_null_in_chain() {
+        let value = 7_u32;
+        let pointer = &raw const value as usize;
+        let null = 0_usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let arch = Architecture::from_native();
+        assert_eq!(
+            handle
+                .get_offset(&[&raw const pointer as usize, 0x4], arch)
+                .unwrap(),
+            pointer + 0x4
+        );
+        let err = handle
+            .get_offset(&[&raw const null as usize, 0x10, 0x8], arch)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("offset index 0"));
+        assert_eq!(
+            handle.get_offset(&[], arch).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn get_offset_32_bit_hops() {
+        // Only the low four bytes should be read as a pointer, and then zero-extended.
+        let pointer = 0xDEAD_BEEF_0000_1000_u64.to_le();
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap()
+            .set_arch(Architecture::Arch32Bit);
+        assert_eq!(handle.get_arch(), Architecture::Arch32Bit);
+        assert_eq!(handle.get_pointer_width(), Architecture::Arch32Bit);
+
+        let offsets = [&raw const pointer as usize, 0x10];
+        assert_eq!(
+            handle
+                .get_offset(&offsets, handle.get_pointer_width())
+                .unwrap(),
+            0x1010
+        );
+        let member = DataMember::<u32>::new_offset(handle, offsets.to_vec());
+        assert_eq!(member.get_offset().unwrap(), 0x1010);
+    }
+
+    #[test]
+    fn copy_address_from_several_threads() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let values: Vec<u64> = (0..1024).collect();
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_send_sync(&handle);
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                let _ = scope.spawn(|| {
+                    for value in &values {
+                        let mut buf = [0_u8; 8];
+                        handle
+                            .copy_address(std::ptr::from_ref(value) as usize, &mut buf)
+                            .unwrap();
+                        assert_eq!(*value, u64::from_ne_bytes(buf));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn copy_addresses_batched() {
+        let first = 0x0102_0304_u32;
+        let second = [7_u8; 3];
+        let third = -2_i64;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut first_buf = [0_u8; 4];
+        let mut second_buf = [0_u8; 3];
+        let mut third_buf = [0_u8; 8];
+        handle
+            .copy_addresses(&mut [
+                (&raw const first as usize, &mut first_buf),
+                (&raw const second as usize, &mut second_buf),
+                (&raw const third as usize, &mut third_buf),
+            ])
+            .unwrap();
+        assert_eq!(first, u32::from_ne_bytes(first_buf));
+        assert_eq!(second, second_buf);
+        assert_eq!(third, i64::from_ne_bytes(third_buf));
+    }
+    #[test]
+    fn copy_address_into_array() {
+        let value = -3_i64;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let bytes = copy_address_array::<8>(&raw const value as usize, &handle).unwrap();
+        assert_eq!(value, i64::from_ne_bytes(bytes));
+        assert_eq!(
+            copy_address_array::<0>(&raw const value as usize, &handle).unwrap(),
+            []
+        );
+    }
+    #[test]
+    fn iterate_regions() {
+        let value = 0_u64;
+        let addr = &raw const value as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert!(handle
+            .regions_iter()
+            .unwrap()
+            .map(Result::unwrap)
+            .any(|region| region.base <= addr && addr < region.end()));
+    }
+    #[test]
+    fn copy_address_clamped_to_region() {
+        let value = Box::new(0x1234_u32);
+        let addr = std::ptr::from_ref(&*value) as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let region = handle.region_containing(addr).unwrap().unwrap();
+        // A stack buffer, as allocating one on the heap could grow the region.
+        let mut buf = [0_u8; 16];
+        assert_eq!(handle.copy_address_clamped(addr, &mut buf[..4]).unwrap(), 4);
+        assert_eq!(buf[..4], value.to_ne_bytes());
+        assert_eq!(
+            handle
+                .copy_address_clamped(region.end() - 4, &mut buf)
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            handle.copy_address_clamped(0, &mut buf).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+    #[test]
+    fn probe_protection() {
+        let value = Box::new(0_u64);
+        let addr = std::ptr::from_ref(&*value) as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert!(handle.is_readable(addr));
+        assert!(handle.is_writable(addr));
+        let code = probe_protection as *const () as usize;
+        assert!(handle.is_readable(code));
+        assert!(!handle.is_writable(code));
+        assert!(!handle.is_readable(0));
+        assert!(!handle.is_writable(0));
+    }
+    #[test]
+    #[cfg(not(target_os = "freebsd"))]
+    fn enumerate_threads() {
+        let (started, ready) = std::sync::mpsc::channel();
+        let (finish, finished) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            started.send(()).unwrap();
+            finished.recv().unwrap();
+        });
+        ready.recv().unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let threads = handle.threads().unwrap();
+        assert!(threads.len() >= 2);
+        assert_eq!(threads.iter().filter(|thread| thread.is_main).count(), 1);
+        finish.send(()).unwrap();
+        thread.join().unwrap();
+    }
+    #[test]
+    fn resolve_broken_chain() {
+        let value = 7_u32;
+        let pointer = Box::new(&raw const value as usize);
+        let null = 0_usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let base = std::ptr::from_ref(&*pointer) as usize;
+        assert_eq!(
+            resolve_chain(&handle, base, &[0, 4]).unwrap(),
+            vec![base, base, &raw const value as usize + 4]
+        );
+        assert_eq!(resolve_chain(&handle, base, &[]).unwrap(), vec![base]);
+        let null_addr = &raw const null as usize;
+        let err = resolve_chain(&handle, null_addr, &[0, 8, 4]).unwrap_err();
+        assert_eq!(err.resolved, vec![null_addr, null_addr]);
+        assert!(matches!(
+            MemoryError::from(err.error),
+            MemoryError::NullInChain(_)
+        ));
+        let err = resolve_chain(&handle, 0, &[0, 4]).unwrap_err();
+        assert_eq!(err.resolved, vec![0, 0]);
+        let err = resolve_chain(&handle, base, &[0, usize::MAX]).unwrap_err();
+        assert_eq!(err.resolved, vec![base, base]);
+        assert_eq!(err.error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.error.to_string().contains("hop 1"));
+    }
+    #[cfg(any(target_os = "linux", target_os = "android", windows))]
+    #[test]
+    fn read_after_exit_is_process_gone() {
+        let value = 5_u32;
+        #[cfg(windows)]
+        let mut child = std::process::Command::new("cmd")
+            .args(["/C", "exit"])
+            .spawn()
+            .unwrap();
+        #[cfg(not(windows))]
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        let _ = child.wait().unwrap();
+        assert!(!handle.is_alive());
+
+        let mut buf = [0_u8; 4];
+        let err = handle
+            .copy_address(&raw const value as usize, &mut buf)
+            .unwrap_err();
+        assert!(matches!(
+            MemoryError::from(err),
+            MemoryError::ProcessGone(_)
+        ));
+        assert!(!handle.is_alive());
+    }
+    #[test]
+    fn resolve_rip_relative_operands() {
+        // lea rax, [rip + 0x10], then lea rcx, [rip - 0x20], in the middle of a buffer.
+        let mut code = [0x90_u8; 0x40];
+        code[0x20..0x27].copy_from_slice(&[0x48, 0x8d, 0x05, 0x10, 0x00, 0x00, 0x00]);
+        code[0x27..0x2e].copy_from_slice(&[0x48, 0x8d, 0x0d, 0xe0, 0xff, 0xff, 0xff]);
+        let base = code.as_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_eq!(
+            handle.resolve_rip_relative(base + 0x20, 3, 7).unwrap(),
+            base + 0x27 + 0x10
+        );
+        assert_eq!(
+            handle.resolve_rip_relative(base + 0x27, 3, 7).unwrap(),
+            base + 0x2e - 0x20
+        );
+        assert!(handle.resolve_rip_relative(0, 3, 7).is_err());
+    }
+    #[test]
+    fn deref_single_pointer() {
+        let value = Box::new(7_u32);
+        let pointer = std::ptr::from_ref(&*value) as usize;
+        let null = 0_usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_eq!(handle.deref(&raw const pointer as usize).unwrap(), pointer);
+        let err = handle.deref(&raw const null as usize).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(matches!(
+            MemoryError::from(err),
+            MemoryError::NullInChain(_)
+        ));
+    }
+    #[test]
+    fn open_by_name() {
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        let (pid, handle) = open_process_by_name(name, AccessMode::READ).unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let expected = std::process::id() as Pid;
+        assert_eq!(pid, expected);
+        assert_eq!(handle.get_pid().unwrap(), expected);
+        assert_eq!(
+            open_process_by_name("no such process, hopefully", AccessMode::READ)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+    #[test]
+    fn dump_own_memory() {
+        /// Only keeps the bytes written at `offsets`, as the whole dump is far too large to keep.
+        struct Window {
+            position: u64,
+            offsets: std::ops::Range<u64>,
+            kept: Vec<u8>,
+        }
+        impl std::io::Write for Window {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                for &byte in buf {
+                    if self.offsets.contains(&self.position) {
+                        self.kept.push(byte);
+                    }
+                    self.position += 1;
+                }
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let test = [0xfe_u8, 0xed, 0xfa, 0xce];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut out = Window {
+            position: 0,
+            offsets: 0..0,
+            kept: Vec::new(),
+        };
+        let mut manifest = handle.dump_to(&mut out).unwrap();
+        assert_eq!(manifest.len(), out.position);
+        assert_eq!(manifest.address_of(manifest.len()), None);
+
+        // Keep the bytes where the test value was written in the previous dump, until two dumps
+        // in a row are laid out the same, as other tests may be changing the memory map.
+        for _ in 0..10 {
+            let offset = manifest.offset_of(test.as_ptr() as usize).unwrap();
+            assert_eq!(manifest.address_of(offset), Some(test.as_ptr() as usize));
+            out = Window {
+                position: 0,
+                offsets: offset..offset + 4,
+                kept: Vec::new(),
+            };
+            let previous = std::mem::replace(&mut manifest, handle.dump_to(&mut out).unwrap());
+            if manifest == previous {
+                assert_eq!(out.kept, test);
+                return;
+            }
+        }
+        panic!("The memory map kept changing between dumps");
+    }
+    #[test]
+    fn retry_transient_errors() {
+        use memory_error::ErrorClass::{
+            AddressUnreadable, NullInChain, PermissionDenied, ProcessGone,
+        };
+
+        /// Fails with `errors` in order, then succeeds.
+        struct Flaky {
+            errors: std::cell::RefCell<Vec<memory_error::ErrorClass>>,
+            reads: std::cell::Cell<usize>,
+        }
+        impl Memory<u32> for Flaky {
+            fn set_offset(&mut self, _new_offsets: Vec<usize>) {}
+            fn get_offset(&self) -> std::io::Result<usize> {
+                Ok(0)
+            }
+            fn read(&self) -> std::io::Result<u32> {
+                self.reads.set(self.reads.get() + 1);
+                if self.errors.borrow().is_empty() {
+                    return Ok(7);
+                }
+                let class = self.errors.borrow_mut().remove(0);
+                Err(memory_error::classified_error(
+                    std::io::ErrorKind::Other,
+                    class,
+                    format!("Failing with {class:?}"),
+                ))
+            }
+            fn write(&self, _value: &u32) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let flaky = |errors| Flaky {
+            errors: std::cell::RefCell::new(errors),
+            reads: std::cell::Cell::new(0),
+        };
+        let backoff = std::time::Duration::from_millis(1);
+
+        let member = flaky(vec![NullInChain, AddressUnreadable, NullInChain]);
+        assert_eq!(member.read_retry(4, backoff).unwrap(), 7);
+        assert_eq!(member.reads.get(), 4);
+
+        let member = flaky(vec![NullInChain, NullInChain, NullInChain]);
+        assert!(member.read_retry(3, backoff).is_err());
+        assert_eq!(member.reads.get(), 3);
+
+        let member = flaky(vec![NullInChain]);
+        assert!(member.read_retry(0, backoff).is_err());
+        assert_eq!(member.reads.get(), 1);
+
+        for class in [ProcessGone, PermissionDenied] {
+            let member = flaky(vec![NullInChain, class, NullInChain]);
+            assert!(member.read_retry(10, backoff).is_err());
+            assert_eq!(member.reads.get(), 2);
+        }
+    }
+    #[test]
+    // Only some platforms' handles need dropping.
+    #[allow(clippy::drop_non_drop)]
+    fn drop_one_clone() {
+        let test = 0x1234_u32;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let clone = handle.clone();
+        assert_eq!(clone, handle);
+        drop(handle);
+        let mut buffer = [0_u8; 4];
+        clone
+            .copy_address(&raw const test as usize, &mut buffer)
+            .unwrap();
+        assert_eq!(u32::from_ne_bytes(buffer), test);
+        let clone_of_clone = clone.clone();
+        drop(clone);
+        assert!(clone_of_clone.is_alive());
+    }
+    #[test]
+    fn inspect_own_process() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_eq!(
+            handle.exe_path().unwrap().canonicalize().unwrap(),
+            std::env::current_exe().unwrap().canonicalize().unwrap()
+        );
+        assert_eq!(
+            handle.cmdline().unwrap(),
+            std::env::args().collect::<Vec<_>>()
+        );
+    }
+    #[test]
+    fn find_own_main_module() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            handle.main_module_base().unwrap(),
+            handle.get_module_base(name).unwrap()
+        );
+    }
+    #[test]
+    fn module_relative_round_trip() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        let addr = module_relative_round_trip as fn() as usize;
+        let (module, offset) = handle.to_module_relative(addr).unwrap().unwrap();
+        assert_eq!(module, name);
+        assert_eq!(offset, addr - handle.get_module_base(name).unwrap());
+        assert_eq!(handle.from_module_relative(&module, offset).unwrap(), addr);
+
+        let heap = vec![0_u8; 0x10_0000];
+        assert_eq!(
+            handle.to_module_relative(heap.as_ptr() as usize).unwrap(),
+            None
+        );
+    }
+    #[test]
+    fn wait_for_loaded_module() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            handle
+                .wait_for_module(name, std::time::Duration::ZERO)
+                .unwrap(),
+            handle.get_module_base(name).unwrap()
+        );
+
+        let timeout = std::time::Duration::from_millis(120);
+        let start = std::time::Instant::now();
+        let err = handle
+            .wait_for_module("no-such-module.so", timeout)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= timeout);
+    }
+    #[test]
+    fn restore_protection_after_write() {
+        /// Tracks the protection of its memory, and only allows writes while it is writable.
+        struct Patchable {
+            protection: std::cell::Cell<Protection>,
+            fail: bool,
+        }
+        impl ProtectMemory for Patchable {
+            fn set_protection(
+                &self,
+                _addr: usize,
+                _size: usize,
+                prot: Protection,
+            ) -> std::io::Result<Protection> {
+                Ok(self.protection.replace(prot))
+            }
+        }
+        impl PutAddress for Patchable {
+            fn put_address(&self, _addr: usize, _buf: &[u8]) -> std::io::Result<()> {
+                if self.fail || !self.protection.get().contains(Protection::WRITE) {
+                    return Err(std::io::Error::other("Write failed"));
+                }
+                Ok(())
+            }
+        }
+
+        let code = Patchable {
+            protection: std::cell::Cell::new(Protection::READ | Protection::EXECUTE),
+            fail: false,
+        };
+        code.write_protected(0x1000, &[0x90]).unwrap();
+        assert_eq!(
+            code.protection.get(),
+            Protection::READ | Protection::EXECUTE
+        );
+
+        let code = Patchable {
+            protection: std::cell::Cell::new(Protection::READ),
+            fail: true,
+        };
+        assert!(code.write_protected(0x1000, &[0x90]).is_err());
+        assert_eq!(code.protection.get(), Protection::READ);
+    }
+    #[test]
+    fn copy_large_buffer() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        // Large enough to be split into several reads on platforms that limit the size of one.
+        #[allow(clippy::cast_possible_truncation)]
+        let source: Vec<u8> = (0..0x100_0000_usize).map(|i| (i % 251) as u8).collect();
+        let mut copy = vec![0_u8; source.len()];
+        handle
+            .copy_address(source.as_ptr() as usize, &mut copy)
+            .unwrap();
+        assert_eq!(copy, source);
+    }
+    #[test]
+    fn copy_into_uninitialised_buffer() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        #[allow(clippy::cast_possible_truncation)]
+        let source: Vec<u8> = (0..0x3000_usize).map(|i| (i % 251) as u8).collect();
+        let mut buf = Vec::with_capacity(source.len());
+        let read = handle
+            .copy_address_uninit(source.as_ptr() as usize, buf.spare_capacity_mut())
+            .unwrap();
+        assert_eq!(read, source.len());
+        unsafe { buf.set_len(read) };
+        assert_eq!(buf, source);
+
+        let mut uninit = [std::mem::MaybeUninit::<u8>::uninit(); 16];
+        let copied = handle
+            .copy_address_into_uninit(source.as_ptr() as usize + 8, &mut uninit)
+            .unwrap();
+        assert_eq!(copied, &source[8..24]);
+        assert!(handle.copy_address_uninit(0, &mut uninit).is_err());
+    }
+    #[test]
+    fn page_size_of_own_process() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let page_size = handle.page_size();
+        assert!(page_size >= 4096 && page_size.is_power_of_two());
+        if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+            assert_eq!(page_size, 16384);
+        }
+    }
+    #[test]
+    fn classify_read_errors() {
+        let null = 0_usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let member =
+            DataMember::<u32>::new_offset(handle.clone(), vec![&raw const null as usize, 0]);
+        assert!(matches!(
+            member.try_read(),
+            Err(MemoryError::NullInChain(_))
+        ));
+        let member = DataMember::<u32>::new_offset(handle, vec![0x10]);
+        assert!(matches!(
+            member.try_read(),
+            Err(MemoryError::AddressUnreadable(_))
+        ));
+        assert!(matches!(
+            member.try_write(&1),
+            Err(MemoryError::AddressUnreadable(_))
+        ));
+    }
+    #[test]
+    fn read_cstrings() {
+        let text = b"a player name that is longer than one chunk\0trailing";
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let string = read_cstring(&handle, text.as_ptr() as usize, 64).unwrap();
+        assert_eq!(
+            b"a player name that is longer than one chunk",
+            string.as_bytes()
+        );
+        let string = read_cstring(&handle, text.as_ptr() as usize + 34, 64).unwrap();
+        assert_eq!(b"one chunk", string.as_bytes());
+        assert_eq!(
+            std::io::ErrorKind::InvalidData,
+            read_cstring(&handle, text.as_ptr() as usize, 10)
+                .unwrap_err()
+                .kind()
+        );
+    }
+    #[test]
+    fn read_wide_strings() {
+        let text: Vec<u8> = "wide ñame"
+            .encode_utf16()
+            .chain([0, 0xd800, 0])
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let addr = text.as_ptr() as usize;
+        assert_eq!("wide ñame", read_wide_string(&handle, addr, 9).unwrap());
+        assert_eq!(
+            std::io::ErrorKind::InvalidData,
+            read_wide_string(&handle, addr, 8).unwrap_err().kind()
+        );
+        // A lone surrogate isn't valid UTF-16.
+        assert_eq!(
+            std::io::ErrorKind::InvalidData,
+            read_wide_string(&handle, addr + 20, 9).unwrap_err().kind()
+        );
+    }
+    #[test]
+    fn read_strings_at_end_of_address_space() {
+        /// Memory that holds an `a` at every address, so strings never end.
+        struct Endless;
+        impl CopyAddress for Endless {
+            fn copy_address(&self, _addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+                buf.fill(b'a');
+                Ok(())
+            }
+        }
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            read_cstring(&Endless, usize::MAX - 40, 64)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            read_wide_string(&Endless, usize::MAX - 5, 8)
+                .unwrap_err()
+                .kind()
+        );
+    }
+    #[test]
+    fn get_pid_of_own_handle() {
+        #[allow(clippy::cast_possible_wrap)]
+        let pid = std::process::id() as Pid;
+        let handle = pid.try_into_process_handle().unwrap();
+        assert!(handle.check_handle());
+        assert_eq!(pid, handle.get_pid().unwrap());
+    }
+}
@@ -1,66 +1,1731 @@
-use libc::{c_void, iovec, pid_t, process_vm_readv, process_vm_writev};
+use libc::{c_void, iovec, pid_t};
+#[cfg(not(target_os = "android"))]
+use libc::{process_vm_readv, process_vm_writev};
+use std::convert::TryInto;
 use std::process::Child;
 
-use super::{CopyAddress, HandleChecker, PutAddress, TryIntoProcessHandle};
+use crate::memory_error::{
+    classified_error, classified_os_error, copy_error, os_error_code, ErrorClass,
+};
+use crate::trace::{trace_read, trace_write};
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+mod watchpoint;
+
+#[cfg(all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64")))]
+pub use watchpoint::{WatchHit, WatchId, WatchKind, WatchSize};
+
+use super::{
+    AccessMode, Allocate, Architecture, ControlProcess, CopyAddress, EnumerateRegions,
+    EnumerateThreads, FindModule, ProcessHandleExt, ProtectMemory, Protection, PutAddress, Region,
+    ThreadContext, ThreadInfo, TryIntoProcessHandle,
+};
 
 /// On Linux a `Pid` is just a `libc::pid_t`.
 pub type Pid = pid_t;
-/// On Linux a `ProcessHandle` is just a `libc::pid_t`.
-pub type ProcessHandle = pid_t;
+/// On Linux a `Tid` is also a `libc::pid_t`, as threads are tasks just like processes.
+pub type Tid = pid_t;
+/// On Linux a `ProcessHandle` is just a `libc::pid_t`, along with the `Architecture` of the
+/// process.
+///
+/// Memory is read and written with `process_vm_readv` and `process_vm_writev`, which keep no
+/// state between calls. Where those are blocked, memory can be accessed through
+/// `/proc/<pid>/mem` instead (see [`ProcessHandle::use_proc_mem`]), which is opened once and
+/// shared by every clone of the handle. Either way a `ProcessHandle` is `Send` and `Sync`, and it
+/// can be used from several threads at once. Handles are compared by their pid and architecture
+/// alone.
+///
+/// The same backend is used on Android. Its C library only has `process_vm_readv` and
+/// `process_vm_writev` from API level 23, so there they are called as system calls directly,
+/// which the kernel has supported since long before API level 21. Where a seccomp filter blocks
+/// them, memory is accessed through `/proc/<pid>/mem` just as on Linux.
+///
+/// [`ProcessHandle::use_proc_mem`]: struct.ProcessHandle.html#method.use_proc_mem
+#[derive(Clone, Debug)]
+pub struct ProcessHandle {
+    pid: Pid,
+    arch: Architecture,
+    mem: std::sync::Arc<ProcMem>,
+}
+
+/// The `/proc/<pid>/mem` backend of a `ProcessHandle`, which is shared between its clones.
+#[derive(Debug, Default)]
+struct ProcMem {
+    /// `/proc/<pid>/mem`, opened the first time it is used.
+    file: std::sync::OnceLock<std::fs::File>,
+    /// Whether memory is accessed through `file` instead of `process_vm_readv` and
+    /// `process_vm_writev`, either because we were asked to or because they were unavailable.
+    enabled: std::sync::atomic::AtomicBool,
+    /// Whether the kernel has been told with `posix_fadvise` that `file` will be read
+    /// sequentially.
+    sequential: std::sync::atomic::AtomicBool,
+}
+
+impl ProcMem {
+    /// Pass on the current scan mode to the kernel for `file`. This is only a hint, so failing
+    /// isn't an error.
+    fn advise(&self, file: &std::fs::File) {
+        use std::os::unix::io::AsRawFd;
+
+        let advice = if self.sequential.load(std::sync::atomic::Ordering::Relaxed) {
+            libc::POSIX_FADV_SEQUENTIAL
+        } else {
+            libc::POSIX_FADV_NORMAL
+        };
+        let _ = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, advice) };
+    }
+}
 
-impl HandleChecker for ProcessHandle {
+impl PartialEq for ProcessHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.pid == other.pid && self.arch == other.arch
+    }
+}
+
+impl Eq for ProcessHandle {}
+
+impl std::hash::Hash for ProcessHandle {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pid.hash(state);
+        self.arch.hash(state);
+    }
+}
+
+/// On Linux the raw form of a `ProcessHandle` is the `Pid`.
+pub type RawProcessHandle = Pid;
+
+/// On Linux the registers of a thread are a `libc::user_regs_struct`, as read with
+/// `ptrace(PTRACE_GETREGSET)`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+pub type RawThreadContext = libc::user_regs_struct;
+/// Registers can't be read on this architecture, so there are none.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub type RawThreadContext = ();
+
+impl ProcessHandle {
+    /// Make a `ProcessHandle` for the process `pid`, with the `Architecture` of the host. As a
+    /// pid isn't owned by anything, this is the same as calling `try_into_process_handle` on it.
     #[must_use]
-    fn check_handle(&self) -> bool {
-        *self != 0
+    pub fn from_raw_handle(pid: RawProcessHandle) -> ProcessHandle {
+        ProcessHandle {
+            pid,
+            arch: Architecture::from_native(),
+            mem: std::sync::Arc::default(),
+        }
     }
+
+    /// Get the `Pid` of the process that the `ProcessHandle` refers to.
     #[must_use]
-    fn null_type() -> Pid {
-        0
+    pub fn into_raw_handle(self) -> RawProcessHandle {
+        self.pid
+    }
+
+    /// Choose whether memory is read and written through `/proc/<pid>/mem`, with `pread` and
+    /// `pwrite`, instead of with `process_vm_readv` and `process_vm_writev`. This is useful in
+    /// locked-down containers where a seccomp filter blocks the system calls but the file can
+    /// still be opened. The file is opened the first time it is used and kept open from then on,
+    /// so it isn't reopened for every access. The setting, and the open file, are shared by every
+    /// clone of the handle.
+    ///
+    /// There is usually no need to call this: if `process_vm_readv` or `process_vm_writev` fail
+    /// with `ENOSYS` or `EPERM`, `/proc/<pid>/mem` is tried instead, and if that works it is used
+    /// from then on.
+    ///
+    /// Once opened, the file keeps referring to the memory of the process as it was then, so if
+    /// the process calls `exec` every access through it fails, and a new handle is needed.
+    pub fn use_proc_mem(&self, enabled: bool) {
+        self.mem
+            .enabled
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Read the auxiliary vector that the kernel passed to the process when it started, from
+    /// `/proc/<pid>/auxv`, as pairs of an `AT_*` key and its value, such as `AT_RANDOM` (the
+    /// address of 16 random bytes) or `AT_SYSINFO_EHDR` (the base address of the vDSO). The
+    /// `AT_NULL` entry that ends the vector isn't included.
+    ///
+    /// The entries are read as words of the `Architecture` of the handle, so for a 32-bit process
+    /// it must be set to `Arch32Bit` with [`ProcessHandleExt::set_arch`].
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read, which needs the same permissions as reading the
+    /// memory of the process, or an error with a `std::io::ErrorKind` of `Unsupported` if the
+    /// `Architecture` is neither 32-bit nor 64-bit.
+    ///
+    /// [`ProcessHandleExt::set_arch`]: trait.ProcessHandleExt.html#tymethod.set_arch
+    pub fn auxv(&self) -> std::io::Result<Vec<(u64, u64)>> {
+        let auxv = std::fs::read(format!("/proc/{}/auxv", self.pid))
+            .map_err(|err| ptrace_error(err, self.pid))?;
+        parse_auxv(&auxv, self.arch)
+    }
+
+    /// Whether memory is accessed through `/proc/<pid>/mem`.
+    fn uses_proc_mem(&self) -> bool {
+        self.mem.enabled.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Get `/proc/<pid>/mem`, opening it if it isn't open yet. It is opened for writing as well as
+    /// reading if we are allowed to.
+    fn proc_mem(&self) -> std::io::Result<&std::fs::File> {
+        if let Some(file) = self.mem.file.get() {
+            return Ok(file);
+        }
+        let path = format!("/proc/{}/mem", self.pid);
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .or_else(|_| std::fs::File::open(&path))
+            .map_err(|err| ptrace_error(err, self.pid))?;
+        let file = self.mem.file.get_or_init(|| file);
+        self.mem.advise(file);
+        Ok(file)
+    }
+
+    /// Read `buf` from `addr` through `/proc/<pid>/mem`. Like `process_vm_readv`, this stops at
+    /// the first byte that can't be read and returns how many bytes were read before it.
+    fn read_proc_mem(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+
+        let mem = self.proc_mem()?;
+        let mut read = 0;
+        while read < buf.len() {
+            match mem.read_at(&mut buf[read..], addr.wrapping_add(read) as u64) {
+                Ok(0) => break,
+                Ok(count) => read += count,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) if read > 0 => break,
+                Err(err) if err.raw_os_error() == Some(libc::EIO) => {
+                    return Err(unreadable_region(addr, buf.len()))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(read)
+    }
+
+    /// Write `buf` to `addr` through `/proc/<pid>/mem`.
+    fn write_proc_mem(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        use std::os::unix::fs::FileExt;
+
+        self.proc_mem()?
+            .write_all_at(buf, addr as u64)
+            .map_err(|err| write_error(err, self.pid, addr, buf.len()))
     }
 }
 
+impl ProcessHandleExt for ProcessHandle {
+    fn check_handle(&self) -> bool {
+        self.pid != 0
+    }
+    fn null_type() -> ProcessHandle {
+        ProcessHandle {
+            pid: 0,
+            arch: Architecture::from_native(),
+            mem: std::sync::Arc::default(),
+        }
+    }
+    /// On Linux a `ProcessHandle` is the `Pid`.
+    fn get_pid(&self) -> std::io::Result<Pid> {
+        Ok(self.pid)
+    }
+    fn set_arch(mut self, arch: Architecture) -> Self {
+        self.arch = arch;
+        self
+    }
+    fn get_arch(&self) -> Architecture {
+        self.arch
+    }
+    fn page_size(&self) -> usize {
+        page_size()
+    }
+    /// `kill` with a signal of 0 checks whether the process exists (it fails with `EPERM` for a
+    /// process we can't signal, which still exists), and the state in `/proc/<pid>/stat` tells
+    /// us whether it has exited but not been reaped yet.
+    fn is_alive(&self) -> bool {
+        if self.pid <= 0 {
+            return false;
+        }
+        if unsafe { libc::kill(self.pid, 0) } == -1
+            && std::io::Error::last_os_error().raw_os_error() != Some(libc::EPERM)
+        {
+            return false;
+        }
+        // The state follows the command name, which is in parentheses and may contain spaces or
+        // parentheses itself.
+        match std::fs::read_to_string(format!("/proc/{}/stat", self.pid)) {
+            Ok(stat) => !matches!(
+                stat.rfind(')')
+                    .and_then(|end| stat[end + 1..].trim_start().chars().next()),
+                Some('Z' | 'X')
+            ),
+            Err(_) => std::path::Path::new(&format!("/proc/{}", self.pid)).exists(),
+        }
+    }
+    /// This is the target of the `/proc/<pid>/exe` link, which ends in `" (deleted)"` if the
+    /// executable has been deleted or replaced since the process started.
+    fn exe_path(&self) -> std::io::Result<std::path::PathBuf> {
+        std::fs::read_link(format!("/proc/{}/exe", self.pid))
+    }
+    /// The arguments are read from `/proc/<pid>/cmdline`, which reflects any changes the process
+    /// has made to them. Kernel threads and zombies have no arguments.
+    fn cmdline(&self) -> std::io::Result<Vec<String>> {
+        std::fs::read(format!("/proc/{}/cmdline", self.pid)).map(|args| crate::split_args(&args))
+    }
+    /// The hint is given for `/proc/<pid>/mem` as soon as it is open, whether or not it is being
+    /// used yet. `process_vm_readv` has no equivalent, so it is ignored there.
+    fn set_scan_mode(&self, enabled: bool) {
+        self.mem
+            .sequential
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+        if let Some(file) = self.mem.file.get() {
+            self.mem.advise(file);
+        }
+    }
+}
+
+/// On Linux there is nothing to open, so any `Pid` can be turned into a `ProcessHandle`.
+impl TryIntoProcessHandle for Pid {
+    fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
+        Ok(ProcessHandle {
+            pid: *self,
+            arch: Architecture::from_native(),
+            mem: std::sync::Arc::default(),
+        })
+    }
+}
+
+/// Access isn't granted per handle on Linux, so `access` is ignored.
+pub(crate) fn open_process(pid: Pid, _access: AccessMode) -> std::io::Result<ProcessHandle> {
+    pid.try_into_process_handle()
+}
+
+/// The size of a page of memory on this system, from `sysconf`.
+pub(crate) fn page_size() -> usize {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    size
+}
+
 /// A `Child` always has a pid, which is all we need on Linux.
 impl TryIntoProcessHandle for Child {
     fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
         #[allow(clippy::cast_possible_wrap)]
-        Ok(self.id() as ProcessHandle)
+        Pid::try_into_process_handle(&(self.id() as Pid))
     }
 }
 
 impl CopyAddress for ProcessHandle {
+    fn get_pointer_width(&self) -> Architecture {
+        self.arch
+    }
+
     fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
-        let local_iov = iovec {
-            iov_base: buf.as_mut_ptr() as *mut c_void,
-            iov_len: buf.len(),
+        let result = match self.copy_address_partial(addr, buf) {
+            Ok(read) if read == buf.len() => Ok(()),
+            Ok(read) => Err(unreadable_region(addr + read, buf.len() - read)),
+            Err(err) => Err(err),
         };
-        let remote_iov = iovec {
-            iov_base: addr as *mut c_void,
-            iov_len: buf.len(),
+        trace_read(self, addr, buf.len(), &result);
+        result
+    }
+
+    /// `process_vm_readv` returns the number of bytes it read, and stops early rather than failing
+    /// when it reaches memory it can't read.
+    ///
+    /// If `process_vm_readv` isn't available (it may be missing from the kernel, or blocked by a
+    /// seccomp filter in a restricted container), the memory is read through `/proc/<pid>/mem`
+    /// instead, or failing that a word at a time with `ptrace`. Reading with `ptrace` briefly
+    /// stops the process while we are attached to it.
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.uses_proc_mem() {
+            return self.read_proc_mem(addr, buf);
+        }
+        let err = match read_vm(self.pid, addr, crate::as_uninit_mut(buf)) {
+            Ok(read) => return Ok(read),
+            Err(err) => err,
         };
-        let result = unsafe { process_vm_readv(*self, &local_iov, 1, &remote_iov, 1, 0) };
-        if result == -1 {
-            Err(std::io::Error::last_os_error())
-        } else {
+        if matches!(err.raw_os_error(), Some(libc::ENOSYS | libc::EPERM)) {
+            if let Ok(read) = self.read_proc_mem(addr, buf) {
+                self.use_proc_mem(true);
+                return Ok(read);
+            }
+            if let Ok(read) = peek_memory(self.pid, addr, buf) {
+                return Ok(read);
+            }
+        }
+        Err(ptrace_error(err, self.pid))
+    }
+
+    /// `process_vm_readv` reads straight into `buf`. Reading through `/proc/<pid>/mem` or
+    /// `ptrace` needs `buf` to be zeroed first.
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        if !self.uses_proc_mem() {
+            match read_vm(self.pid, addr, buf) {
+                Err(err) if matches!(err.raw_os_error(), Some(libc::ENOSYS | libc::EPERM)) => {}
+                Err(err) => return Err(ptrace_error(err, self.pid)),
+                Ok(read) => return Ok(read),
+            }
+        }
+        self.copy_address_partial(addr, crate::init_zeroed(buf))
+    }
+
+    /// `process_vm_readv` takes a list of remote regions, so we can read every request with a
+    /// single system call instead of one per request. Through `/proc/<pid>/mem` each request
+    /// needs a read of its own. Like `copy_address`, if `process_vm_readv` isn't available the
+    /// requests are read through `/proc/<pid>/mem` instead, which is used from then on.
+    fn copy_addresses(&self, requests: &mut [(usize, &mut [u8])]) -> std::io::Result<()> {
+        let one_at_a_time = |requests: &mut [(usize, &mut [u8])]| {
+            for (addr, buf) in requests.iter_mut() {
+                self.copy_address(*addr, buf)?;
+            }
             Ok(())
+        };
+        if self.uses_proc_mem() {
+            return one_at_a_time(requests);
+        }
+        let regions: Vec<(usize, usize)> = requests
+            .iter()
+            .map(|(addr, buf)| (*addr, buf.len()))
+            .collect();
+        let mut scratch = vec![0_u8; regions.iter().map(|(_, len)| len).sum()];
+        let results = read_regions(self.pid, &regions, &mut scratch);
+        if results.iter().any(|result| {
+            result
+                .as_ref()
+                .is_err_and(|err| matches!(os_error_code(err), Some(libc::ENOSYS | libc::EPERM)))
+        }) {
+            return one_at_a_time(requests);
+        }
+
+        let mut copied = scratch.as_slice();
+        for ((_, buf), result) in requests.iter_mut().zip(results) {
+            result?;
+            let (region, rest) = copied.split_at(buf.len());
+            buf.copy_from_slice(region);
+            copied = rest;
+        }
+        Ok(())
+    }
+}
+
+/// Find the pid of every process whose executable is called `name`, by looking through `/proc`.
+///
+/// The executable is found from the `/proc/<pid>/exe` link, which can only be read for processes
+/// we could access anyway, so for any other process its `comm` is used instead.
+pub(crate) fn processes_named(name: &str) -> std::io::Result<Vec<Pid>> {
+    let mut pids = Vec::new();
+    for entry in std::fs::read_dir("/proc")? {
+        let Some(pid) = entry?.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        let matches = match std::fs::read_link(format!("/proc/{pid}/exe")) {
+            // The link has " (deleted)" appended if the executable has been replaced since the
+            // process started.
+            Ok(exe) => crate::module_name_matches(
+                exe.to_string_lossy().trim_end_matches(" (deleted)"),
+                name,
+            ),
+            Err(_) => std::fs::read_to_string(format!("/proc/{pid}/comm"))
+                .is_ok_and(|comm| crate::comm_matches(comm.trim_end_matches('\n'), name, 15)),
+        };
+        if matches {
+            pids.push(pid);
+        }
+    }
+    Ok(pids)
+}
+
+/// Linux has no way to call `mprotect` on behalf of another process. Doing so would require
+/// attaching to the process with `ptrace` and making it run the system call itself, so this always
+/// returns an `Unsupported` error.
+///
+/// Writes through `/proc/<pid>/mem` ignore page protections, so code can usually be patched
+/// without changing its protection first, which is what `write_protected` does.
+impl ProtectMemory for ProcessHandle {
+    fn set_protection(
+        &self,
+        _addr: usize,
+        _size: usize,
+        _prot: Protection,
+    ) -> std::io::Result<Protection> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Changing the protection of another process's memory requires ptrace on Linux",
+        ))
+    }
+
+    fn write_protected(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        let result = self.write_proc_mem(addr, buf);
+        trace_write(self, addr, buf.len(), &result);
+        result
+    }
+}
+
+/// Like changing memory protection, Linux has no way to `mmap` memory into another process without
+/// using `ptrace` to make the process do it itself, so this always returns an `Unsupported` error.
+impl Allocate for ProcessHandle {
+    fn allocate(&self, _size: usize, _prot: Protection) -> std::io::Result<usize> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Allocating memory in another process requires ptrace on Linux",
+        ))
+    }
+
+    fn free(&self, _addr: usize, _size: usize) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Freeing memory in another process requires ptrace on Linux",
+        ))
+    }
+}
+
+/// On Linux the memory map of a process is available from `/proc/<pid>/maps`.
+impl EnumerateRegions for ProcessHandle {
+    fn regions(&self) -> std::io::Result<Vec<Region>> {
+        self.regions_iter()?.collect()
+    }
+
+    /// `/proc/<pid>/maps` is read and parsed a line at a time as the iterator is advanced, and a
+    /// malformed line is an error with a `std::io::ErrorKind` of `InvalidData`.
+    fn regions_iter(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<Region>> + '_> {
+        Ok(maps_entries(self.pid)?.map(|entry| entry.map(|(region, _)| region)))
+    }
+
+    /// This is the first mapping whose pathname in `/proc/<pid>/maps` matches `name`. For a
+    /// mapped file, that is the mapping of the start of the file.
+    fn find_region_by_name(&self, name: &str) -> std::io::Result<Option<Region>> {
+        crate::region_named(maps_entries(self.pid)?, name)
+    }
+}
+
+/// Modules are found by looking for the mappings in `/proc/<pid>/maps` whose pathname matches
+/// the module's file name, or its file stem.
+impl FindModule for ProcessHandle {
+    fn get_module_base(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self.pid, name).map(|(base, _)| base)
+    }
+
+    fn get_module_size(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self.pid, name).map(|(_, size)| size)
+    }
+
+    /// The executable is the file that `/proc/<pid>/exe` links to, and its base address is the
+    /// start of its first mapping.
+    fn main_module_base(&self) -> std::io::Result<usize> {
+        let exe = self.exe_path()?;
+        read_maps(self.pid)?
+            .into_iter()
+            .filter(|(_, path)| std::path::Path::new(path) == exe)
+            .map(|(region, _)| region.base)
+            .min()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("The executable of process {} isn't mapped", self.pid),
+                )
+            })
+    }
+
+    /// Only mappings of files count as modules, so this returns `None` for pseudo-mappings such
+    /// as `[heap]` as well as for anonymous memory.
+    fn to_module_relative(&self, addr: usize) -> std::io::Result<Option<(String, usize)>> {
+        Ok(crate::module_relative(&read_maps(self.pid)?, addr))
+    }
+}
+
+/// The process is stopped and continued with `SIGSTOP` and `SIGCONT`, just like job control in a
+/// shell.
+impl ControlProcess for ProcessHandle {
+    fn suspend(&self) -> std::io::Result<()> {
+        send_signal(self.pid, libc::SIGSTOP)
+    }
+
+    fn resume(&self) -> std::io::Result<()> {
+        send_signal(self.pid, libc::SIGCONT)
+    }
+}
+
+/// Each thread has a directory in `/proc/<pid>/task`, named after its tid. The main thread's tid
+/// is the same as the pid.
+impl EnumerateThreads for ProcessHandle {
+    fn threads(&self) -> std::io::Result<Vec<ThreadInfo>> {
+        let mut threads = Vec::new();
+        for entry in std::fs::read_dir(format!("/proc/{}/task", self.pid))? {
+            if let Some(tid) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            {
+                threads.push(ThreadInfo {
+                    tid,
+                    is_main: tid == self.pid,
+                });
+            }
         }
+        Ok(threads)
     }
+
+    /// The registers are read with `ptrace(PTRACE_GETREGSET)`, attaching to just the thread
+    /// `tid` for as long as it takes.
+    fn thread_context(&self, tid: Tid) -> std::io::Result<ThreadContext> {
+        if !std::path::Path::new(&format!("/proc/{}/task/{tid}", self.pid)).exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Process {} has no thread {tid}", self.pid),
+            ));
+        }
+        while_attached(tid, || thread_registers(tid))
+    }
+}
+
+/// Read the registers of the thread `tid`, which we must be attached to.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+fn thread_registers(tid: Tid) -> std::io::Result<ThreadContext> {
+    /// The register set holding the general purpose registers.
+    const NT_PRSTATUS: usize = 1;
+
+    let mut regs: libc::user_regs_struct = unsafe { std::mem::zeroed() };
+    let mut iov = iovec {
+        iov_base: (&raw mut regs).cast(),
+        iov_len: std::mem::size_of_val(&regs),
+    };
+    if unsafe { libc::ptrace(libc::PTRACE_GETREGSET, tid, NT_PRSTATUS, &raw mut iov) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[cfg(target_arch = "x86")]
+    let (instruction_pointer, stack_pointer) = (regs.eip, regs.esp);
+    #[cfg(target_arch = "x86_64")]
+    let (instruction_pointer, stack_pointer) = (regs.rip, regs.rsp);
+    #[cfg(target_arch = "aarch64")]
+    let (instruction_pointer, stack_pointer) = (regs.pc, regs.sp);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok(ThreadContext {
+        instruction_pointer: instruction_pointer as usize,
+        stack_pointer: stack_pointer as usize,
+        raw: regs,
+    })
+}
+
+/// Registers can't be read on this architecture.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+fn thread_registers(_tid: Tid) -> std::io::Result<ThreadContext> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Reading the registers of a thread isn't supported on this architecture",
+    ))
 }
 
+/// Send `signal` to `pid` with `kill`.
+fn send_signal(pid: Pid, signal: libc::c_int) -> std::io::Result<()> {
+    if unsafe { libc::kill(pid, signal) } == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Find the base address and size of the module `name` from all of the mappings of its file. The
+/// size covers everything from the first mapping to the end of the last.
+fn find_module(pid: Pid, name: &str) -> std::io::Result<(usize, usize)> {
+    let (base, end) = read_maps(pid)?
+        .into_iter()
+        .filter(|(_, path)| crate::module_name_matches(path, name))
+        .fold(None, |bounds, (region, _)| match bounds {
+            None => Some((region.base, region.end())),
+            Some((base, end)) => Some((region.base.min(base), region.end().max(end))),
+        })
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No module named {name:?} is loaded in process {pid}"),
+            )
+        })?;
+    Ok((base, end - base))
+}
+
+/// Bionic only has a `process_vm_readv` wrapper from API level 23, so make the system call
+/// ourselves. Like the wrapper, this returns -1 and sets `errno` on failure.
+#[cfg(target_os = "android")]
+unsafe fn process_vm_readv(
+    pid: pid_t,
+    local_iov: *const iovec,
+    liovcnt: libc::c_ulong,
+    remote_iov: *const iovec,
+    riovcnt: libc::c_ulong,
+    flags: libc::c_ulong,
+) -> isize {
+    // A `c_long` is as wide as a pointer on Android.
+    #[allow(clippy::cast_possible_truncation)]
+    let result = libc::syscall(
+        libc::SYS_process_vm_readv,
+        pid,
+        local_iov,
+        liovcnt,
+        remote_iov,
+        riovcnt,
+        flags,
+    ) as isize;
+    result
+}
+
+/// The same as `process_vm_readv`, for `process_vm_writev`.
+#[cfg(target_os = "android")]
+unsafe fn process_vm_writev(
+    pid: pid_t,
+    local_iov: *const iovec,
+    liovcnt: libc::c_ulong,
+    remote_iov: *const iovec,
+    riovcnt: libc::c_ulong,
+    flags: libc::c_ulong,
+) -> isize {
+    // A `c_long` is as wide as a pointer on Android.
+    #[allow(clippy::cast_possible_truncation)]
+    let result = libc::syscall(
+        libc::SYS_process_vm_writev,
+        pid,
+        local_iov,
+        liovcnt,
+        remote_iov,
+        riovcnt,
+        flags,
+    ) as isize;
+    result
+}
+
+/// Read as much of the memory at `addr` of `pid` into `buf` as possible with a single call to
+/// `process_vm_readv`, returning the number of bytes read, which have then been initialised. The
+/// error is returned as-is from the system call.
+fn read_vm(pid: Pid, addr: usize, buf: &mut [std::mem::MaybeUninit<u8>]) -> std::io::Result<usize> {
+    let local_iov = iovec {
+        iov_base: buf.as_mut_ptr().cast::<c_void>(),
+        iov_len: buf.len(),
+    };
+    let remote_iov = iovec {
+        iov_base: addr as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let result =
+        unsafe { process_vm_readv(pid, &raw const local_iov, 1, &raw const remote_iov, 1, 0) };
+    if result == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        #[allow(clippy::cast_sign_loss)]
+        Ok(result as usize)
+    }
+}
+
+/// Parse the contents of `/proc/<pid>/auxv`, a list of key and value pairs of words of `arch`,
+/// stopping at the `AT_NULL` key.
+fn parse_auxv(auxv: &[u8], arch: Architecture) -> std::io::Result<Vec<(u64, u64)>> {
+    let word = arch as usize;
+    let read_word: fn(&[u8]) -> u64 = match word {
+        4 => |bytes| u32::from_ne_bytes(bytes.try_into().unwrap()).into(),
+        8 => |bytes| u64::from_ne_bytes(bytes.try_into().unwrap()),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!(
+                    "Can't read the auxiliary vector of a {}-bit process",
+                    word * 8
+                ),
+            ))
+        }
+    };
+    Ok(auxv
+        .chunks_exact(word * 2)
+        .map(|entry| (read_word(&entry[..word]), read_word(&entry[word..])))
+        .take_while(|&(key, _)| key != 0)
+        .collect())
+}
+
+/// Read and parse `/proc/<pid>/maps`, returning each region along with its pathname (which is
+/// empty for anonymous mappings).
+fn read_maps(pid: Pid) -> std::io::Result<Vec<(Region, String)>> {
+    maps_entries(pid)?.collect()
+}
+
+/// Open `/proc/<pid>/maps`, returning an iterator that reads and parses a line of it at a time.
+fn maps_entries(
+    pid: Pid,
+) -> std::io::Result<impl Iterator<Item = std::io::Result<(Region, String)>>> {
+    use std::io::BufRead;
+
+    let maps = std::fs::File::open(format!("/proc/{pid}/maps"))?;
+    Ok(std::io::BufReader::new(maps).lines().map(move |line| {
+        let line = line?;
+        parse_maps_line(&line)
+            .map(|(region, path)| (region, path.to_owned()))
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Malformed line in /proc/{pid}/maps: {line:?}"),
+                )
+            })
+    }))
+}
+
+/// Parse a single line of `/proc/<pid>/maps`, which looks like
+/// `7f1c6f5e2000-7f1c6f5e4000 rw-p 00000000 00:00 0    [stack]`.
+///
+/// The first five fields are separated by single spaces, and are followed by padding and then
+/// the pathname, which may itself contain spaces.
+fn parse_maps_line(line: &str) -> Option<(Region, &str)> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let base = usize::from_str_radix(start, 16).ok()?;
+    let end = usize::from_str_radix(end, 16).ok()?;
+    let perms = fields.next()?.as_bytes();
+    if perms.len() < 4 || end < base || fields.by_ref().take(3).count() != 3 {
+        return None;
+    }
+    let path = fields.next().unwrap_or_default().trim_start();
+    let mut protection = Protection::empty();
+    protection.set(Protection::READ, perms[0] == b'r');
+    protection.set(Protection::WRITE, perms[1] == b'w');
+    protection.set(Protection::EXECUTE, perms[2] == b'x');
+    Some((
+        Region {
+            base,
+            size: end - base,
+            protection,
+            is_private: perms[3] == b'p',
+        },
+        path,
+    ))
+}
+
+/// Read a batch of `(address, length)` regions from `pid` into `buf`, one after the other.
+///
+/// The regions are handed to `process_vm_readv` up to `UIO_MAXIOV` at a time, so a batch
+/// typically only costs a single system call. The kernel stops a transfer at the first region it
+/// cannot read and reports how many bytes it read before that, so when that happens we mark the
+/// region as failed and carry on reading from the region after it. This means that one unmapped
+/// region does not prevent the valid regions around it from being read.
+///
+/// `buf` must be exactly as long as the sum of the region lengths. The returned `Vec` has one
+/// result per region; the section of `buf` belonging to a failed region is left unspecified.
+fn read_regions(pid: Pid, regions: &[(usize, usize)], buf: &mut [u8]) -> Vec<std::io::Result<()>> {
+    debug_assert_eq!(buf.len(), regions.iter().map(|(_, len)| len).sum::<usize>());
+    let mut results = Vec::with_capacity(regions.len());
+    let mut buf = buf;
+    let mut index = 0;
+    while index < regions.len() {
+        #[allow(clippy::cast_sign_loss)]
+        let batch = &regions[index..regions.len().min(index + libc::UIO_MAXIOV as usize)];
+        let mut remaining = &mut *buf;
+        let mut local_iov = Vec::with_capacity(batch.len());
+        let mut remote_iov = Vec::with_capacity(batch.len());
+        for &(addr, len) in batch {
+            let (local, rest) = std::mem::take(&mut remaining).split_at_mut(len);
+            local_iov.push(iovec {
+                iov_base: local.as_mut_ptr().cast::<c_void>(),
+                iov_len: len,
+            });
+            remote_iov.push(iovec {
+                iov_base: addr as *mut c_void,
+                iov_len: len,
+            });
+            remaining = rest;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let result = unsafe {
+            process_vm_readv(
+                pid,
+                local_iov.as_ptr(),
+                local_iov.len() as _,
+                remote_iov.as_ptr(),
+                remote_iov.len() as _,
+                0,
+            )
+        };
+        // When nothing could be read the kernel reports the error directly, otherwise it tells us
+        // how far it got before it hit a region it couldn't read.
+        #[allow(clippy::cast_sign_loss)]
+        let mut read = if result == -1 {
+            let code = std::io::Error::last_os_error().raw_os_error();
+            if code != Some(libc::EFAULT) {
+                // Anything other than a bad address (such as a missing process or a lack of
+                // permissions) applies to every region, so there is no point trying the rest.
+                let err = ptrace_error(
+                    std::io::Error::from_raw_os_error(code.unwrap_or(libc::EIO)),
+                    pid,
+                );
+                let copies: Vec<_> = (index + 1..regions.len())
+                    .map(|_| Err(copy_error(&err)))
+                    .collect();
+                results.push(Err(err));
+                results.extend(copies);
+                break;
+            }
+            0
+        } else {
+            result as usize
+        };
+
+        for &(addr, len) in batch {
+            let (_, rest) = std::mem::take(&mut buf).split_at_mut(len);
+            buf = rest;
+            index += 1;
+            if read >= len {
+                read -= len;
+                results.push(Ok(()));
+            } else {
+                results.push(Err(unreadable_region(addr, len)));
+                break;
+            }
+        }
+    }
+    results
+}
+
+/// The error used for a region that `process_vm_readv` couldn't read. The kernel reports these as
+/// `EFAULT`, which the standard library doesn't categorise, so we give it a more meaningful kind.
+fn unreadable_region(addr: usize, len: usize) -> std::io::Error {
+    classified_error(
+        std::io::ErrorKind::InvalidInput,
+        ErrorClass::AddressUnreadable,
+        format!("Unable to read {len} bytes at {addr:#x} (bad address)"),
+    )
+}
+
+/// Attach to the thread `tid` with `ptrace`, which stops it, call `f`, and detach again, which
+/// lets it carry on. Attaching to a process's pid only attaches to its main thread.
+fn while_attached<R>(tid: Tid, f: impl FnOnce() -> std::io::Result<R>) -> std::io::Result<R> {
+    if unsafe { libc::ptrace(libc::PTRACE_ATTACH, tid, 0, 0) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut status = 0;
+    if unsafe { libc::waitpid(tid, &raw mut status, libc::__WALL) } == -1 {
+        let err = std::io::Error::last_os_error();
+        let _ = unsafe { libc::ptrace(libc::PTRACE_DETACH, tid, 0, 0) };
+        return Err(err);
+    }
+    let result = f();
+    let _ = unsafe { libc::ptrace(libc::PTRACE_DETACH, tid, 0, 0) };
+    result
+}
+
+/// Read `buf` from `addr` in `pid` with `PTRACE_PEEKDATA`, for when `process_vm_readv` can't be
+/// used. We attach to the process for the duration of the read, which stops it.
+fn peek_memory(pid: Pid, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+    while_attached(pid, || peek_words(pid, addr, buf))
+}
+
+/// Read `buf` from `addr` in `pid`, which we must be attached to, with `PTRACE_PEEKDATA`.
+fn peek_words(pid: Pid, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+    read_words(addr, buf, |word| {
+        // Any word is a valid result, so the only way to tell that the read failed is `errno`.
+        unsafe {
+            #[cfg(not(target_os = "android"))]
+            let errno = libc::__errno_location();
+            #[cfg(target_os = "android")]
+            let errno = libc::__errno();
+            *errno = 0;
+            let value = libc::ptrace(libc::PTRACE_PEEKDATA, pid, word, 0);
+            let err = std::io::Error::last_os_error();
+            if value == -1 && err.raw_os_error() != Some(0) {
+                Err(err)
+            } else {
+                Ok(value.to_ne_bytes())
+            }
+        }
+    })
+}
+
+/// Fill `buf` with the memory at `addr` using `read_word`, which reads the word at a word-aligned
+/// address. Reads that only work a word at a time (like `PTRACE_PEEKDATA`) may fail or be slow on
+/// unaligned addresses, so we read every aligned word that overlaps `addr..addr + buf.len()` and
+/// copy the bytes we want out of each one. Neither `addr` nor the length of `buf` need to be
+/// aligned.
+///
+/// Like `process_vm_readv`, this stops at the first word that can't be read and returns how many
+/// bytes were read before it, failing only if nothing could be read.
+fn read_words<F>(addr: usize, buf: &mut [u8], mut read_word: F) -> std::io::Result<usize>
+where
+    F: FnMut(usize) -> std::io::Result<[u8; std::mem::size_of::<libc::c_long>()]>,
+{
+    const WORD: usize = std::mem::size_of::<libc::c_long>();
+
+    let mut read = 0;
+    while read < buf.len() {
+        let start = addr.wrapping_add(read);
+        let skip = start % WORD;
+        let word = match read_word(start - skip) {
+            Ok(word) => word,
+            Err(_) if read > 0 => break,
+            Err(_) => return Err(unreadable_region(addr, buf.len())),
+        };
+        let len = (WORD - skip).min(buf.len() - read);
+        buf[read..read + len].copy_from_slice(&word[skip..skip + len]);
+        read += len;
+    }
+    Ok(read)
+}
+
+/// Writes use `process_vm_writev`. If that isn't available or permitted (for instance because of
+/// Yama's `ptrace_scope` or a seccomp filter), we fall back to writing through `/proc/<pid>/mem`,
+/// which some setups allow instead. Once that has worked it is used from then on, as it is if
+/// [`ProcessHandle::use_proc_mem`] was called.
+///
+/// [`ProcessHandle::use_proc_mem`]: struct.ProcessHandle.html#method.use_proc_mem
 impl PutAddress for ProcessHandle {
     fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
-        let local_iov = iovec {
-            iov_base: buf.as_ptr() as *mut c_void,
-            iov_len: buf.len(),
+        let result = write_memory(self, addr, buf);
+        trace_write(self, addr, buf.len(), &result);
+        result
+    }
+
+    /// `process_vm_writev` takes a list of remote regions, so we can perform every write with a
+    /// single system call (per `UIO_MAXIOV` writes) instead of one per write.
+    fn put_addresses(&self, writes: &[(usize, &[u8])]) -> std::io::Result<()> {
+        if self.uses_proc_mem() {
+            for (addr, buf) in writes {
+                self.write_proc_mem(*addr, buf)?;
+            }
+            return Ok(());
+        }
+        #[allow(clippy::cast_sign_loss)]
+        for batch in writes.chunks(libc::UIO_MAXIOV as usize) {
+            let local_iovs: Vec<iovec> = batch
+                .iter()
+                .map(|(_, buf)| iovec {
+                    iov_base: buf.as_ptr() as *mut c_void,
+                    iov_len: buf.len(),
+                })
+                .collect();
+            let remote_iovs: Vec<iovec> = batch
+                .iter()
+                .map(|(addr, buf)| iovec {
+                    iov_base: *addr as *mut c_void,
+                    iov_len: buf.len(),
+                })
+                .collect();
+            #[allow(clippy::cast_possible_truncation)]
+            let result = unsafe {
+                process_vm_writev(
+                    self.pid,
+                    local_iovs.as_ptr(),
+                    local_iovs.len() as libc::c_ulong,
+                    remote_iovs.as_ptr(),
+                    remote_iovs.len() as libc::c_ulong,
+                    0,
+                )
+            };
+            if result == -1 {
+                let err = std::io::Error::last_os_error();
+                if matches!(err.raw_os_error(), Some(libc::ENOSYS | libc::EPERM)) {
+                    for (addr, buf) in batch {
+                        self.write_proc_mem(*addr, buf)?;
+                    }
+                    self.use_proc_mem(true);
+                    continue;
+                }
+                let (addr, buf) = batch[0];
+                return Err(write_error(err, self.pid, addr, buf.len()));
+            }
+
+            // Like `process_vm_readv`, a short count means writing stopped at the first region
+            // that couldn't be written to.
+            #[allow(clippy::cast_sign_loss)]
+            let mut written = result as usize;
+            for (addr, buf) in batch {
+                if written < buf.len() {
+                    return Err(unwritable_region(addr + written, buf.len() - written));
+                }
+                written -= buf.len();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `buf` to `addr` in the process with `process_vm_writev`, falling back to
+/// `/proc/<pid>/mem`.
+fn write_memory(handle: &ProcessHandle, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+    if handle.uses_proc_mem() {
+        return handle.write_proc_mem(addr, buf);
+    }
+    let pid = handle.pid;
+    let local_iov = iovec {
+        iov_base: buf.as_ptr() as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let remote_iov = iovec {
+        iov_base: addr as *mut c_void,
+        iov_len: buf.len(),
+    };
+    let result =
+        unsafe { process_vm_writev(pid, &raw const local_iov, 1, &raw const remote_iov, 1, 0) };
+    if result == -1 {
+        let err = std::io::Error::last_os_error();
+        return if matches!(err.raw_os_error(), Some(libc::ENOSYS | libc::EPERM)) {
+            let result = handle.write_proc_mem(addr, buf);
+            if result.is_ok() {
+                handle.use_proc_mem(true);
+            }
+            result
+        } else {
+            Err(write_error(err, pid, addr, buf.len()))
         };
-        let remote_iov = iovec {
-            iov_base: addr as *mut c_void,
-            iov_len: buf.len(),
+    }
+    #[allow(clippy::cast_sign_loss)]
+    let written = result as usize;
+    if written == buf.len() {
+        Ok(())
+    } else {
+        Err(unwritable_region(addr + written, buf.len() - written))
+    }
+}
+
+/// Explain `EPERM` and `EACCES` errors from accessing the memory of `pid` when they are likely to
+/// be caused by Yama's `ptrace_scope`, which stops us from accessing processes that aren't our
+/// children unless we have `CAP_SYS_PTRACE`, or by an enforcing `/sys/fs/selinux` policy, which
+/// on Android stops apps from accessing any process but their own. The settings are only read
+/// once something has already failed, so they cost nothing on success.
+fn ptrace_error(err: std::io::Error, pid: Pid) -> std::io::Error {
+    if !matches!(err.raw_os_error(), Some(libc::EPERM | libc::EACCES)) {
+        return err;
+    }
+    let scope = std::fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()
+        .and_then(|scope| scope.trim().parse().ok());
+    let message = if let Some(hint) = scope.and_then(ptrace_scope_hint) {
+        format!(
+            "Yama's ptrace_scope is {} ({hint})",
+            scope.unwrap_or_default()
+        )
+    } else {
+        let enforce = std::fs::read_to_string("/sys/fs/selinux/enforce").ok();
+        match selinux_hint(enforce.as_deref().map(str::trim)) {
+            Some(hint) => hint.to_owned(),
+            None => return err,
+        }
+    };
+    classified_os_error(
+        &err,
+        std::io::ErrorKind::PermissionDenied,
+        ErrorClass::PermissionDenied,
+        format!("Permission denied accessing the memory of process {pid}: {message}"),
+    )
+}
+
+/// Explain a permission error that the security policy may be the cause of, given the contents of
+/// `/sys/fs/selinux/enforce`, if it could be read. Android always enforces its policy, but doesn't
+/// let apps read the file.
+fn selinux_hint(enforce: Option<&str>) -> Option<&'static str> {
+    match enforce {
+        Some("1") => Some(
+            "SELinux is enforcing and may be denying access; check the audit log for denials, \
+             or run in a domain that is allowed to access the process",
+        ),
+        None if cfg!(target_os = "android") => Some(
+            "SELinux is denying access; only processes of the same app can be accessed, unless \
+             running as root in an unrestricted domain",
+        ),
+        _ => None,
+    }
+}
+
+/// Explain what a Yama `ptrace_scope` value allows, and how to get access anyway. Scope 0 allows
+/// access to any process with the same uid, so it can't be the cause of a permission error.
+fn ptrace_scope_hint(scope: u8) -> Option<&'static str> {
+    match scope {
+        1 => Some(
+            "only descendants can be accessed; have the target allow us with \
+             prctl(PR_SET_PTRACER), run with CAP_SYS_PTRACE, or set \
+             /proc/sys/kernel/yama/ptrace_scope to 0",
+        ),
+        2 => Some("only processes with CAP_SYS_PTRACE can access others; run with CAP_SYS_PTRACE"),
+        3 => Some("no process can access others; this can't be changed without rebooting"),
+        _ => None,
+    }
+}
+
+/// Give the errors from writing `len` bytes at `addr` in `pid` a more meaningful kind, so that
+/// callers can tell a bad address from a missing process or a lack of permissions.
+/// `PermissionDenied` errors (`EPERM` and `EACCES`) are already categorised by the standard
+/// library.
+fn write_error(err: std::io::Error, pid: Pid, addr: usize, len: usize) -> std::io::Error {
+    match err.raw_os_error() {
+        Some(libc::EFAULT | libc::EIO) => classified_os_error(
+            &err,
+            std::io::ErrorKind::InvalidInput,
+            ErrorClass::AddressUnreadable,
+            format!("Unable to write {len} bytes at {addr:#x} (bad address)"),
+        ),
+        Some(libc::ESRCH) => classified_os_error(
+            &err,
+            std::io::ErrorKind::NotFound,
+            ErrorClass::ProcessGone,
+            format!("No process with pid {pid}"),
+        ),
+        _ => err,
+    }
+}
+
+/// The error used for a region that `process_vm_writev` couldn't write to, which like
+/// `unreadable_region` the kernel reports as `EFAULT`.
+fn unwritable_region(addr: usize, len: usize) -> std::io::Error {
+    classified_error(
+        std::io::ErrorKind::InvalidInput,
+        ErrorClass::AddressUnreadable,
+        format!("Unable to write {len} bytes at {addr:#x} (bad address)"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn explain_ptrace_scope() {
+        assert_eq!(ptrace_scope_hint(0), None);
+        assert!(ptrace_scope_hint(1).unwrap().contains("PR_SET_PTRACER"));
+        assert!(ptrace_scope_hint(2).unwrap().contains("CAP_SYS_PTRACE"));
+        assert!(ptrace_scope_hint(3).is_some());
+        let err = ptrace_error(std::io::Error::from_raw_os_error(libc::ESRCH), 1);
+        assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+    }
+    #[test]
+    fn explain_selinux() {
+        assert!(selinux_hint(Some("1")).unwrap().contains("SELinux"));
+        assert_eq!(selinux_hint(Some("0")), None);
+        assert_eq!(selinux_hint(None).is_some(), cfg!(target_os = "android"));
+    }
+    #[test]
+    fn read_own_auxv() {
+        /// The `AT_PAGESZ` key, whose value is the page size.
+        const AT_PAGESZ: u64 = 6;
+
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let auxv = handle.auxv().unwrap();
+        assert!(auxv.contains(&(AT_PAGESZ, page_size() as u64)));
+
+        let mut words = Vec::new();
+        for word in [6_u32, 4096, 25, 0x1234, 0, 0, 7, 8] {
+            words.extend_from_slice(&word.to_ne_bytes());
+        }
+        assert_eq!(
+            parse_auxv(&words, Architecture::Arch32Bit).unwrap(),
+            [(6, 4096), (25, 0x1234)]
+        );
+    }
+    #[test]
+    fn copy_address_partial_stops_at_unmapped_page() {
+        let page = page_size();
+        let pages = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                page * 2,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
         };
-        let result = unsafe { process_vm_writev(*self, &local_iov, 1, &remote_iov, 1, 0) };
-        if result == -1 {
-            Err(std::io::Error::last_os_error())
-        } else {
-            Ok(())
+        assert_ne!(libc::MAP_FAILED, pages);
+        let base = pages as usize;
+        unsafe {
+            std::ptr::write_bytes(pages.cast::<u8>(), 0xAB, page);
+            assert_eq!(0, libc::munmap((base + page) as *mut c_void, page));
         }
+
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut buf = vec![0_u8; 16];
+        let start = base + page - 8;
+        assert_eq!(8, handle.copy_address_partial(start, &mut buf).unwrap());
+        assert_eq!([0xAB; 8], buf[..8]);
+        assert_eq!(
+            std::io::ErrorKind::InvalidInput,
+            handle.copy_address(start, &mut buf).unwrap_err().kind()
+        );
+        // A region listed before its second page was unmapped is read up to the unmapped page.
+        let stale = Region {
+            base,
+            size: page * 2,
+            protection: Protection::READ | Protection::WRITE,
+            is_private: true,
+        };
+        assert_eq!(handle.read_region(&stale).unwrap(), vec![0xAB; page]);
+        unsafe {
+            assert_eq!(0, libc::munmap(pages, page));
+        }
+    }
+    #[test]
+    fn suspend_and_resume_child() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        // The third field of `/proc/<pid>/stat` is the state of the process, which is `T` while
+        // it is stopped.
+        let state = || {
+            let stat = std::fs::read_to_string(format!("/proc/{}/stat", child.id())).unwrap();
+            stat.rsplit(") ").next().unwrap().chars().next().unwrap()
+        };
+        let wait_for = |expected: bool| {
+            for _ in 0..100 {
+                if (state() == 'T') == expected {
+                    return true;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            false
+        };
+
+        handle.suspend().unwrap();
+        assert!(wait_for(true));
+        handle.resume().unwrap();
+        assert!(wait_for(false));
+        assert!(handle.suspended(|| wait_for(true)).unwrap());
+        assert!(wait_for(false));
+
+        child.kill().unwrap();
+        let _ = child.wait().unwrap();
+    }
+
+    #[test]
+    fn peek_unaligned_value() {
+        const WORD: usize = std::mem::size_of::<libc::c_long>();
+
+        let words = [
+            0x0706_0504_0302_0100_u64.to_le(),
+            0x0f0e_0d0c_0b0a_0908_u64.to_le(),
+        ];
+        let base = words.as_ptr() as usize;
+        let read_word = |word: usize| -> std::io::Result<[u8; WORD]> {
+            assert_eq!(word % WORD, 0);
+            Ok(unsafe { (word as *const [u8; WORD]).read() })
+        };
+        let mut value = [0_u8; 8];
+        assert_eq!(read_words(base + 3, &mut value, read_word).unwrap(), 8);
+        assert_eq!(u64::from_le_bytes(value), 0x0a09_0807_0605_0403);
+        let mut short = [0_u8; 3];
+        assert_eq!(read_words(base + 1, &mut short, read_word).unwrap(), 3);
+        assert_eq!(short, [1, 2, 3]);
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        let region = handle
+            .regions()
+            .unwrap()
+            .into_iter()
+            .find(|region| region.protection.contains(Protection::READ))
+            .unwrap();
+        let mut expected = [0_u8; 8];
+        handle.copy_address(region.base + 1, &mut expected).unwrap();
+        let mut value = [0_u8; 8];
+        assert_eq!(
+            peek_memory(handle.pid, region.base + 1, &mut value).unwrap(),
+            8
+        );
+        assert_eq!(u64::from_ne_bytes(value), u64::from_ne_bytes(expected));
+
+        child.kill().unwrap();
+        let _ = child.wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn copy_addresses_without_process_vm_readv() {
+        let values = [0x1122_3344_u32, 0x5566_7788];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let reader = handle.clone();
+        // A seccomp filter only applies to the thread that installs it, so block
+        // `process_vm_readv` on a thread of its own, the way a container's filter would.
+        std::thread::spawn(move || {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let mut filter = [
+                libc::sock_filter {
+                    code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+                    jt: 0,
+                    jf: 0,
+                    k: 0,
+                },
+                libc::sock_filter {
+                    code: (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16,
+                    jt: 0,
+                    jf: 1,
+                    k: libc::SYS_process_vm_readv as u32,
+                },
+                libc::sock_filter {
+                    code: (libc::BPF_RET | libc::BPF_K) as u16,
+                    jt: 0,
+                    jf: 0,
+                    k: libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32,
+                },
+                libc::sock_filter {
+                    code: (libc::BPF_RET | libc::BPF_K) as u16,
+                    jt: 0,
+                    jf: 0,
+                    k: libc::SECCOMP_RET_ALLOW,
+                },
+            ];
+            let program = libc::sock_fprog {
+                len: 4,
+                filter: filter.as_mut_ptr(),
+            };
+            unsafe {
+                assert_eq!(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0), 0);
+                assert_eq!(
+                    libc::prctl(
+                        libc::PR_SET_SECCOMP,
+                        libc::SECCOMP_MODE_FILTER,
+                        &raw const program,
+                    ),
+                    0
+                );
+            }
+
+            assert!(!reader.uses_proc_mem());
+            let (mut first, mut second) = ([0; 4], [0; 4]);
+            let mut requests = [
+                (values.as_ptr() as usize, &mut first[..]),
+                (values.as_ptr() as usize + 4, &mut second[..]),
+            ];
+            reader.copy_addresses(&mut requests).unwrap();
+            assert_eq!(first, 0x1122_3344_u32.to_ne_bytes());
+            assert_eq!(second, 0x5566_7788_u32.to_ne_bytes());
+        })
+        .join()
+        .unwrap();
+        assert!(handle.uses_proc_mem());
+    }
+
+    #[test]
+    fn read_and_write_through_proc_mem() {
+        let value = [1_u8, 2, 3, 4, 5];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        handle.use_proc_mem(true);
+        let clone = handle.clone();
+        assert!(clone.uses_proc_mem());
+        assert_eq!(clone, handle);
+
+        let addr = value.as_ptr() as usize;
+        let mut read = [0_u8; 4];
+        handle.copy_address(addr + 1, &mut read).unwrap();
+        assert_eq!(read, [2, 3, 4, 5]);
+        handle.put_address(addr, &[9, 8]).unwrap();
+        clone.put_addresses(&[(addr + 4, &[7])]).unwrap();
+        assert!(std::ptr::eq(
+            handle.mem.file.get().unwrap(),
+            clone.mem.file.get().unwrap()
+        ));
+        assert_eq!(value, [9, 8, 3, 4, 7]);
+        assert!(handle.copy_address(0, &mut read).is_err());
+
+        // The scan mode hint is shared between clones, and doesn't change what is read.
+        handle.set_scan_mode(true);
+        assert!(clone
+            .mem
+            .sequential
+            .load(std::sync::atomic::Ordering::Relaxed));
+        clone.copy_address(addr, &mut read).unwrap();
+        assert_eq!(read, [9, 8, 3, 4]);
+        clone.set_scan_mode(false);
+        assert!(!handle
+            .mem
+            .sequential
+            .load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[test]
+    fn write_to_read_only_page() {
+        let len = page_size();
+        let page = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(libc::MAP_FAILED, page);
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let addr = page as usize;
+        assert!(handle.put_address(addr, &[1, 2]).is_err());
+        handle.write_protected(addr, &[1, 2]).unwrap();
+        assert_eq!(
+            unsafe { std::ptr::read_volatile(page.cast::<[u8; 2]>()) },
+            [1, 2]
+        );
+        assert!(!handle.is_writable(addr));
+        unsafe {
+            assert_eq!(0, libc::munmap(page, len));
+        }
+    }
+
+    #[test]
+    fn read_child_registers() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let tid = child.id() as Tid;
+        let context = handle.thread_context(tid).unwrap();
+        assert_ne!(context.instruction_pointer(), 0);
+        let stack = read_maps(handle.pid)
+            .unwrap()
+            .into_iter()
+            .find(|(_, path)| path == "[stack]")
+            .unwrap()
+            .0;
+        assert!(stack.contains(context.stack_pointer()));
+        assert_eq!(
+            handle.thread_context(-1).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        child.kill().unwrap();
+        let _ = child.wait().unwrap();
+    }
+
+    #[test]
+    fn put_addresses_stops_at_unmapped() {
+        let mut fields = [0_u32; 3];
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let base = fields.as_mut_ptr() as usize;
+        let addr = |index: usize| base + index * 4;
+        let (x, y, z) = (
+            1_u32.to_ne_bytes(),
+            2_u32.to_ne_bytes(),
+            3_u32.to_ne_bytes(),
+        );
+        handle
+            .put_addresses(&[(addr(0), &x), (addr(1), &y), (addr(2), &z)])
+            .unwrap();
+        assert_eq!(std::hint::black_box(&mut fields), &[1, 2, 3]);
+
+        // Nothing is ever mapped at the null page, so the second write fails and the third is
+        // never attempted.
+        let zero = 0_u32.to_ne_bytes();
+        let err = handle
+            .put_addresses(&[(addr(0), &zero), (0x10, &zero), (addr(2), &zero)])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(std::hint::black_box(&mut fields), &[0, 2, 3]);
+    }
+
+    #[test]
+    fn put_address_error_kinds() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert_eq!(
+            handle.put_address(0x10, &[0; 4]).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        // No process can have the largest possible pid, as it is above the kernel's `pid_max`.
+        let missing = Pid::MAX.try_into_process_handle().unwrap();
+        assert_eq!(
+            missing.put_address(0x10, &[0; 4]).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+        let err = crate::MemoryError::from(missing.put_address(0x10, &[0; 4]).unwrap_err());
+        assert!(matches!(err, crate::MemoryError::ProcessGone(_)));
+        assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+        let err = crate::MemoryError::from(handle.put_address(0x10, &[0; 4]).unwrap_err());
+        assert_eq!(err.raw_os_error(), Some(libc::EFAULT));
+        let err = crate::MemoryError::from(missing.copy_address(0x10, &mut [0; 4]).unwrap_err());
+        assert!(matches!(err, crate::MemoryError::ProcessGone(_)));
+        let (mut first, mut second) = ([0; 4], [0; 4]);
+        let mut requests = [(0x10, &mut first[..]), (0x20, &mut second[..])];
+        let err = crate::MemoryError::from(missing.copy_addresses(&mut requests).unwrap_err());
+        assert!(matches!(err, crate::MemoryError::ProcessGone(_)));
+        assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+        let results = read_regions(Pid::MAX, &[(0x10, 4), (0x20, 4)], &mut [0; 8]);
+        for result in results {
+            let err = crate::MemoryError::from(result.unwrap_err());
+            assert!(matches!(err, crate::MemoryError::ProcessGone(_)));
+            assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+        }
+    }
+
+    #[test]
+    fn raw_handle_round_trip() {
+        #[allow(clippy::cast_possible_wrap)]
+        let pid = std::process::id() as Pid;
+        let handle = ProcessHandle::from_raw_handle(pid);
+        assert_eq!(handle, pid.try_into_process_handle().unwrap());
+        assert_eq!(handle.into_raw_handle(), pid);
+    }
+    #[test]
+    fn detect_exited_process() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        assert!(handle.is_alive());
+        assert!(!ProcessHandle::null_type().is_alive());
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        // Until we wait for it, the child is a zombie once it has exited.
+        let start = std::time::Instant::now();
+        while handle.is_alive() {
+            assert!(start.elapsed() < std::time::Duration::from_secs(10));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let _ = child.wait().unwrap();
+        assert!(!handle.is_alive());
+    }
+    #[test]
+    fn parse_maps_lines() {
+        assert_eq!(
+            Some((
+                Region {
+                    base: 0xff9e_2000,
+                    size: 0x2000,
+                    protection: Protection::READ | Protection::WRITE,
+                    is_private: true,
+                },
+                "[stack]"
+            )),
+            parse_maps_line("ff9e2000-ff9e4000 rw-p 00000000 00:00 0    [stack]")
+        );
+        assert_eq!(
+            Some((
+                Region {
+                    base: 0x5655_4000,
+                    size: 0x1000,
+                    protection: Protection::READ | Protection::EXECUTE,
+                    is_private: false,
+                },
+                "/usr/bin/my program"
+            )),
+            parse_maps_line("56554000-56555000 r-xs 00001000 08:01 1234 /usr/bin/my program")
+        );
+        assert_eq!(
+            Some(""),
+            parse_maps_line("ff9e2000-ff9e4000 rw-p 00000000 00:00 0").map(|(_, path)| path)
+        );
+        assert_eq!(None, parse_maps_line("not a maps line"));
+    }
+    #[test]
+    fn find_own_executable() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        let base = handle.get_module_base(name).unwrap();
+        let size = handle.get_module_size(name).unwrap();
+        let function = find_own_executable as *const () as usize;
+        assert!(base <= function && function < base + size);
+        assert_eq!(
+            std::io::ErrorKind::NotFound,
+            handle.get_module_base("no-such-module").unwrap_err().kind()
+        );
+    }
+    #[test]
+    fn regions_contain_stack() {
+        let value = 0_u32;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let regions = handle.regions().unwrap();
+        let region = regions
+            .iter()
+            .find(|region| region.contains(&raw const value as usize))
+            .unwrap();
+        assert!(region
+            .protection
+            .contains(Protection::READ | Protection::WRITE));
+        assert!(region.is_private);
+    }
+    #[test]
+    fn find_regions_by_name() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let stack = handle.find_region_by_name("[stack]").unwrap().unwrap();
+        assert!(stack
+            .protection
+            .contains(Protection::READ | Protection::WRITE));
+
+        let exe = std::env::current_exe().unwrap();
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        let region = handle.find_region_by_name(name).unwrap().unwrap();
+        assert_eq!(region.base, handle.get_module_base(name).unwrap());
+        assert_eq!(
+            handle.find_region_by_name(exe.to_str().unwrap()).unwrap(),
+            Some(region)
+        );
+        assert_eq!(handle.find_region_by_name("[nothing]").unwrap(), None);
+    }
+    #[test]
+    fn read_regions_skips_unmapped() {
+        let first = 0x1122_3344_u32;
+        let last = 0x5566_7788_u32;
+        #[allow(clippy::cast_possible_wrap)]
+        let pid = std::process::id() as Pid;
+        // Nothing is ever mapped at the null page, so the middle region can't be read.
+        let regions = [
+            (&raw const first as usize, 4),
+            (0x10, 4),
+            (&raw const last as usize, 4),
+        ];
+        let mut buf = [0_u8; 12];
+        let results = read_regions(pid, &regions, &mut buf);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(results[2].is_ok());
+        assert_eq!(first.to_ne_bytes(), buf[..4]);
+        assert_eq!(last.to_ne_bytes(), buf[8..]);
+    }
+    #[test]
+    fn read_regions_more_than_iov_max() {
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let values: Vec<u8> = (0..libc::UIO_MAXIOV as usize * 2 + 3)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        #[allow(clippy::cast_possible_wrap)]
+        let pid = std::process::id() as Pid;
+        let regions: Vec<(usize, usize)> = values
+            .iter()
+            .map(|v| (std::ptr::from_ref(v) as usize, 1))
+            .collect();
+        let mut buf = vec![0_u8; values.len()];
+        let results = read_regions(pid, &regions, &mut buf);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(values, buf);
     }
 }
@@ -1,4 +1,31 @@
-use crate::Memory;
+use crate::{Memory, Pid, ProcessHandle, ProcessHandleExt, TryIntoProcessHandle};
+
+/// Check that the `size` bytes at `addr` in this process can be written to, so that writing to
+/// them returns an error instead of crashing.
+fn check_writable(addr: usize, size: usize) -> std::io::Result<()> {
+    static OWN_PROCESS: std::sync::OnceLock<ProcessHandle> = std::sync::OnceLock::new();
+
+    if size == 0 {
+        return Ok(());
+    }
+    let handle = if let Some(handle) = OWN_PROCESS.get() {
+        handle
+    } else {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid).try_into_process_handle()?;
+        OWN_PROCESS.get_or_init(|| handle)
+    };
+    let last = addr.wrapping_add(size - 1);
+    if handle.is_writable(addr) && handle.is_writable(last) {
+        Ok(())
+    } else {
+        Err(crate::memory_error::classified_error(
+            std::io::ErrorKind::PermissionDenied,
+            crate::memory_error::ErrorClass::AddressUnreadable,
+            format!("The memory at {addr:#x}..={last:#x} isn't writable"),
+        ))
+    }
+}
 
 /// # Tools for working with local memory
 /// This module provides functions for modifying the memory of a program from within the address
@@ -20,6 +47,23 @@ use crate::Memory;
 /// member.write(&6u32).unwrap();
 /// assert_eq!(x, 6u32);
 /// ```
+///
+/// Unlike a [`DataMember`], a `LocalMember` follows its offsets, and reads its value, with plain
+/// pointer dereferences, without making a system call. This makes it much faster, but it also
+/// means that nothing checks the addresses it uses. Every pointer in the chain, and the final
+/// address, must be valid memory of this process that is mapped for as long as the `LocalMember`
+/// is used, as anything else will crash the program (or worse) instead of returning an error.
+/// Only null pointers in the chain are checked for.
+///
+/// Writing is the exception: as it is easy to point a `LocalMember` at read-only memory, such as
+/// a string literal, where a write would crash the program, every write first looks up the memory
+/// it is about to write to with [`ProcessHandleExt::is_writable`], and fails with a
+/// `std::io::ErrorKind` of `PermissionDenied` if it isn't writable. This makes writes much slower
+/// than reads. The check can't stop another thread from making the memory read-only between the
+/// check and the write.
+///
+/// [`ProcessHandleExt::is_writable`]: trait.ProcessHandleExt.html#method.is_writable
+/// [`DataMember`]: struct.DataMember.html
 #[derive(Clone, Debug, Default)]
 pub struct LocalMember<T> {
     offsets: Vec<usize>,
@@ -52,6 +96,36 @@ impl<T: Sized + Copy> LocalMember<T> {
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Resolves the offsets and returns a reference to the value at the final address, without
+    /// copying it. This is useful when `T` is large and only a few of its fields are needed.
+    ///
+    /// # Safety
+    /// Nothing ties the memory to the returned reference: the caller must ensure that the value
+    /// is a valid `T`, stays mapped and isn't written to (by this or any other thread) for as long
+    /// as the reference is used. If the data changes or is deallocated while the reference is
+    /// alive, the behaviour is undefined. The same requirements on the offsets apply as for
+    /// [`Memory::read`].
+    ///
+    /// # Errors
+    /// Returns an error if one of the offsets gives a null pointer, or with a
+    /// `std::io::ErrorKind` of `InvalidData` if the final address isn't aligned for `T`, as a
+    /// reference must always be aligned.
+    ///
+    /// [`Memory::read`]: trait.Memory.html#tymethod.read
+    pub unsafe fn read_ref(&self) -> std::io::Result<&T> {
+        let offset = self.get_offset()?;
+        if offset % std::mem::align_of::<T>() != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "0x{offset:x} isn't aligned to the {} bytes needed for a reference",
+                    std::mem::align_of::<T>()
+                ),
+            ));
+        }
+        Ok(&*(offset as *const T))
+    }
 }
 
 impl<T: Sized + Copy> Memory<T> for LocalMember<T> {
@@ -62,11 +136,12 @@ impl<T: Sized + Copy> Memory<T> for LocalMember<T> {
     fn get_offset(&self) -> std::io::Result<usize> {
         let mut offset = 0_usize;
         for i in 0..self.offsets.len() - 1 {
-            offset = offset.wrapping_add(self.offsets[i]);
+            offset = crate::add_offset(offset, self.offsets[i], i)?;
             if offset == 0 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Would be a null dereference!",
+                return Err(crate::memory_error::classified_error(
+                    std::io::ErrorKind::NotFound,
+                    crate::memory_error::ErrorClass::NullInChain,
+                    "Would be a null dereference!".to_owned(),
                 ));
             }
             // We can't guarantee alignment, so we must use `read_unaligned()`
@@ -76,10 +151,12 @@ impl<T: Sized + Copy> Memory<T> for LocalMember<T> {
                 offset = (offset as *const usize).read_unaligned();
             }
         }
-        Ok(offset.wrapping_add(self.offsets[self.offsets.len() - 1]))
+        let last = self.offsets.len() - 1;
+        crate::add_offset(offset, self.offsets[last], last)
     }
 
-    /// This will only return a error if one of the offsets gives a null pointer.
+    /// This will only return a error if one of the offsets gives a null pointer, or adding one of
+    /// them overflows the address space.
     fn read(&self) -> std::io::Result<T> {
         let offset = self.get_offset()? as *const T;
         // Read the value of the pointer. We can't guarantee alignment, so this
@@ -88,19 +165,41 @@ impl<T: Sized + Copy> Memory<T> for LocalMember<T> {
         Ok(x)
     }
 
-    /// This will only return a error if one of the offsets gives a null pointer.
+    /// This will return an error if one of the offsets gives a null pointer, or the memory being
+    /// written to isn't writable.
     fn write(&self, value: &T) -> std::io::Result<()> {
-        use std::ptr::copy_nonoverlapping;
+        let _ = self.write_and_resolve(value)?;
+        Ok(())
+    }
 
-        let offset = self.get_offset()? as *mut T;
+    /// This will return an error if one of the offsets gives a null pointer, or the memory being
+    /// written to isn't writable.
+    fn write_and_resolve(&self, value: &T) -> std::io::Result<usize> {
+        let offset = self.get_offset()?;
+        check_writable(offset, std::mem::size_of::<T>())?;
+        // Write the value to the pointer. As with reading, we can't guarantee alignment, so this
+        // is `write_unaligned()` instead of `write()`
         unsafe {
-            copy_nonoverlapping(value, offset, 1_usize);
+            (offset as *mut T).write_unaligned(*value);
         }
-        Ok(())
+        Ok(offset)
+    }
+
+    /// This will return an error if one of the offsets gives a null pointer, or the memory being
+    /// written to isn't writable.
+    fn write_verified(&self, value: &T) -> std::io::Result<bool> {
+        let offset = self.write_and_resolve(value)?;
+        let read = unsafe { (offset as *const T).read_unaligned() };
+        Ok(crate::bytes_of(&read) == crate::bytes_of(value))
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::borrow_as_ptr,
+    clippy::ref_as_ptr,
+    clippy::uninlined_format_args
+)]
 mod test {
     use super::*;
     #[test]
@@ -122,6 +221,30 @@ mod test {
         assert_eq!(test, -1);
     }
     #[test]
+    fn refuse_read_only_write() {
+        let text: &'static str = "read-only";
+        let member = LocalMember::<u8>::new_offset(vec![text.as_ptr() as usize]);
+        assert_eq!(member.read().unwrap(), b'r');
+        assert_eq!(
+            member.write(&b'R').unwrap_err().kind(),
+            std::io::ErrorKind::PermissionDenied
+        );
+        assert!(member.write_verified(&b'R').is_err());
+        assert_eq!(text, "read-only");
+    }
+    #[test]
+    fn refuse_overflowing_offsets() {
+        let test = 1_u8;
+        let pointer = &raw const test as usize;
+        let member = LocalMember::<u8>::new_offset(vec![&raw const pointer as usize, usize::MAX]);
+        assert_eq!(
+            member.read().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert!(member.write(&2).is_err());
+        assert_eq!(test, 1);
+    }
+    #[test]
     fn modify_local_usize() {
         let test = 0_usize;
         let mut member = LocalMember::<usize>::new();
@@ -130,4 +253,18 @@ mod test {
         member.write(&0xffff).unwrap();
         assert_eq!(test, 0xffff);
     }
+    #[test]
+    fn borrow_local_array() {
+        let mut test = [1_u64, 2, 3, 4];
+        let member = LocalMember::<[u64; 4]>::new_offset(vec![test.as_ptr() as usize]);
+        assert_eq!(unsafe { member.read_ref() }.unwrap(), &[1, 2, 3, 4]);
+        test[2] = 5;
+        assert_eq!(unsafe { member.read_ref() }.unwrap()[2], 5);
+
+        let misaligned = LocalMember::<u64>::new_offset(vec![test.as_ptr() as usize + 1]);
+        assert_eq!(
+            unsafe { misaligned.read_ref() }.unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
 }
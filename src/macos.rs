@@ -1,14 +1,397 @@
 use libc::{c_int, pid_t};
-use mach::kern_return::KERN_SUCCESS;
-use mach::port::{mach_port_name_t, MACH_PORT_NULL};
+use mach::kern_return::{
+    kern_return_t, KERN_INVALID_ADDRESS, KERN_INVALID_ARGUMENT, KERN_NO_ACCESS,
+    KERN_PROTECTION_FAILURE, KERN_SUCCESS, KERN_TERMINATED,
+};
+use mach::message::{
+    mach_msg_body_t, mach_msg_header_t, mach_msg_id_t, mach_msg_port_descriptor_t,
+    mach_msg_timeout_t, mach_msg_trailer_t, mach_msg_type_number_t, MACH_MSGH_BITS,
+    MACH_MSGH_BITS_COMPLEX, MACH_MSG_SUCCESS, MACH_MSG_TIMEOUT_NONE, MACH_MSG_TYPE_COPY_SEND,
+    MACH_MSG_TYPE_MAKE_SEND, MACH_RCV_MSG, MACH_RCV_TIMED_OUT, MACH_RCV_TIMEOUT,
+    MACH_SEND_INVALID_DEST, MACH_SEND_MSG,
+};
+use mach::port::{
+    mach_port_name_t, mach_port_t, MACH_PORT_NULL, MACH_PORT_RIGHT_DEAD_NAME,
+    MACH_PORT_RIGHT_RECEIVE, MACH_PORT_RIGHT_SEND,
+};
+use mach::vm_prot::{vm_prot_t, VM_PROT_COPY, VM_PROT_EXECUTE, VM_PROT_READ, VM_PROT_WRITE};
+use std::convert::TryInto;
 use std::process::Child;
 
-use super::{CopyAddress, PutAddress, TryIntoProcessHandle};
+use crate::memory_error::ErrorClass;
+use crate::trace::{trace_read, trace_write};
+
+use super::{
+    AccessMode, Allocate, Architecture, ControlProcess, CopyAddress, EnumerateRegions,
+    EnumerateThreads, FindModule, ProcessHandleExt, ProtectMemory, Protection, PutAddress, Region,
+    ThreadContext, ThreadInfo, TryIntoProcessHandle,
+};
 
 /// On OS X a `Pid` is just a `libc::pid_t`.
 pub type Pid = pid_t;
-/// On OS X a `ProcessHandle` is a mach port.
-pub type ProcessHandle = mach_port_name_t;
+/// On OS X a `Tid` is the system-wide, 64-bit thread id from `thread_info`, which unlike a thread
+/// port is the same in every task.
+pub type Tid = u64;
+/// On OS X a `ProcessHandle` is a mach port, along with the `Architecture` of the process.
+///
+/// A mach port name is just a number that is valid in every thread of this task, so a
+/// `ProcessHandle` is `Send` and `Sync`, and the `mach_vm_*` calls it makes can be made from
+/// several threads at once.
+///
+/// Each `ProcessHandle` owns a reference to the send right for its task port, which it
+/// deallocates when it is dropped. Cloning a `ProcessHandle` adds another reference with
+/// `mach_port_mod_refs`, so every clone can be dropped independently of the others.
+///
+/// A handle opened from a `Pid` may hold a task name port instead of a task port, if the
+/// privileges that `task_for_pid` needs are missing. Such a handle is read-only, which
+/// [`is_read_only`] tells you.
+///
+/// [`is_read_only`]: struct.ProcessHandle.html#method.is_read_only
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct ProcessHandle {
+    task: mach_port_name_t,
+    arch: Architecture,
+    read_only: bool,
+}
+
+/// On OS X the raw form of a `ProcessHandle` is the name of its task port.
+pub type RawProcessHandle = mach_port_name_t;
+
+/// On OS X the registers of a thread are an `x86_thread_state64_t`, as read with
+/// `thread_get_state`.
+#[cfg(target_arch = "x86_64")]
+pub type RawThreadContext = mach::structs::x86_thread_state64_t;
+/// On Apple Silicon the registers of a thread are an [`ArmThreadState64`], as read with
+/// `thread_get_state`.
+///
+/// [`ArmThreadState64`]: struct.ArmThreadState64.html
+#[cfg(target_arch = "aarch64")]
+pub type RawThreadContext = ArmThreadState64;
+
+/// The general purpose registers of a thread on Apple Silicon, which is `arm_thread_state64_t`.
+/// On arm64e the link register, frame pointer, stack pointer and program counter may be signed
+/// with pointer authentication codes.
+#[cfg(target_arch = "aarch64")]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ArmThreadState64 {
+    /// The general purpose registers `x0` to `x28`.
+    pub x: [u64; 29],
+    /// The frame pointer, `x29`.
+    pub fp: u64,
+    /// The link register, `x30`.
+    pub lr: u64,
+    /// The stack pointer.
+    pub sp: u64,
+    /// The program counter.
+    pub pc: u64,
+    /// The current program status register.
+    pub cpsr: u32,
+    /// Padding.
+    pub pad: u32,
+}
+
+impl ProcessHandle {
+    /// Make a `ProcessHandle` for `task`, assuming it has the same `Architecture` as the host.
+    fn from_task(task: mach_port_name_t) -> Self {
+        ProcessHandle {
+            task,
+            arch: Architecture::from_native(),
+            read_only: false,
+        }
+    }
+
+    /// Make a `ProcessHandle` from a task port that was obtained elsewhere, such as from your own
+    /// call to `task_for_pid`, with the `Architecture` of the host.
+    ///
+    /// The `ProcessHandle` takes over one reference to the send right for `task`, which is
+    /// deallocated with `mach_port_deallocate` when it is dropped, so the caller must not
+    /// deallocate that reference itself. Use `mach_port_mod_refs` to add a reference first to keep
+    /// using the port after handing it over.
+    #[must_use]
+    pub fn from_raw_handle(task: RawProcessHandle) -> ProcessHandle {
+        Self::from_task(task)
+    }
+
+    /// Give up the reference to the send right for the task port that the `ProcessHandle` owns,
+    /// for use with other Mach calls. The caller is responsible for deallocating it.
+    #[must_use]
+    pub fn into_raw_handle(self) -> RawProcessHandle {
+        std::mem::ManuallyDrop::new(self).task
+    }
+
+    /// Whether the handle only holds a task name port, from the `task_name_for_pid` fallback
+    /// when opening a `Pid`. Writing to the memory of the process, changing its protection or
+    /// allocating in it through a read-only handle fails with a `std::io::ErrorKind` of
+    /// `PermissionDenied`.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fail with a `std::io::ErrorKind` of `PermissionDenied` if the handle is read-only, so that
+    /// trying to `operation` says why it can't, rather than failing with a Mach error.
+    fn check_writable(&self, operation: &str) -> std::io::Result<()> {
+        if self.read_only {
+            Err(crate::memory_error::classified_error(
+                std::io::ErrorKind::PermissionDenied,
+                ErrorClass::PermissionDenied,
+                format!("Cannot {operation} with a read-only handle to a task name port"),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A clone of a `ProcessHandle` owns its own reference to the send right. If the task has died,
+/// the send right has become a dead name, and a reference to that is added instead.
+impl Clone for ProcessHandle {
+    fn clone(&self) -> Self {
+        if self.task != MACH_PORT_NULL {
+            let this = unsafe { mach::traps::mach_task_self() };
+            let added = [MACH_PORT_RIGHT_SEND, MACH_PORT_RIGHT_DEAD_NAME]
+                .iter()
+                .any(
+                |&right| unsafe { mach_port_mod_refs(this, self.task, right, 1) } == KERN_SUCCESS,
+            );
+            // Both can only fail if the reference count would overflow, in which case sharing the
+            // reference could deallocate it from under us, so the clone gets no port at all.
+            if !added {
+                return ProcessHandle::from_task(MACH_PORT_NULL).set_arch(self.arch);
+            }
+        }
+        ProcessHandle {
+            task: self.task,
+            arch: self.arch,
+            read_only: self.read_only,
+        }
+    }
+}
+
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        if self.task != MACH_PORT_NULL {
+            unsafe {
+                let _ =
+                    mach::mach_port::mach_port_deallocate(mach::traps::mach_task_self(), self.task);
+            }
+        }
+    }
+}
+
+/// The error from a Mach call that failed, such as `mach_vm_read_overwrite` or `task_suspend`,
+/// which keeps the `kern_return_t` it returned.
+///
+/// Mach calls don't set `errno`, so errors from them have no `raw_os_error`. Instead, the
+/// `std::io::Error` returned by this crate wraps a `MachError`, which can be got back with
+/// `get_ref` and `downcast_ref` to tell, for instance, `KERN_PROTECTION_FAILURE` from
+/// `KERN_INVALID_ADDRESS`:
+///
+/// ```no_run
+/// # use process_memory::{CopyAddress, MachError, Pid, TryIntoProcessHandle};
+/// # let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let err = handle.copy_address(0, &mut [0_u8; 4]).unwrap_err();
+/// let code = err
+///     .get_ref()
+///     .and_then(|inner| inner.downcast_ref::<MachError>())
+///     .map(MachError::code);
+/// assert_eq!(code, Some(mach::kern_return::KERN_INVALID_ADDRESS));
+/// ```
+#[derive(Debug)]
+pub struct MachError {
+    code: kern_return_t,
+    message: String,
+}
+
+impl MachError {
+    /// Get the `kern_return_t` that the call returned.
+    #[must_use]
+    pub fn code(&self) -> kern_return_t {
+        self.code
+    }
+
+    /// The `ErrorClass` of the error, for turning it into a `MemoryError`.
+    pub(crate) fn class(&self) -> Option<ErrorClass> {
+        match self.code {
+            KERN_INVALID_ADDRESS | KERN_PROTECTION_FAILURE => Some(ErrorClass::AddressUnreadable),
+            KERN_NO_ACCESS => Some(ErrorClass::PermissionDenied),
+            KERN_TERMINATED | MACH_SEND_INVALID_DEST => Some(ErrorClass::ProcessGone),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MachError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (kern_return_t {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for MachError {}
+
+/// The `std::io::ErrorKind` is picked from the `kern_return_t`, so that a bad address or a lack
+/// of permissions can be told apart without looking at the code.
+impl From<MachError> for std::io::Error {
+    fn from(err: MachError) -> Self {
+        let kind = match err.code {
+            KERN_PROTECTION_FAILURE | KERN_NO_ACCESS => std::io::ErrorKind::PermissionDenied,
+            KERN_INVALID_ADDRESS | KERN_INVALID_ARGUMENT => std::io::ErrorKind::InvalidInput,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, err)
+    }
+}
+
+/// Make a `std::io::Error` for the Mach call described by `message` failing with `code`.
+fn mach_error(code: kern_return_t, message: String) -> std::io::Error {
+    MachError { code, message }.into()
+}
+
+extern "C" {
+    /// `pid_for_task`, `mach_port_mod_refs` and `mach_port_type` aren't exposed by the `mach`
+    /// crate, so we declare them ourselves.
+    fn pid_for_task(task: mach_port_name_t, pid: *mut c_int) -> kern_return_t;
+    fn mach_port_mod_refs(
+        task: mach_port_name_t,
+        name: mach_port_name_t,
+        right: mach::port::mach_port_right_t,
+        delta: mach::vm_types::integer_t,
+    ) -> kern_return_t;
+    fn mach_port_type(
+        task: mach_port_name_t,
+        name: mach_port_name_t,
+        port_type: *mut mach_port_type_t,
+    ) -> kern_return_t;
+    /// Nor is `task_name_for_pid`.
+    fn task_name_for_pid(
+        target_tport: mach_port_name_t,
+        pid: c_int,
+        task_name: *mut mach_port_name_t,
+    ) -> kern_return_t;
+}
+
+impl ProcessHandleExt for ProcessHandle {
+    fn check_handle(&self) -> bool {
+        self.task != MACH_PORT_NULL
+    }
+    fn null_type() -> ProcessHandle {
+        ProcessHandle::from_task(MACH_PORT_NULL)
+    }
+    /// Use `pid_for_task` to find the `Pid` a task port belongs to.
+    fn get_pid(&self) -> std::io::Result<Pid> {
+        let mut pid: c_int = 0;
+        let result = unsafe { pid_for_task(self.task, &raw mut pid) };
+        if result != KERN_SUCCESS {
+            return Err(mach_error(result, "`pid_for_task` failed".to_owned()));
+        }
+        Ok(pid)
+    }
+    fn set_arch(mut self, arch: Architecture) -> Self {
+        self.arch = arch;
+        self
+    }
+    fn get_arch(&self) -> Architecture {
+        self.arch
+    }
+    fn page_size(&self) -> usize {
+        page_size()
+    }
+    /// Once a task dies, our send right to its port turns into a dead name, which
+    /// `mach_port_type` tells us about. Suspending a task doesn't affect its port.
+    fn is_alive(&self) -> bool {
+        let mut port_type: mach_port_type_t = 0;
+        self.task != MACH_PORT_NULL
+            && unsafe {
+                mach_port_type(mach::traps::mach_task_self(), self.task, &raw mut port_type)
+            } == KERN_SUCCESS
+            && port_type & MACH_PORT_TYPE_DEAD_NAME == 0
+    }
+    /// The path comes from `proc_pidpath`.
+    fn exe_path(&self) -> std::io::Result<std::path::PathBuf> {
+        use std::os::unix::ffi::OsStringExt;
+
+        let pid = self.get_pid()?;
+        let mut path = vec![0_u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+        #[allow(clippy::cast_possible_truncation)]
+        let len = unsafe { libc::proc_pidpath(pid, path.as_mut_ptr().cast(), path.len() as u32) };
+        if len <= 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        #[allow(clippy::cast_sign_loss)]
+        path.truncate(len as usize);
+        Ok(std::ffi::OsString::from_vec(path).into())
+    }
+    /// The arguments come from the `kern.procargs2` sysctl, which holds the arguments the process
+    /// was started with, regardless of any changes it has made to them since.
+    fn cmdline(&self) -> std::io::Result<Vec<String>> {
+        let pid = self.get_pid()?;
+        let mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+        let mut size: libc::size_t = 0;
+        #[allow(clippy::cast_possible_truncation)]
+        let result = unsafe {
+            libc::sysctl(
+                mib.as_ptr().cast_mut(),
+                mib.len() as _,
+                std::ptr::null_mut(),
+                &raw mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut args = vec![0_u8; size];
+        #[allow(clippy::cast_possible_truncation)]
+        let result = unsafe {
+            libc::sysctl(
+                mib.as_ptr().cast_mut(),
+                mib.len() as _,
+                args.as_mut_ptr().cast(),
+                &raw mut size,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        args.truncate(size);
+        // The buffer starts with `argc`, followed by the executable path and some padding, all
+        // null-terminated, then the arguments and the environment.
+        let argc_len = std::mem::size_of::<c_int>();
+        if args.len() < argc_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The arguments of the process are truncated",
+            ));
+        }
+        #[allow(clippy::cast_sign_loss)]
+        let count = c_int::from_ne_bytes(args[..argc_len].try_into().unwrap()) as usize;
+        let rest = &args[argc_len..];
+        let start = rest
+            .iter()
+            .position(|&byte| byte == 0)
+            .and_then(|path_end| {
+                rest[path_end..]
+                    .iter()
+                    .position(|&byte| byte != 0)
+                    .map(|padding| path_end + padding)
+            })
+            .unwrap_or(rest.len());
+        let mut args = crate::split_args(&rest[start..]);
+        args.truncate(count);
+        Ok(args)
+    }
+}
+
+/// The type of a port right, which is a set of `MACH_PORT_TYPE_*` bits.
+#[allow(non_camel_case_types)]
+type mach_port_type_t = mach::vm_types::natural_t;
+
+/// A dead name, which is what a send right turns into when its port is destroyed.
+const MACH_PORT_TYPE_DEAD_NAME: mach_port_type_t = 1 << (MACH_PORT_RIGHT_DEAD_NAME + 16);
 
 /// A small wrapper around `task_for_pid`, which taskes a pid returns the mach port representing its task.
 fn task_for_pid(pid: Pid) -> std::io::Result<mach_port_name_t> {
@@ -16,23 +399,113 @@ fn task_for_pid(pid: Pid) -> std::io::Result<mach_port_name_t> {
 
     unsafe {
         let result =
-            mach::traps::task_for_pid(mach::traps::mach_task_self(), pid as c_int, &mut task);
+            mach::traps::task_for_pid(mach::traps::mach_task_self(), pid as c_int, &raw mut task);
         if result != KERN_SUCCESS {
-            return Err(std::io::Error::last_os_error());
+            return Err(mach_error(
+                result,
+                format!("`task_for_pid` failed for pid {pid}"),
+            ));
         }
     }
 
     Ok(task)
 }
 
+/// Get the task name port of `pid` with `task_name_for_pid`.
+fn name_for_pid(pid: Pid) -> std::io::Result<mach_port_name_t> {
+    let mut name: mach_port_name_t = MACH_PORT_NULL;
+    let result =
+        unsafe { task_name_for_pid(mach::traps::mach_task_self(), pid as c_int, &raw mut name) };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(
+            result,
+            format!("`task_name_for_pid` failed for pid {pid}"),
+        ));
+    }
+    Ok(name)
+}
+
 /// `Pid` can be turned into a `ProcessHandle` with `task_for_pid`.
+///
+/// `task_for_pid` needs this process to be running as root or to be signed with the
+/// `com.apple.security.cs.debugger` entitlement, and even then it fails for processes protected
+/// by System Integrity Protection and for those built with the hardened runtime that don't have
+/// the `com.apple.security.get-task-allow` entitlement. When it fails, the task name port is tried
+/// with `task_name_for_pid` instead, which only needs the process to belong to the same user.
+/// A name port is only meant for inspecting a task, so the handle is marked read-only (see
+/// [`ProcessHandle::is_read_only`]) and any attempt to write to it fails with a
+/// `std::io::ErrorKind` of `PermissionDenied`. Depending on the version of macOS, reading memory
+/// through a name port may still be refused with a Mach error. If both calls fail, the error from
+/// `task_for_pid` is returned.
+///
+/// [`ProcessHandle::is_read_only`]: struct.ProcessHandle.html#method.is_read_only
 impl TryIntoProcessHandle for Pid {
     fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
-        task_for_pid(*self)
+        match task_for_pid(*self) {
+            Ok(task) => Ok(ProcessHandle::from_task(task)),
+            Err(err) => match name_for_pid(*self) {
+                Ok(name) => {
+                    let mut handle = ProcessHandle::from_task(name);
+                    handle.read_only = true;
+                    Ok(handle)
+                }
+                Err(_) => Err(err),
+            },
+        }
     }
 }
 
-/// This `TryIntoProcessHandle` impl simply calls the `TryIntoProcessHandle` impl for `Pid`.
+/// Access isn't granted per handle on macOS, so `access` is ignored.
+pub(crate) fn open_process(pid: Pid, _access: AccessMode) -> std::io::Result<ProcessHandle> {
+    pid.try_into_process_handle()
+}
+
+/// The size of a page of memory on this system, from `sysconf`.
+pub(crate) fn page_size() -> usize {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    size
+}
+
+/// Find the pid of every process whose executable is called `name`, by listing every process with
+/// `proc_listallpids` and looking up their executables with `proc_pidpath`.
+pub(crate) fn processes_named(name: &str) -> std::io::Result<Vec<Pid>> {
+    let count = unsafe { libc::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // Leave room for processes that start between the two calls.
+    #[allow(clippy::cast_sign_loss)]
+    let mut pids: Vec<Pid> = vec![0; count as usize + 16];
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let count = unsafe {
+        libc::proc_listallpids(
+            pids.as_mut_ptr().cast(),
+            std::mem::size_of_val(pids.as_slice()) as c_int,
+        )
+    };
+    if count < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::cast_sign_loss)]
+    pids.truncate(count as usize);
+    let mut path = [0_u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+    pids.retain(|&pid| {
+        #[allow(clippy::cast_possible_truncation)]
+        let len = unsafe { libc::proc_pidpath(pid, path.as_mut_ptr().cast(), path.len() as u32) };
+        #[allow(clippy::cast_sign_loss)]
+        let matches = len > 0
+            && crate::module_name_matches(&String::from_utf8_lossy(&path[..len as usize]), name);
+        matches
+    });
+    Ok(pids)
+}
+
+/// This `TryIntoProcessHandle` impl simply calls the `TryIntoProcessHandle` impl for `Pid`, so it
+/// needs the same privileges as calling `task_for_pid` on any other process. Use
+/// [`CommandExt::spawn_with_task_port`] to get the task port of a child without them.
+///
+/// [`CommandExt::spawn_with_task_port`]: trait.CommandExt.html#tymethod.spawn_with_task_port
 impl TryIntoProcessHandle for Child {
     fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
         #[allow(clippy::cast_possible_wrap)]
@@ -40,17 +513,312 @@ impl TryIntoProcessHandle for Child {
     }
 }
 
+extern "C" {
+    /// `mach_ports_register` and `mach_ports_lookup` aren't exposed by the `mach` crate either.
+    fn mach_ports_register(
+        task: mach_port_name_t,
+        ports: *mut mach_port_t,
+        count: mach_msg_type_number_t,
+    ) -> kern_return_t;
+    fn mach_ports_lookup(
+        task: mach_port_name_t,
+        ports: *mut *mut mach_port_t,
+        count: *mut mach_msg_type_number_t,
+    ) -> kern_return_t;
+}
+
+/// How long [`CommandExt::spawn_with_task_port`] waits for the child to send its task port.
+///
+/// [`CommandExt::spawn_with_task_port`]: trait.CommandExt.html#tymethod.spawn_with_task_port
+const TASK_PORT_TIMEOUT_MS: mach_msg_timeout_t = 10_000;
+/// The `msgh_id` of the message a child sends its task port in.
+const TASK_PORT_MESSAGE_ID: mach_msg_id_t = 0x7072_6d65;
+
+/// The message a child sends its task port to its parent in.
+#[repr(C)]
+struct TaskPortMessage {
+    header: mach_msg_header_t,
+    body: mach_msg_body_t,
+    task: mach_msg_port_descriptor_t,
+}
+
+/// A [`TaskPortMessage`] as it is received, with the trailer the kernel appends.
+#[repr(C)]
+struct ReceivedTaskPortMessage {
+    message: TaskPortMessage,
+    trailer: mach_msg_trailer_t,
+}
+
+/// Extra functions on `std::process::Command` for getting a [`ProcessHandle`] to a child without
+/// the entitlements (or root) that `task_for_pid` needs.
+///
+/// macOS gives no way to get the task port of another process, even a child, without them.
+/// Instead the child has to cooperate by sending its own task port to its parent: before spawning
+/// the child, the parent registers a port it can receive on with `mach_ports_register`, which is
+/// inherited by the child. The child must then call [`send_task_port_to_parent`] as early as it
+/// can, which looks the port up with `mach_ports_lookup` and sends its task port through it. This
+/// means the child has to be a program you control that uses this crate.
+///
+/// [`ProcessHandle`]: struct.ProcessHandle.html
+/// [`send_task_port_to_parent`]: fn.send_task_port_to_parent.html
+pub trait CommandExt {
+    /// Spawn the command, and wait for the child to send its task port with
+    /// [`send_task_port_to_parent`].
+    ///
+    /// The ports registered with `mach_ports_register` are process-wide, so this shouldn't be
+    /// called while another thread is spawning processes or using them.
+    ///
+    /// # Errors
+    /// Returns an error if the command can't be spawned, or if the child doesn't send its task port
+    /// within 10 seconds, in which case the child is killed.
+    ///
+    /// [`send_task_port_to_parent`]: fn.send_task_port_to_parent.html
+    fn spawn_with_task_port(&mut self) -> std::io::Result<(Child, ProcessHandle)>;
+}
+
+impl CommandExt for std::process::Command {
+    fn spawn_with_task_port(&mut self) -> std::io::Result<(Child, ProcessHandle)> {
+        let this = unsafe { mach::traps::mach_task_self() };
+        let mut port: mach_port_name_t = MACH_PORT_NULL;
+        let result = unsafe {
+            mach::mach_port::mach_port_allocate(this, MACH_PORT_RIGHT_RECEIVE, &raw mut port)
+        };
+        if result != KERN_SUCCESS {
+            return Err(mach_error(result, "`mach_port_allocate` failed".to_owned()));
+        }
+        let child = register_port_and_spawn(self, port);
+        let task = child.and_then(|child| match receive_task_port(port, &child) {
+            Ok(task) => Ok((child, task)),
+            Err(e) => {
+                let mut child = child;
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e)
+            }
+        });
+        unsafe {
+            let _ = mach::mach_port::mach_port_destroy(this, port);
+        }
+        task
+    }
+}
+
+/// Register a send right to `port` with `mach_ports_register` while spawning `command`, so that
+/// the child inherits it, then restore the previously registered ports.
+fn register_port_and_spawn(
+    command: &mut std::process::Command,
+    port: mach_port_name_t,
+) -> std::io::Result<Child> {
+    let this = unsafe { mach::traps::mach_task_self() };
+    let result = unsafe {
+        mach::mach_port::mach_port_insert_right(this, port, port, MACH_MSG_TYPE_MAKE_SEND)
+    };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(
+            result,
+            "`mach_port_insert_right` failed".to_owned(),
+        ));
+    }
+    let mut previous = registered_ports()?;
+    let mut ports = [port];
+    let result = unsafe { mach_ports_register(this, ports.as_mut_ptr(), 1) };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(
+            result,
+            "`mach_ports_register` failed".to_owned(),
+        ));
+    }
+    let child = command.spawn();
+    #[allow(clippy::cast_possible_truncation)]
+    unsafe {
+        let _ = mach_ports_register(this, previous.as_mut_ptr(), previous.len() as _);
+        for previous in previous {
+            if previous != MACH_PORT_NULL {
+                let _ = mach::mach_port::mach_port_deallocate(this, previous);
+            }
+        }
+    }
+    child
+}
+
+/// Wait for `child` to send its task port to `port`, and check that it really is the task port of
+/// `child`.
+fn receive_task_port(port: mach_port_name_t, child: &Child) -> std::io::Result<ProcessHandle> {
+    let mut received: ReceivedTaskPortMessage = unsafe { std::mem::zeroed() };
+    #[allow(clippy::cast_possible_truncation)]
+    let result = unsafe {
+        mach::message::mach_msg(
+            &raw mut received.message.header,
+            MACH_RCV_MSG | MACH_RCV_TIMEOUT,
+            0,
+            std::mem::size_of::<ReceivedTaskPortMessage>() as _,
+            port,
+            TASK_PORT_TIMEOUT_MS,
+            MACH_PORT_NULL,
+        )
+    };
+    match result {
+        MACH_MSG_SUCCESS => {}
+        MACH_RCV_TIMED_OUT => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "The child didn't send its task port",
+            ))
+        }
+        _ => {
+            return Err(std::io::Error::other(format!(
+                "`mach_msg` failed (mach_msg_return_t {result})"
+            )))
+        }
+    }
+    let message = &received.message;
+    if message.header.msgh_id != TASK_PORT_MESSAGE_ID
+        || message.header.msgh_bits & MACH_MSGH_BITS_COMPLEX == 0
+        || message.body.msgh_descriptor_count != 1
+    {
+        unsafe { mach::message::mach_msg_destroy(&raw mut received.message.header) };
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received an unexpected message instead of the child's task port",
+        ));
+    }
+    let task = ProcessHandle::from_task(message.task.name);
+    #[allow(clippy::cast_possible_wrap)]
+    if task.get_pid()? != child.id() as Pid {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received the task port of a process other than the child",
+        ));
+    }
+    Ok(task)
+}
+
+/// Get the ports that are registered with `mach_ports_register`.
+fn registered_ports() -> std::io::Result<Vec<mach_port_t>> {
+    let this = unsafe { mach::traps::mach_task_self() };
+    let mut ports: *mut mach_port_t = std::ptr::null_mut();
+    let mut count: mach_msg_type_number_t = 0;
+    let result = unsafe { mach_ports_lookup(this, &raw mut ports, &raw mut count) };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(result, "`mach_ports_lookup` failed".to_owned()));
+    }
+    // `mach_ports_lookup` allocates the array in our address space, so we copy the ports out and
+    // free it.
+    let registered = unsafe { std::slice::from_raw_parts(ports, count as usize) }.to_vec();
+    unsafe {
+        let _ = mach::vm::mach_vm_deallocate(
+            this,
+            ports as _,
+            std::mem::size_of_val(registered.as_slice()) as _,
+        );
+    }
+    Ok(registered)
+}
+
+/// Send the task port of this process to its parent, which must have spawned it with
+/// [`CommandExt::spawn_with_task_port`]. This should be called as early as possible in the child,
+/// as the parent only waits 10 seconds for it.
+///
+/// # Errors
+/// Returns an error with a `std::io::ErrorKind` of `NotFound` if the parent didn't register a port
+/// to send the task port to, or an error if sending it fails.
+///
+/// [`CommandExt::spawn_with_task_port`]: trait.CommandExt.html#tymethod.spawn_with_task_port
+pub fn send_task_port_to_parent() -> std::io::Result<()> {
+    let this = unsafe { mach::traps::mach_task_self() };
+    let ports = registered_ports()?;
+    let parent = ports.first().copied().unwrap_or(MACH_PORT_NULL);
+    for &port in ports.iter().skip(1) {
+        if port != MACH_PORT_NULL {
+            unsafe {
+                let _ = mach::mach_port::mach_port_deallocate(this, port);
+            }
+        }
+    }
+    if parent == MACH_PORT_NULL {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "The parent didn't register a port to send the task port to",
+        ));
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let mut message = TaskPortMessage {
+        header: mach_msg_header_t {
+            msgh_bits: MACH_MSGH_BITS(MACH_MSG_TYPE_COPY_SEND, 0) | MACH_MSGH_BITS_COMPLEX,
+            msgh_size: std::mem::size_of::<TaskPortMessage>() as _,
+            msgh_remote_port: parent,
+            msgh_local_port: MACH_PORT_NULL,
+            msgh_voucher_port: MACH_PORT_NULL,
+            msgh_id: TASK_PORT_MESSAGE_ID,
+        },
+        body: mach_msg_body_t {
+            msgh_descriptor_count: 1,
+        },
+        task: mach_msg_port_descriptor_t::new(this, MACH_MSG_TYPE_COPY_SEND),
+    };
+    let result = unsafe {
+        mach::message::mach_msg(
+            &raw mut message.header,
+            MACH_SEND_MSG,
+            message.header.msgh_size,
+            0,
+            MACH_PORT_NULL,
+            MACH_MSG_TIMEOUT_NONE,
+            MACH_PORT_NULL,
+        )
+    };
+    unsafe {
+        let _ = mach::mach_port::mach_port_deallocate(this, parent);
+    }
+    if result == MACH_MSG_SUCCESS {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "`mach_msg` failed (mach_msg_return_t {result})"
+        )))
+    }
+}
+
 /// Here we use `mach_vm_write` to write a buffer to some arbitrary address on a process.
+///
+/// `mach_vm_write` has no way of telling us how much of a buffer it wrote, so we write one page
+/// at a time. That way, if the buffer straddles a page that can't be written to, we stop at that
+/// page and can report exactly where writing stopped.
 impl PutAddress for ProcessHandle {
     fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        let result = write_memory(self, addr, buf);
+        trace_write(self, addr, buf.len(), &result);
+        result
+    }
+}
+
+/// Write `buf` to `addr` in `task` with `mach_vm_write`, a page at a time.
+fn write_memory(task: &ProcessHandle, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+    task.check_writable("write memory")?;
+    let page_size = unsafe { mach::vm_page_size::vm_page_size };
+    let mut written = 0;
+    while written < buf.len() {
+        let page_addr = addr + written;
+        // The first write only goes up to the end of the first page, after which every write
+        // is page-aligned.
+        let len = (page_size - page_addr % page_size).min(buf.len() - written);
+        let page = &buf[written..written + len];
         #[allow(clippy::cast_possible_truncation)]
-        let result =
-            unsafe { mach::vm::mach_vm_write(*self, addr as _, buf.as_ptr() as _, buf.len() as _) };
+        let result = unsafe {
+            mach::vm::mach_vm_write(task.task, page_addr as _, page.as_ptr() as _, len as _)
+        };
         if result != KERN_SUCCESS {
-            return Err(std::io::Error::last_os_error());
+            return Err(mach_error(
+                result,
+                format!(
+                    "`mach_vm_write` failed at {page_addr:#x} after writing {written} of {} bytes",
+                    buf.len()
+                ),
+            ));
         }
-        Ok(())
+        written += len;
     }
+    Ok(())
 }
 
 /// Use `vm_read_overwrite` to read memory from another process on OS X.
@@ -58,33 +826,618 @@ impl PutAddress for ProcessHandle {
 /// We use `vm_read_overwrite` instead of `vm_read` because it can handle non-aligned reads and
 /// won't read an entire page.
 impl CopyAddress for ProcessHandle {
+    fn get_pointer_width(&self) -> Architecture {
+        self.arch
+    }
+
     fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
-        let mut read_len: u64 = 0;
+        let result = match self.copy_address_partial(addr, buf) {
+            Ok(read_len) if read_len == buf.len() => Ok(()),
+            Ok(read_len) => Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                format!(
+                    "Mismatched read sizes for `vm_read_overwrite` (expected {}, got {})",
+                    buf.len(),
+                    read_len
+                ),
+            )),
+            Err(err) => Err(err),
+        };
+        trace_read(self, addr, buf.len(), &result);
+        result
+    }
+
+    /// `vm_read_overwrite` tells us how many bytes it read through its `outsize` parameter.
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.copy_address_uninit(addr, crate::as_uninit_mut(buf))
+    }
+
+    /// `mach_vm_read_overwrite` copies straight into `buf`, so it doesn't need to be zeroed
+    /// first.
+    ///
+    /// The kernel refuses very large reads with `KERN_INVALID_ARGUMENT`, so reads are split into
+    /// chunks of at most `MAX_READ_SIZE` bytes, stopping at the first chunk that can't be read in
+    /// full.
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        /// The most that is read with a single call to `mach_vm_read_overwrite`.
+        const MAX_READ_SIZE: usize = 0x40_0000;
+
+        let mut read = 0;
+        for chunk in buf.chunks_mut(MAX_READ_SIZE) {
+            let chunk_addr = addr + read;
+            let mut read_len: u64 = 0;
+            let result = unsafe {
+                mach::vm::mach_vm_read_overwrite(
+                    self.task,
+                    chunk_addr as _,
+                    chunk.len() as _,
+                    chunk.as_mut_ptr() as _,
+                    &raw mut read_len,
+                )
+            };
+
+            if result != KERN_SUCCESS && read_len == 0 {
+                if read > 0 {
+                    break;
+                }
+                return Err(mach_error(
+                    result,
+                    format!("`mach_vm_read_overwrite` failed at {chunk_addr:#x}"),
+                ));
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let read_len = read_len as usize;
+            read += read_len;
+            if read_len < chunk.len() {
+                break;
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Use `mach_vm_protect` to change the protection of memory in another process on OS X.
+///
+/// `mach_vm_protect` doesn't tell us what the protection used to be, so we look that up with
+/// `mach_vm_region` first.
+impl ProtectMemory for ProcessHandle {
+    fn set_protection(
+        &self,
+        addr: usize,
+        size: usize,
+        prot: Protection,
+    ) -> std::io::Result<Protection> {
+        self.check_writable("change the protection of memory")?;
+        let old = region_info(self, addr)?.protection;
+        let mut new = to_vm_prot(prot);
+        // Mapped images are usually shared, so we need a private copy of the pages before we can
+        // write to them.
+        if prot.contains(Protection::WRITE) {
+            new |= VM_PROT_COPY;
+        }
+        let result = unsafe { mach::vm::mach_vm_protect(self.task, addr as _, size as _, 0, new) };
+        if result != KERN_SUCCESS {
+            return Err(mach_error(
+                result,
+                format!("`mach_vm_protect` failed at {addr:#x}"),
+            ));
+        }
+        Ok(from_vm_prot(old))
+    }
+}
+
+/// Use `mach_vm_allocate` and `mach_vm_deallocate` to manage memory in another process on OS X.
+impl Allocate for ProcessHandle {
+    fn allocate(&self, size: usize, prot: Protection) -> std::io::Result<usize> {
+        self.check_writable("allocate memory")?;
+        let mut address: mach::vm_types::mach_vm_address_t = 0;
         let result = unsafe {
-            mach::vm::mach_vm_read_overwrite(
-                *self,
-                addr as _,
-                buf.len() as _,
-                buf.as_mut_ptr() as _,
-                &mut read_len,
+            mach::vm::mach_vm_allocate(
+                self.task,
+                &raw mut address,
+                size as _,
+                mach::vm_statistics::VM_FLAGS_ANYWHERE,
             )
         };
+        if result != KERN_SUCCESS {
+            return Err(mach_error(result, "`mach_vm_allocate` failed".to_owned()));
+        }
+        // New allocations are always readable and writable, so we need to fix up the protection.
+        let result = unsafe {
+            mach::vm::mach_vm_protect(self.task, address, size as _, 0, to_vm_prot(prot))
+        };
+        if result != KERN_SUCCESS {
+            #[allow(clippy::cast_possible_truncation)]
+            let _ = self.free(address as usize, size);
+            return Err(mach_error(
+                result,
+                format!("`mach_vm_protect` failed at {address:#x}"),
+            ));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(address as usize)
+    }
 
+    fn free(&self, addr: usize, size: usize) -> std::io::Result<()> {
+        let result = unsafe { mach::vm::mach_vm_deallocate(self.task, addr as _, size as _) };
         if result != KERN_SUCCESS {
-            return Err(std::io::Error::last_os_error());
+            return Err(mach_error(
+                result,
+                format!("`mach_vm_deallocate` failed at {addr:#x}"),
+            ));
         }
+        Ok(())
+    }
+}
+
+/// Use `mach_vm_region_recurse` to walk the memory map of another process on OS X.
+///
+/// Some regions (such as the shared cache) are submaps containing further regions, in which case
+/// we descend into the submap and list its regions instead.
+impl EnumerateRegions for ProcessHandle {
+    fn regions(&self) -> std::io::Result<Vec<Region>> {
+        self.regions_iter()?.collect()
+    }
+
+    /// Each region is queried with `mach_vm_region_recurse` as the iterator is advanced. If a
+    /// query fails, the error is returned and the iterator ends, as there is no way to know where
+    /// the next region starts.
+    fn regions_iter(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<Region>> + '_> {
+        let mut address: mach::vm_types::mach_vm_address_t = 0;
+        let mut depth: mach::vm_types::natural_t = 0;
+        let mut done = false;
+        Ok(std::iter::from_fn(move || {
+            while !done {
+                let mut size: mach::vm_types::mach_vm_size_t = 0;
+                let mut info = mach::vm_region::vm_region_submap_info_64::default();
+                let mut count = mach::vm_region::vm_region_submap_info_64::count();
+                let result = unsafe {
+                    mach::vm::mach_vm_region_recurse(
+                        self.task,
+                        &raw mut address,
+                        &raw mut size,
+                        &raw mut depth,
+                        (&raw mut info).cast(),
+                        &raw mut count,
+                    )
+                };
+                // We get `KERN_INVALID_ADDRESS` once there are no regions left.
+                if result == KERN_INVALID_ADDRESS {
+                    done = true;
+                } else if result != KERN_SUCCESS {
+                    done = true;
+                    return Some(Err(mach_error(
+                        result,
+                        format!("`mach_vm_region_recurse` failed at {address:#x}"),
+                    )));
+                } else if info.is_submap != 0 {
+                    depth += 1;
+                } else {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let region = Region {
+                        base: address as usize,
+                        size: size as usize,
+                        protection: from_vm_prot(info.protection),
+                        is_private: matches!(
+                            info.share_mode,
+                            mach::vm_region::SM_PRIVATE | mach::vm_region::SM_PRIVATE_ALIASED
+                        ),
+                    };
+                    address += size;
+                    return Some(Ok(region));
+                }
+            }
+            None
+        }))
+    }
+
+    /// `mach_vm_region` can look up the region containing an address directly. Unlike
+    /// `regions_iter`, it doesn't descend into submaps, so an address in the shared cache is
+    /// reported as being in the whole submap, with the protection of the submap itself.
+    fn region_containing(&self, addr: usize) -> std::io::Result<Option<Region>> {
+        Ok(query_region(self, addr)?.map(|(region, _)| region))
+    }
 
-        if read_len == buf.len() as _ {
+    /// Regions aren't labelled, so this is the region holding the Mach-O header of the image
+    /// called `name`. There is nothing like `[heap]`, as malloc zones are made of many regions.
+    fn find_region_by_name(&self, name: &str) -> std::io::Result<Option<Region>> {
+        crate::module_region(self, name)
+    }
+}
+
+/// Tasks can be suspended and resumed with `task_suspend` and `task_resume`. These keep a count,
+/// so the task only runs again once it has been resumed as many times as it was suspended.
+impl ControlProcess for ProcessHandle {
+    fn suspend(&self) -> std::io::Result<()> {
+        let result = unsafe { mach::task::task_suspend(self.task) };
+        if result == KERN_SUCCESS {
             Ok(())
         } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::BrokenPipe,
-                format!(
-                    "Mismatched read sizes for `vm_read_overwrite` (expected {}, got {})",
-                    buf.len(),
-                    read_len
-                ),
-            ))
+            Err(mach_error(result, "`task_suspend` failed".to_owned()))
+        }
+    }
+
+    fn resume(&self) -> std::io::Result<()> {
+        let result = unsafe { mach::task::task_resume(self.task) };
+        if result == KERN_SUCCESS {
+            Ok(())
+        } else {
+            Err(mach_error(result, "`task_resume` failed".to_owned()))
+        }
+    }
+}
+
+/// Threads are listed with `task_threads`, which returns them in the order they were created, so
+/// the first is the main thread. Each thread's id is looked up with `thread_info`.
+impl EnumerateThreads for ProcessHandle {
+    fn threads(&self) -> std::io::Result<Vec<ThreadInfo>> {
+        let this = unsafe { mach::traps::mach_task_self() };
+        let mut list: mach::mach_types::thread_act_array_t = std::ptr::null_mut();
+        let mut count: mach_msg_type_number_t = 0;
+        let result = unsafe { mach::task::task_threads(self.task, &raw mut list, &raw mut count) };
+        if result != KERN_SUCCESS {
+            return Err(mach_error(result, "`task_threads` failed".to_owned()));
+        }
+        let ports = unsafe { std::slice::from_raw_parts(list, count as usize) };
+        let threads = ports
+            .iter()
+            .enumerate()
+            .map(|(index, &port)| {
+                let tid = thread_id(port);
+                // Each thread port is a send right that we own, which must be released.
+                unsafe {
+                    let _ = mach::mach_port::mach_port_deallocate(this, port);
+                }
+                Ok(ThreadInfo {
+                    tid: tid?,
+                    is_main: index == 0,
+                })
+            })
+            .collect();
+        unsafe {
+            let _ =
+                mach::vm::mach_vm_deallocate(this, list as _, std::mem::size_of_val(ports) as _);
+        }
+        threads
+    }
+
+    /// The thread is suspended with `thread_suspend` while `thread_get_state` reads its
+    /// registers.
+    fn thread_context(&self, tid: Tid) -> std::io::Result<ThreadContext> {
+        let port = thread_port(self, tid)?;
+        let context = thread_registers(port);
+        unsafe {
+            let _ = mach::mach_port::mach_port_deallocate(mach::traps::mach_task_self(), port);
+        }
+        context
+    }
+}
+
+/// Find the port of the thread of `task` whose system-wide id is `tid`. The port is a send right
+/// that the caller must deallocate.
+fn thread_port(task: &ProcessHandle, tid: Tid) -> std::io::Result<mach_port_t> {
+    let this = unsafe { mach::traps::mach_task_self() };
+    let mut list: mach::mach_types::thread_act_array_t = std::ptr::null_mut();
+    let mut count: mach_msg_type_number_t = 0;
+    let result = unsafe { mach::task::task_threads(task.task, &raw mut list, &raw mut count) };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(result, "`task_threads` failed".to_owned()));
+    }
+    let ports = unsafe { std::slice::from_raw_parts(list, count as usize) };
+    let mut found = None;
+    for &port in ports {
+        if found.is_none() && thread_id(port).ok() == Some(tid) {
+            found = Some(port);
+        } else {
+            unsafe {
+                let _ = mach::mach_port::mach_port_deallocate(this, port);
+            }
+        }
+    }
+    unsafe {
+        let _ = mach::vm::mach_vm_deallocate(this, list as _, std::mem::size_of_val(ports) as _);
+    }
+    found.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("The task has no thread {tid}"),
+        )
+    })
+}
+
+/// Read the registers of the thread `port`, suspending it while we do.
+fn thread_registers(port: mach_port_t) -> std::io::Result<ThreadContext> {
+    #[cfg(target_arch = "x86_64")]
+    let flavor = mach::thread_status::x86_THREAD_STATE64;
+    #[cfg(target_arch = "aarch64")]
+    let flavor: mach::thread_status::thread_state_flavor_t = 6;
+
+    let result = unsafe { mach::thread_act::thread_suspend(port) };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(result, "`thread_suspend` failed".to_owned()));
+    }
+    let mut state = RawThreadContext::default();
+    #[allow(clippy::cast_possible_truncation)]
+    let mut count = (std::mem::size_of::<RawThreadContext>()
+        / std::mem::size_of::<mach::vm_types::natural_t>())
+        as mach_msg_type_number_t;
+    let result = unsafe {
+        mach::thread_act::thread_get_state(port, flavor, (&raw mut state).cast(), &raw mut count)
+    };
+    unsafe {
+        let _ = mach::thread_act::thread_resume(port);
+    }
+    if result != KERN_SUCCESS {
+        return Err(mach_error(result, "`thread_get_state` failed".to_owned()));
+    }
+    #[cfg(target_arch = "x86_64")]
+    let (instruction_pointer, stack_pointer) = (state.__rip, state.__rsp);
+    #[cfg(target_arch = "aarch64")]
+    let (instruction_pointer, stack_pointer) = (state.pc, state.sp);
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(ThreadContext {
+        instruction_pointer: instruction_pointer as usize,
+        stack_pointer: stack_pointer as usize,
+        raw: state,
+    })
+}
+
+/// Get the system-wide id of the thread `port` with `thread_info` and `THREAD_IDENTIFIER_INFO`.
+fn thread_id(port: mach_port_t) -> std::io::Result<Tid> {
+    let mut info: libc::thread_identifier_info = unsafe { std::mem::zeroed() };
+    let mut count = libc::THREAD_IDENTIFIER_INFO_COUNT;
+    let result = unsafe {
+        libc::thread_info(
+            port,
+            libc::THREAD_IDENTIFIER_INFO as _,
+            (&raw mut info).cast(),
+            &raw mut count,
+        )
+    };
+    if result == KERN_SUCCESS {
+        Ok(info.thread_id)
+    } else {
+        Err(mach_error(result, "`thread_info` failed".to_owned()))
+    }
+}
+
+/// Modules are found by walking the list of images that dyld has loaded into the task, which dyld
+/// publishes through `task_info` with `TASK_DYLD_INFO`.
+impl FindModule for ProcessHandle {
+    fn get_module_base(&self, name: &str) -> std::io::Result<usize> {
+        find_image(self, name)
+    }
+
+    fn get_module_size(&self, name: &str) -> std::io::Result<usize> {
+        image_size(self, find_image(self, name)?)
+    }
+
+    /// The executable is the image whose Mach-O header has a file type of `MH_EXECUTE`, which
+    /// dyld normally lists first.
+    fn main_module_base(&self) -> std::io::Result<usize> {
+        const MH_EXECUTE: u32 = 0x2;
+
+        for (base, _) in images(self)? {
+            // The file type follows the magic number, CPU type and CPU subtype.
+            let mut header = [0_u8; 16];
+            if self.copy_address(base, &mut header).is_ok()
+                && u32::from_ne_bytes(header[12..].try_into().unwrap()) == MH_EXECUTE
+            {
+                return Ok(base);
+            }
         }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "The executable isn't among the loaded images",
+        ))
     }
+
+    fn to_module_relative(&self, addr: usize) -> std::io::Result<Option<(String, usize)>> {
+        for (base, path) in images(self)? {
+            if base <= addr && addr - base < image_size(self, base)? {
+                let name = std::path::Path::new(&path)
+                    .file_name()
+                    .map_or(path.clone(), |name| name.to_string_lossy().into_owned());
+                return Ok(Some((name, addr - base)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Get the load address and path of every image dyld has loaded into `task`, by reading its
+/// `dyld_all_image_infos`. We only support 64-bit tasks, as macOS can no longer run 32-bit ones.
+fn images(task: &ProcessHandle) -> std::io::Result<Vec<(usize, String)>> {
+    let mut dyld_info = mach::task_info::task_dyld_info::default();
+    #[allow(clippy::cast_possible_truncation)]
+    let mut count = (std::mem::size_of::<mach::task_info::task_dyld_info>()
+        / std::mem::size_of::<mach::vm_types::natural_t>())
+        as mach::message::mach_msg_type_number_t;
+    let result = unsafe {
+        mach::task::task_info(
+            task.task,
+            mach::task_info::TASK_DYLD_INFO,
+            (&raw mut dyld_info).cast(),
+            &raw mut count,
+        )
+    };
+    if result != KERN_SUCCESS {
+        return Err(mach_error(result, "`task_info` failed".to_owned()));
+    }
+
+    // `dyld_all_image_infos` starts with a `u32` version and a `u32` count, followed by a pointer
+    // to an array of `dyld_image_info`s.
+    #[allow(clippy::cast_possible_truncation)]
+    let all_image_infos = dyld_info.all_image_info_addr as usize;
+    let mut header = [0_u8; 16];
+    task.copy_address(all_image_infos, &mut header)?;
+    let count = u32::from_ne_bytes(header[4..8].try_into().unwrap()) as usize;
+    #[allow(clippy::cast_possible_truncation)]
+    let array = u64::from_ne_bytes(header[8..].try_into().unwrap()) as usize;
+
+    // Each `dyld_image_info` is a load address, a path pointer and a modification date.
+    let mut infos = vec![0_u8; count * 24];
+    task.copy_address(array, &mut infos)?;
+    infos
+        .chunks_exact(24)
+        .map(|info| {
+            #[allow(clippy::cast_possible_truncation)]
+            let base = u64::from_ne_bytes(info[..8].try_into().unwrap()) as usize;
+            #[allow(clippy::cast_possible_truncation)]
+            let path = u64::from_ne_bytes(info[8..16].try_into().unwrap()) as usize;
+            let path = crate::read_cstring(task, path, libc::PATH_MAX as usize)?;
+            Ok((base, path.to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+/// Find the load address of the image called `name`.
+fn find_image(task: &ProcessHandle, name: &str) -> std::io::Result<usize> {
+    images(task)?
+        .into_iter()
+        .find(|(_, path)| crate::module_name_matches(path, name))
+        .map(|(base, _)| base)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("No module named {name:?} is loaded"),
+            )
+        })
+}
+
+/// Work out the size of the image loaded at `base` from its Mach-O header, as the distance from
+/// the start of its first segment to the end of its last. `__PAGEZERO` is skipped as it isn't
+/// really part of the image, and so is `__LINKEDIT`, which for images in the shared cache is
+/// shared between every image and lives far away from the rest of the image.
+fn image_size(task: &ProcessHandle, base: usize) -> std::io::Result<usize> {
+    const MH_MAGIC_64: u32 = 0xfeed_facf;
+    const LC_SEGMENT_64: u32 = 0x19;
+
+    // `mach_header_64` is 32 bytes, with the number and total size of the load commands at 16.
+    let mut header = [0_u8; 32];
+    task.copy_address(base, &mut header)?;
+    if u32::from_ne_bytes(header[..4].try_into().unwrap()) != MH_MAGIC_64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("No 64-bit Mach-O header at {base:#x}"),
+        ));
+    }
+    let ncmds = u32::from_ne_bytes(header[16..20].try_into().unwrap());
+    let sizeofcmds = u32::from_ne_bytes(header[20..24].try_into().unwrap()) as usize;
+    let mut commands = vec![0_u8; sizeofcmds];
+    task.copy_address(base + header.len(), &mut commands)?;
+
+    // Each `segment_command_64` has a 16 byte name at 8, then its `vmaddr` and `vmsize`.
+    let mut start = u64::MAX;
+    let mut end = 0_u64;
+    let mut offset = 0;
+    for _ in 0..ncmds {
+        let command = commands.get(offset..offset + 8).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Truncated load commands in the Mach-O header at {base:#x}"),
+            )
+        })?;
+        let cmd = u32::from_ne_bytes(command[..4].try_into().unwrap());
+        let cmdsize = u32::from_ne_bytes(command[4..].try_into().unwrap()) as usize;
+        if cmd == LC_SEGMENT_64 && offset + 40 <= commands.len() {
+            let segment = &commands[offset..offset + 40];
+            let segname = &segment[8..24];
+            if !segname.starts_with(b"__PAGEZERO\0") && !segname.starts_with(b"__LINKEDIT\0") {
+                let vmaddr = u64::from_ne_bytes(segment[24..32].try_into().unwrap());
+                let vmsize = u64::from_ne_bytes(segment[32..40].try_into().unwrap());
+                start = start.min(vmaddr);
+                end = end.max(vmaddr + vmsize);
+            }
+        }
+        offset += cmdsize.max(8);
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(end.saturating_sub(start) as usize)
+}
+
+/// Use `mach_vm_region` to get the basic information of the region containing `addr`.
+fn region_info(
+    task: &ProcessHandle,
+    addr: usize,
+) -> std::io::Result<mach::vm_region::vm_region_basic_info_64> {
+    query_region(task, addr)?
+        .map(|(_, info)| info)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{addr:#x} is not mapped in the target process"),
+            )
+        })
+}
+
+/// Use `mach_vm_region` to look up the region containing `addr`, returning it along with its basic
+/// information, or `None` if `addr` isn't mapped.
+fn query_region(
+    task: &ProcessHandle,
+    addr: usize,
+) -> std::io::Result<Option<(Region, mach::vm_region::vm_region_basic_info_64)>> {
+    let mut info = mach::vm_region::vm_region_basic_info_64::default();
+    let mut count = mach::vm_region::vm_region_basic_info_64::count();
+    let mut address = addr as mach::vm_types::mach_vm_address_t;
+    let mut size: mach::vm_types::mach_vm_size_t = 0;
+    let mut object_name: mach::port::mach_port_t = MACH_PORT_NULL;
+    let result = unsafe {
+        mach::vm::mach_vm_region(
+            task.task,
+            &raw mut address,
+            &raw mut size,
+            mach::vm_region::VM_REGION_BASIC_INFO_64,
+            (&raw mut info).cast(),
+            &raw mut count,
+            &raw mut object_name,
+        )
+    };
+    // We get `KERN_INVALID_ADDRESS` if there are no regions at or above `addr`, and if `addr`
+    // isn't mapped but there is a region above it, `mach_vm_region` gives us that region instead.
+    if result == KERN_INVALID_ADDRESS || address > addr as mach::vm_types::mach_vm_address_t {
+        return Ok(None);
+    } else if result != KERN_SUCCESS {
+        return Err(mach_error(
+            result,
+            format!("`mach_vm_region` failed at {addr:#x}"),
+        ));
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let region = Region {
+        base: address as usize,
+        size: size as usize,
+        protection: from_vm_prot(info.protection),
+        is_private: info.shared == 0,
+    };
+    Ok(Some((region, info)))
+}
+
+/// Turn a `Protection` into the equivalent `vm_prot_t`.
+fn to_vm_prot(prot: Protection) -> vm_prot_t {
+    let mut vm_prot = 0;
+    if prot.contains(Protection::READ) {
+        vm_prot |= VM_PROT_READ;
+    }
+    if prot.contains(Protection::WRITE) {
+        vm_prot |= VM_PROT_WRITE;
+    }
+    if prot.contains(Protection::EXECUTE) {
+        vm_prot |= VM_PROT_EXECUTE;
+    }
+    vm_prot
+}
+
+/// Turn a `vm_prot_t` into the equivalent `Protection`.
+fn from_vm_prot(vm_prot: vm_prot_t) -> Protection {
+    let mut prot = Protection::empty();
+    prot.set(Protection::READ, vm_prot & VM_PROT_READ != 0);
+    prot.set(Protection::WRITE, vm_prot & VM_PROT_WRITE != 0);
+    prot.set(Protection::EXECUTE, vm_prot & VM_PROT_EXECUTE != 0);
+    prot
 }
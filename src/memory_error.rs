@@ -0,0 +1,258 @@
+/// An error from reading or writing memory, classified by what caused it, so that callers can
+/// react differently to the target process going away and to an address that can't be used.
+///
+/// Every variant keeps the original `std::io::Error`, which has the full details of what went
+/// wrong. A `MemoryError` can be made from any `std::io::Error`, such as one returned by
+/// [`Memory::read`], and turned back into one.
+///
+/// [`Memory::read`]: trait.Memory.html#tymethod.read
+#[derive(Debug)]
+pub enum MemoryError {
+    /// The process has exited, or the handle to it is no longer valid.
+    ProcessGone(std::io::Error),
+    /// An address, either in the chain of offsets or the value itself, can't be read or written.
+    AddressUnreadable(std::io::Error),
+    /// One of the pointers in the chain of offsets is null, such as when the object it points to
+    /// hasn't been created yet.
+    NullInChain(std::io::Error),
+    /// We don't have permission to access the memory of the process.
+    PermissionDenied(std::io::Error),
+    /// Any other error.
+    Other(std::io::Error),
+}
+
+impl MemoryError {
+    /// Get the `std::io::Error` that caused this error.
+    #[must_use]
+    pub fn io_error(&self) -> &std::io::Error {
+        match self {
+            MemoryError::ProcessGone(err)
+            | MemoryError::AddressUnreadable(err)
+            | MemoryError::NullInChain(err)
+            | MemoryError::PermissionDenied(err)
+            | MemoryError::Other(err) => err,
+        }
+    }
+
+    /// Get the error code from the operating system (`errno` on Unix, `GetLastError` on Windows)
+    /// that caused this error, if there was one.
+    ///
+    /// Unlike `std::io::Error::raw_os_error`, this also finds the code of errors that this crate
+    /// has replaced with a more descriptive message, such as the `EFAULT` behind an error for an
+    /// address that couldn't be written to on Linux, or the `ERROR_PARTIAL_COPY` behind a read
+    /// that stopped part of the way through on Windows. On macOS, errors from Mach calls have a
+    /// `kern_return_t` instead, which can be got from the `MachError` they wrap.
+    #[must_use]
+    pub fn raw_os_error(&self) -> Option<i32> {
+        os_error_code(self.io_error())
+    }
+}
+
+impl std::fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.io_error().fmt(f)
+    }
+}
+
+impl std::error::Error for MemoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.io_error())
+    }
+}
+
+/// Errors from the operating system are classified by their error code, and errors made by this
+/// crate by how they were made. Anything else is `Other`, unless it has a `std::io::ErrorKind` of
+/// `PermissionDenied`.
+impl From<std::io::Error> for MemoryError {
+    fn from(err: std::io::Error) -> Self {
+        let class = err.raw_os_error().and_then(classify_os_error).or_else(|| {
+            let inner = err.get_ref()?;
+            #[cfg(target_os = "macos")]
+            if let Some(inner) = inner.downcast_ref::<crate::MachError>() {
+                return inner.class();
+            }
+            inner
+                .downcast_ref::<ClassifiedError>()
+                .map(|inner| inner.class)
+        });
+        match class {
+            Some(ErrorClass::ProcessGone) => MemoryError::ProcessGone(err),
+            Some(ErrorClass::AddressUnreadable) => MemoryError::AddressUnreadable(err),
+            Some(ErrorClass::NullInChain) => MemoryError::NullInChain(err),
+            Some(ErrorClass::PermissionDenied) => MemoryError::PermissionDenied(err),
+            None if err.kind() == std::io::ErrorKind::PermissionDenied => {
+                MemoryError::PermissionDenied(err)
+            }
+            None => MemoryError::Other(err),
+        }
+    }
+}
+
+impl From<MemoryError> for std::io::Error {
+    fn from(err: MemoryError) -> Self {
+        match err {
+            MemoryError::ProcessGone(err)
+            | MemoryError::AddressUnreadable(err)
+            | MemoryError::NullInChain(err)
+            | MemoryError::PermissionDenied(err)
+            | MemoryError::Other(err) => err,
+        }
+    }
+}
+
+/// An error from [`resolve_chain`], along with every address that was resolved before the chain
+/// broke.
+///
+/// [`resolve_chain`]: fn.resolve_chain.html
+#[derive(Debug)]
+pub struct ChainError {
+    /// The addresses that were resolved, in the same order as [`resolve_chain`] returns them. The
+    /// last one is the address that couldn't be read, or that held a null pointer.
+    ///
+    /// [`resolve_chain`]: fn.resolve_chain.html
+    pub resolved: Vec<usize>,
+    /// The error that broke the chain.
+    pub error: std::io::Error,
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The chain broke after {} addresses: {}",
+            self.resolved.len(),
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for ChainError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The resolved addresses are dropped, leaving just the error that broke the chain.
+impl From<ChainError> for std::io::Error {
+    fn from(err: ChainError) -> Self {
+        err.error
+    }
+}
+
+/// The `MemoryError` variant that an error belongs in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ErrorClass {
+    ProcessGone,
+    AddressUnreadable,
+    NullInChain,
+    PermissionDenied,
+}
+
+/// Make a `std::io::Error` that is classified as `class` when it is turned into a `MemoryError`,
+/// for errors that don't come with an error code from the operating system.
+pub(crate) fn classified_error(
+    kind: std::io::ErrorKind,
+    class: ErrorClass,
+    message: String,
+) -> std::io::Error {
+    std::io::Error::new(
+        kind,
+        ClassifiedError {
+            class,
+            message,
+            os_error: None,
+        },
+    )
+}
+
+/// Replace `err`, an error from the operating system, with one made by [`classified_error`] that
+/// keeps `err`'s error code, so that `MemoryError::raw_os_error` can still find it.
+#[cfg(any(target_os = "linux", target_os = "android", windows))]
+pub(crate) fn classified_os_error(
+    err: &std::io::Error,
+    kind: std::io::ErrorKind,
+    class: ErrorClass,
+    message: String,
+) -> std::io::Error {
+    std::io::Error::new(
+        kind,
+        ClassifiedError {
+            class,
+            message,
+            os_error: err.raw_os_error(),
+        },
+    )
+}
+
+/// Get the error code from the operating system behind `err`, including for errors made by
+/// [`classified_os_error`].
+pub(crate) fn os_error_code(err: &std::io::Error) -> Option<i32> {
+    err.raw_os_error()
+        .or_else(|| err.get_ref()?.downcast_ref::<ClassifiedError>()?.os_error)
+}
+
+/// Make a copy of `err`, an error from the operating system or one made by this crate, that keeps
+/// its kind, message, error code and class, for when the same cause fails several requests.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn copy_error(err: &std::io::Error) -> std::io::Error {
+    if let Some(code) = err.raw_os_error() {
+        return std::io::Error::from_raw_os_error(code);
+    }
+    match err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<ClassifiedError>())
+    {
+        Some(inner) => std::io::Error::new(
+            err.kind(),
+            ClassifiedError {
+                class: inner.class,
+                message: inner.message.clone(),
+                os_error: inner.os_error,
+            },
+        ),
+        None => std::io::Error::new(err.kind(), err.to_string()),
+    }
+}
+
+/// The payload of an error made by [`classified_error`], which displays as just its message.
+#[derive(Debug)]
+struct ClassifiedError {
+    class: ErrorClass,
+    message: String,
+    os_error: Option<i32>,
+}
+
+impl std::fmt::Display for ClassifiedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ClassifiedError {}
+
+/// Classify an `errno` value.
+#[cfg(unix)]
+fn classify_os_error(code: i32) -> Option<ErrorClass> {
+    match code {
+        libc::ESRCH => Some(ErrorClass::ProcessGone),
+        libc::EFAULT | libc::EIO => Some(ErrorClass::AddressUnreadable),
+        libc::EPERM | libc::EACCES => Some(ErrorClass::PermissionDenied),
+        _ => None,
+    }
+}
+
+/// Classify a Windows system error code.
+#[cfg(windows)]
+fn classify_os_error(code: i32) -> Option<ErrorClass> {
+    use winapi::shared::winerror;
+
+    #[allow(clippy::cast_sign_loss)]
+    match code as u32 {
+        winerror::ERROR_INVALID_HANDLE => Some(ErrorClass::ProcessGone),
+        winerror::ERROR_PARTIAL_COPY
+        | winerror::ERROR_NOACCESS
+        | winerror::ERROR_INVALID_ADDRESS => Some(ErrorClass::AddressUnreadable),
+        winerror::ERROR_ACCESS_DENIED => Some(ErrorClass::PermissionDenied),
+        _ => None,
+    }
+}
@@ -0,0 +1,153 @@
+use std::convert::TryFrom;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{CopyAddress, ProcessHandle};
+
+/// A cursor over the memory of a process, so that anything that parses a `std::io::Read` can
+/// parse memory directly, without copying it into a `Vec` first.
+///
+/// Each call to [`Read::read`] copies as much as it can from the cursor address with
+/// [`CopyAddress::copy_address_partial`], and moves the cursor past the bytes that were copied.
+/// Reading memory that can't be read at all is an error, rather than the end of the stream, so
+/// `read_to_end` will always fail once it reaches unmapped memory. The cursor can be moved with
+/// [`Seek`], relative to address zero or to the cursor, but as a process's memory has no defined
+/// end, `SeekFrom::End` fails with a `std::io::ErrorKind` of `Unsupported`.
+///
+/// ```rust
+/// # use process_memory::{Pid, ProcessMemoryReader, TryIntoProcessHandle};
+/// use std::io::{Read, Seek, SeekFrom};
+///
+/// let values = [0x1234_5678_u32, 0x9abc_def0];
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let mut reader = ProcessMemoryReader::new(handle, values.as_ptr() as usize);
+///
+/// let mut bytes = [0_u8; 4];
+/// let _ = reader.seek(SeekFrom::Current(4)).unwrap();
+/// reader.read_exact(&mut bytes).unwrap();
+/// assert_eq!(u32::from_ne_bytes(bytes), 0x9abc_def0);
+/// ```
+///
+/// [`Read::read`]: https://doc.rust-lang.org/std/io/trait.Read.html#tymethod.read
+/// [`Seek`]: https://doc.rust-lang.org/std/io/trait.Seek.html
+/// [`CopyAddress::copy_address_partial`]: trait.CopyAddress.html#method.copy_address_partial
+#[derive(Clone, Debug)]
+pub struct ProcessMemoryReader<H = ProcessHandle> {
+    handle: H,
+    position: usize,
+}
+
+impl<H: CopyAddress> ProcessMemoryReader<H> {
+    /// Create a reader over the memory of `handle`, with its cursor at `addr`.
+    #[must_use]
+    pub fn new(handle: H, addr: usize) -> Self {
+        Self {
+            handle,
+            position: addr,
+        }
+    }
+
+    /// Get the address that the next read will start at.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the handle that memory is read through.
+    #[must_use]
+    pub fn get_ref(&self) -> &H {
+        &self.handle
+    }
+
+    /// Consume the reader, returning the handle that memory was read through.
+    #[must_use]
+    pub fn into_inner(self) -> H {
+        self.handle
+    }
+}
+
+impl<H: CopyAddress> Read for ProcessMemoryReader<H> {
+    /// Reads stop at the end of the address space, which is reported as the end of the stream.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(usize::MAX - self.position);
+        if len == 0 {
+            return Ok(0);
+        }
+        let copied = self
+            .handle
+            .copy_address_partial(self.position, &mut buf[..len])?;
+        self.position += copied;
+        Ok(copied)
+    }
+}
+
+impl<H: CopyAddress> Seek for ProcessMemoryReader<H> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(addr) => usize::try_from(addr).ok(),
+            SeekFrom::Current(offset) => isize::try_from(offset)
+                .ok()
+                .and_then(|offset| self.position.checked_add_signed(offset)),
+            SeekFrom::End(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "The memory of a process has no end to seek from",
+                ))
+            }
+        };
+        self.position = position.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Seeking to {pos:?} from {:#x} leaves the address space",
+                    self.position
+                ),
+            )
+        })?;
+        Ok(self.position as u64)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        Ok(self.position as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TryIntoProcessHandle;
+
+    #[test]
+    fn read_and_seek_own_memory() {
+        let values = [1_u16, 2, 3, 4];
+        let base = values.as_ptr() as usize;
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as crate::Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut reader = ProcessMemoryReader::new(handle, base);
+
+        let mut bytes = [0_u8; 4];
+        reader.read_exact(&mut bytes).unwrap();
+        assert_eq!(bytes[..2], 1_u16.to_ne_bytes());
+        assert_eq!(reader.position(), base + 4);
+        assert_eq!(reader.seek(SeekFrom::Current(2)).unwrap(), base as u64 + 6);
+        reader.read_exact(&mut bytes[..2]).unwrap();
+        assert_eq!(bytes[..2], 4_u16.to_ne_bytes());
+        assert_eq!(reader.seek(SeekFrom::Current(-6)).unwrap(), base as u64 + 2);
+        reader.read_exact(&mut bytes[..2]).unwrap();
+        assert_eq!(bytes[..2], 2_u16.to_ne_bytes());
+
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::Current(i64::MIN)).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(reader.stream_position().unwrap(), base as u64 + 4);
+
+        let _ = reader.seek(SeekFrom::Start(0)).unwrap();
+        assert!(reader.read(&mut bytes).is_err());
+    }
+}
@@ -0,0 +1,25 @@
+/// A chain of offsets that starts from the base address of a module, such as `"game.exe" + 0x1234,
+/// 0x10, 0x8`, which can be turned into a [`DataMember`] with [`DataMember::from_path`].
+///
+/// Unlike plain offsets, a `PointerPath` stays correct when the module is loaded at a different
+/// address, which makes it suitable to be stored in a configuration file. With the `serde`
+/// feature enabled it implements `Serialize` and `Deserialize`.
+///
+/// ```rust
+/// # use process_memory::PointerPath;
+/// let path = PointerPath {
+///     module: "game.exe".to_owned(),
+///     offsets: vec![0x1234, 0x10, 0x8],
+/// };
+/// ```
+///
+/// [`DataMember`]: struct.DataMember.html
+/// [`DataMember::from_path`]: struct.DataMember.html#method.from_path
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerPath {
+    /// The name of the module that the first offset is relative to.
+    pub module: String,
+    /// The offsets to follow, the first of which is relative to the base address of `module`.
+    pub offsets: Vec<usize>,
+}
@@ -0,0 +1,16 @@
+bitflags::bitflags! {
+    /// The access protection of a region of memory.
+    ///
+    /// Not every platform can represent every combination of flags. For example, Windows has no
+    /// notion of memory that can be written to but not read, so asking for `WRITE` on its own
+    /// will also make the memory readable.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+    pub struct Protection: u8 {
+        /// The memory can be read.
+        const READ = 1;
+        /// The memory can be written to.
+        const WRITE = 1 << 1;
+        /// The memory can be executed.
+        const EXECUTE = 1 << 2;
+    }
+}
@@ -0,0 +1,129 @@
+/// A `#[repr(C)]` struct whose layout is known to match that of a struct in the target, which can
+/// be read with [`DataMember::read_struct`].
+///
+/// `SIZE` and `ALIGN` describe the struct in the target, and are checked against the size and
+/// alignment of `Self` before every read, so that a layout mismatch is an error rather than
+/// silently reading the wrong number of bytes. Implement this with [`read_struct_layout!`], which
+/// also checks them, along with the offsets of any fields that are listed, at compile time.
+///
+/// # Safety
+/// Every bit pattern of `SIZE` bytes must be a valid value of `Self`, as the bytes are copied
+/// straight from the target. This is the case for `#[repr(C)]` structs made up of integers,
+/// floats and arrays or structs of them, but not for structs containing references, `bool`s,
+/// `char`s or most enums.
+///
+/// [`DataMember::read_struct`]: struct.DataMember.html#method.read_struct
+/// [`read_struct_layout!`]: macro.read_struct_layout.html
+pub unsafe trait ReadStruct: Copy {
+    /// The size in bytes of the struct in the target.
+    const SIZE: usize;
+    /// The alignment in bytes of the struct in the target.
+    const ALIGN: usize;
+}
+
+/// Check that the layout of `S` matches the size and alignment it claims to have in the target.
+pub(crate) fn check_layout<S: ReadStruct>() -> std::io::Result<()> {
+    if std::mem::size_of::<S>() == S::SIZE && std::mem::align_of::<S>() == S::ALIGN {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "{} is {} bytes with an alignment of {}, but is {} bytes with an alignment of {} \
+                 in the target",
+                std::any::type_name::<S>(),
+                std::mem::size_of::<S>(),
+                std::mem::align_of::<S>(),
+                S::SIZE,
+                S::ALIGN
+            ),
+        ))
+    }
+}
+
+/// Implement [`ReadStruct`] for a `#[repr(C)]` struct, checking at compile time that its size,
+/// alignment and the offsets of the listed fields match the layout of the struct in the target.
+///
+/// The `unsafe` is part of the syntax, as implementing [`ReadStruct`] promises that every bit
+/// pattern is a valid value of the struct. Fields that aren't listed aren't checked.
+///
+/// ```rust
+/// use process_memory::{read_struct_layout, ReadStruct};
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Player {
+///     id: u32,
+///     health: f32,
+///     position: [f64; 3],
+/// }
+///
+/// read_struct_layout! {
+///     unsafe impl ReadStruct for Player {
+///         size: 0x20,
+///         align: 8,
+///         health: 0x4,
+///         position: 0x8,
+///     }
+/// }
+/// ```
+///
+/// A layout that doesn't match fails to compile:
+///
+/// ```compile_fail
+/// # use process_memory::{read_struct_layout, ReadStruct};
+/// # #[derive(Clone, Copy)]
+/// # #[repr(C)]
+/// # struct Player {
+/// #     id: u32,
+/// #     health: f32,
+/// #     position: [f64; 3],
+/// # }
+/// read_struct_layout! {
+///     unsafe impl ReadStruct for Player {
+///         size: 0x20,
+///         align: 8,
+///         position: 0x10,
+///     }
+/// }
+/// ```
+///
+/// [`ReadStruct`]: trait.ReadStruct.html
+#[macro_export]
+macro_rules! read_struct_layout {
+    (
+        unsafe impl ReadStruct for $ty:ty {
+            size: $size:expr,
+            align: $align:expr
+            $(, $field:ident: $offset:expr)* $(,)?
+        }
+    ) => {
+        unsafe impl $crate::ReadStruct for $ty {
+            const SIZE: usize = $size;
+            const ALIGN: usize = $align;
+        }
+
+        const _: () = {
+            assert!(
+                ::std::mem::size_of::<$ty>() == $size,
+                concat!("The size of ", stringify!($ty), " doesn't match the target")
+            );
+            assert!(
+                ::std::mem::align_of::<$ty>() == $align,
+                concat!("The alignment of ", stringify!($ty), " doesn't match the target")
+            );
+            $(
+                assert!(
+                    ::std::mem::offset_of!($ty, $field) == $offset,
+                    concat!(
+                        "The offset of ",
+                        stringify!($ty),
+                        "::",
+                        stringify!($field),
+                        " doesn't match the target"
+                    )
+                );
+            )*
+        };
+    };
+}
@@ -0,0 +1,29 @@
+use crate::Protection;
+
+/// A contiguous region of memory that is mapped into a process.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Region {
+    /// The address of the start of the region.
+    pub base: usize,
+    /// The size of the region in bytes.
+    pub size: usize,
+    /// The access protection of the region.
+    pub protection: Protection,
+    /// Whether the region is private to the process, rather than being shared with other
+    /// processes (such as a shared mapping of a file).
+    pub is_private: bool,
+}
+
+impl Region {
+    /// The address one past the end of the region.
+    #[must_use]
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    /// Returns `true` if `addr` lies within the region.
+    #[must_use]
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr - self.base < self.size
+    }
+}
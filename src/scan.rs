@@ -0,0 +1,600 @@
+//! Functions for scanning the memory of a process for byte patterns (signatures) and for values.
+//!
+//! Searching for patterns and pointers works on anything that implements `CopyAddress`, such as a
+//! `SnapshotSource` as well as a `ProcessHandle`. Scanning all of memory, with
+//! `find_pattern_parallel` or `scan_value`, also needs `EnumerateRegions` to list the regions.
+//!
+//! ```rust
+//! # use process_memory::{Pid, TryIntoProcessHandle};
+//! # use process_memory::scan::{find_pattern, parse_pattern};
+//! let code = [0x55_u8, 0x48, 0x8B, 0xEC, 0xC3];
+//! let start = code.as_ptr() as usize;
+//! let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+//! let pattern = parse_pattern("48 8B ?? C3").unwrap();
+//! let found = find_pattern(&handle, start..start + code.len(), &pattern).unwrap();
+//! assert_eq!(found, Some(start + 1));
+//! ```
+use std::ops::Range;
+
+use crate::{CopyAddress, EnumerateRegions, Protection};
+
+/// Parse an IDA-style pattern such as `"48 8B ?? C3"` into a pattern for [`find_pattern`].
+///
+/// Bytes are written as two hexadecimal digits and separated by whitespace, and `?` or `??` is a
+/// wildcard that matches any byte.
+///
+/// # Errors
+/// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if any part of `pattern` is
+/// neither a byte nor a wildcard.
+///
+/// [`find_pattern`]: fn.find_pattern.html
+pub fn parse_pattern(pattern: &str) -> std::io::Result<Vec<Option<u8>>> {
+    pattern
+        .split_whitespace()
+        .map(|byte| match byte {
+            "?" | "??" => Ok(None),
+            _ if byte.len() == 2 => u8::from_str_radix(byte, 16).map(Some).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Invalid byte {byte:?} in pattern"),
+                )
+            }),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid byte {byte:?} in pattern"),
+            )),
+        })
+        .collect()
+}
+
+/// Find the address of the first match of `pattern` within `region` of the memory of `handle`.
+/// `None` entries in `pattern` are wildcards that match any byte.
+///
+/// # Errors
+/// Returns an error if copying memory fails, or an error with a `std::io::ErrorKind` of
+/// `InvalidInput` if `pattern` is empty.
+pub fn find_pattern<T: CopyAddress>(
+    handle: &T,
+    region: Range<usize>,
+    pattern: &[Option<u8>],
+) -> std::io::Result<Option<usize>> {
+    let mut found = None;
+    scan(handle, region, pattern, |addr| {
+        found = Some(addr);
+        false
+    })?;
+    Ok(found)
+}
+
+/// Find the addresses of every match of `pattern` within `region` of the memory of `handle`.
+/// `None` entries in `pattern` are wildcards that match any byte. Matches may overlap.
+///
+/// # Errors
+/// Returns an error if copying memory fails, or an error with a `std::io::ErrorKind` of
+/// `InvalidInput` if `pattern` is empty.
+pub fn find_all_patterns<T: CopyAddress>(
+    handle: &T,
+    region: Range<usize>,
+    pattern: &[Option<u8>],
+) -> std::io::Result<Vec<usize>> {
+    let mut found = Vec::new();
+    scan(handle, region, pattern, |addr| {
+        found.push(addr);
+        true
+    })?;
+    Ok(found)
+}
+
+/// Find the addresses of every match of `pattern` in all of the readable memory of `handle`,
+/// using `threads` threads to scan different regions at the same time. A `threads` of `0` uses
+/// as many threads as `std::thread::available_parallelism` suggests.
+///
+/// The matches are always returned in ascending address order, however the regions were split
+/// between the threads, so the result is the same as scanning each region in turn. Matches may
+/// overlap, but they never span two regions. The rest of a region is skipped if it can't be read,
+/// for instance because it was unmapped during the scan.
+///
+/// # Errors
+/// Returns an error if the regions of `handle` can't be listed, or an error with a
+/// `std::io::ErrorKind` of `InvalidInput` if `pattern` is empty.
+pub fn find_pattern_parallel(
+    handle: &(impl CopyAddress + EnumerateRegions + Sync),
+    pattern: &[Option<u8>],
+    threads: usize,
+) -> std::io::Result<Vec<usize>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    check_pattern(pattern)?;
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+        threads => threads,
+    };
+    let regions: Vec<_> = handle
+        .regions()?
+        .into_iter()
+        .filter(|region| region.protection.contains(Protection::READ))
+        .collect();
+    // Each thread takes the next region that no thread has scanned yet, so a few large regions
+    // don't leave the other threads idle.
+    let next = AtomicUsize::new(0);
+    let mut found: Vec<usize> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(regions.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut buffer = Vec::new();
+                    let mut found = Vec::new();
+                    while let Some(region) = regions.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let _ = scan_with_buffer(
+                            handle,
+                            region.base..region.end(),
+                            pattern,
+                            &mut buffer,
+                            |addr| {
+                                found.push(addr);
+                                true
+                            },
+                        );
+                    }
+                    found
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    found.sort_unstable();
+    Ok(found)
+}
+
+/// Find the addresses within `search` of the memory of `handle` that hold a pointer to `target`,
+/// such as to find everything that refers to a known object.
+///
+/// `search` is treated as an array of pointers of the width given by
+/// [`CopyAddress::get_pointer_width`], which for a `ProcessHandle` is its `Architecture`, so only
+/// addresses aligned to that width are checked. Use [`find_pointers_into`] to
+/// also find pointers to somewhere inside the object.
+///
+/// # Errors
+/// Returns an error if copying memory fails.
+///
+/// [`find_pointers_into`]: fn.find_pointers_into.html
+/// [`CopyAddress::get_pointer_width`]: ../trait.CopyAddress.html#method.get_pointer_width
+pub fn find_pointers_to<T: CopyAddress>(
+    handle: &T,
+    target: usize,
+    search: Range<usize>,
+) -> std::io::Result<Vec<usize>> {
+    find_pointers_into(handle, target..target.saturating_add(1), search)
+}
+
+/// Find the addresses within `search` of the memory of `handle` that hold a pointer to anywhere
+/// in `targets`, such as `object..object + size` to find pointers to an object or to any of its
+/// fields.
+///
+/// As with [`find_pointers_to`], `search` is treated as an array of pointers of the width given
+/// by [`CopyAddress::get_pointer_width`], so only addresses aligned to that width are checked.
+///
+/// # Errors
+/// Returns an error if copying memory fails.
+///
+/// [`find_pointers_to`]: fn.find_pointers_to.html
+/// [`CopyAddress::get_pointer_width`]: ../trait.CopyAddress.html#method.get_pointer_width
+pub fn find_pointers_into<T: CopyAddress>(
+    handle: &T,
+    targets: Range<usize>,
+    search: Range<usize>,
+) -> std::io::Result<Vec<usize>> {
+    let arch = handle.get_pointer_width();
+    let width = arch as usize;
+    let mut found = Vec::new();
+    let page_size = crate::platform::page_size();
+    let mut buffer = Vec::with_capacity(page_size);
+    // A search starting in the last pointer of the address space has no aligned address in it.
+    let Some(mut addr) = search.start.checked_next_multiple_of(width) else {
+        return Ok(Vec::new());
+    };
+    while addr < search.end {
+        // Chunks are page-aligned, and a page is a multiple of the pointer width, so no pointer is
+        // split between two chunks.
+        let len = (page_size - addr % page_size).min(search.end - addr);
+        buffer.resize(len, 0);
+        handle.copy_address(addr, &mut buffer)?;
+        found.extend(
+            buffer
+                .chunks_exact(width)
+                .enumerate()
+                .filter(|(_, pointer)| targets.contains(&arch.pointer_from_ne_bytes(pointer)))
+                .map(|(index, _)| addr + index * width),
+        );
+        addr += len;
+    }
+    Ok(found)
+}
+
+/// Read `region` in page-sized chunks, calling `on_match` with the address of each match of
+/// `pattern` until it returns `false`.
+///
+/// The last `pattern.len() - 1` bytes of each chunk are carried over to the start of the next
+/// chunk, so matches that span two chunks are still found.
+fn scan<T: CopyAddress>(
+    handle: &T,
+    region: Range<usize>,
+    pattern: &[Option<u8>],
+    on_match: impl FnMut(usize) -> bool,
+) -> std::io::Result<()> {
+    check_pattern(pattern)?;
+    let mut buffer = Vec::with_capacity(crate::platform::page_size() + pattern.len() - 1);
+    scan_with_buffer(handle, region, pattern, &mut buffer, on_match)
+}
+
+/// Like [`scan`], but reading into `buffer`, which can be reused between scans to avoid
+/// allocating a new one each time. `pattern` must not be empty.
+///
+/// [`scan`]: fn.scan.html
+fn scan_with_buffer<T: CopyAddress>(
+    handle: &T,
+    region: Range<usize>,
+    pattern: &[Option<u8>],
+    buffer: &mut Vec<u8>,
+    mut on_match: impl FnMut(usize) -> bool,
+) -> std::io::Result<()> {
+    let page_size = crate::platform::page_size();
+    buffer.clear();
+    // The address of the first byte in `buffer`.
+    let mut buffer_addr = region.start;
+    let mut addr = region.start;
+    while addr < region.end {
+        // Keep chunks page-aligned so that each read touches exactly one page.
+        let len = (page_size - addr % page_size).min(region.end - addr);
+        let carried = buffer.len();
+        buffer.resize(carried + len, 0);
+        handle.copy_address(addr, &mut buffer[carried..])?;
+        addr += len;
+
+        for (offset, window) in buffer.windows(pattern.len()).enumerate() {
+            if matches(window, pattern) && !on_match(buffer_addr + offset) {
+                return Ok(());
+            }
+        }
+
+        let keep = buffer.len().min(pattern.len() - 1);
+        let _ = buffer.drain(..buffer.len() - keep);
+        buffer_addr = addr - keep;
+    }
+    Ok(())
+}
+
+/// Find the addresses of every occurrence of `value` in the readable and writable private memory of
+/// `handle`, such as its heap and stacks, to narrow down with [`rescan_value`] once the value has
+/// changed.
+///
+/// Only addresses aligned to `std::mem::align_of::<T>()` are checked, as that is where the target
+/// will have stored its values. `T` should be a plain-old-data type, such as an integer or a float,
+/// as memory is read as a `T` without checking that it forms a valid value of `T`. Regions are read
+/// in page-sized chunks, and the rest of a region is skipped if it can't be read, for instance
+/// because it was unmapped during the scan.
+///
+/// # Errors
+/// Returns an error if the regions of `handle` can't be listed, or an error with a
+/// `std::io::ErrorKind` of `InvalidInput` if `T` is zero-sized.
+///
+/// [`rescan_value`]: fn.rescan_value.html
+pub fn scan_value<T: PartialEq + Copy>(
+    handle: &(impl CopyAddress + EnumerateRegions),
+    value: T,
+) -> std::io::Result<Vec<usize>> {
+    let size = value_size::<T>()?;
+    let page_size = crate::platform::page_size();
+    let mut found = Vec::new();
+    let mut buffer = Vec::with_capacity(page_size + size - 1);
+    for region in handle.regions()? {
+        if !region.is_private
+            || !region
+                .protection
+                .contains(Protection::READ | Protection::WRITE)
+        {
+            continue;
+        }
+        buffer.clear();
+        // The address of the first byte in `buffer`.
+        let mut buffer_addr = region.base;
+        let mut addr = region.base;
+        while addr < region.end() {
+            // As in `scan_with_buffer`, the last `size - 1` bytes of each chunk are carried over so
+            // that values spanning two chunks are still found.
+            let len = (page_size - addr % page_size).min(region.end() - addr);
+            let carried = buffer.len();
+            buffer.resize(carried + len, 0);
+            if handle.copy_address(addr, &mut buffer[carried..]).is_err() {
+                break;
+            }
+            addr += len;
+
+            // Start from the first aligned address in the buffer.
+            if let (Some(first), Some(last)) = (
+                buffer_addr.checked_next_multiple_of(std::mem::align_of::<T>()),
+                buffer.len().checked_sub(size),
+            ) {
+                found.extend(
+                    (first - buffer_addr..=last)
+                        .step_by(std::mem::align_of::<T>())
+                        .filter(|&offset| read_value::<T>(&buffer[offset..offset + size]) == value)
+                        .map(|offset| buffer_addr + offset),
+                );
+            }
+
+            let keep = buffer.len().min(size - 1);
+            let _ = buffer.drain(..buffer.len() - keep);
+            buffer_addr = addr - keep;
+        }
+    }
+    Ok(found)
+}
+
+/// Re-read each of the `candidates`, which are typically from a previous call to [`scan_value`] or
+/// `rescan_value`, and return those that now hold `value`. Candidates that can't be read are
+/// dropped.
+///
+/// # Errors
+/// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `T` is zero-sized.
+///
+/// [`scan_value`]: fn.scan_value.html
+pub fn rescan_value<T: PartialEq + Copy>(
+    handle: &impl CopyAddress,
+    candidates: &[usize],
+    value: T,
+) -> std::io::Result<Vec<usize>> {
+    let mut buffer = vec![0_u8; value_size::<T>()?];
+    Ok(candidates
+        .iter()
+        .copied()
+        .filter(|&addr| {
+            handle.copy_address(addr, &mut buffer).is_ok() && read_value::<T>(&buffer) == value
+        })
+        .collect())
+}
+
+/// Check that `pattern` isn't empty, as an empty pattern would match everywhere.
+fn check_pattern(pattern: &[Option<u8>]) -> std::io::Result<()> {
+    if pattern.is_empty() {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Cannot scan for an empty pattern",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Get the size of `T`, which can't be scanned for if it is zero-sized.
+fn value_size<T>() -> std::io::Result<usize> {
+    match std::mem::size_of::<T>() {
+        0 => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Cannot scan for a zero-sized value",
+        )),
+        size => Ok(size),
+    }
+}
+
+/// Read a `T` from `bytes`, which must be `std::mem::size_of::<T>()` bytes long.
+fn read_value<T: Copy>(bytes: &[u8]) -> T {
+    debug_assert_eq!(bytes.len(), std::mem::size_of::<T>());
+    unsafe { bytes.as_ptr().cast::<T>().read_unaligned() }
+}
+
+/// Check whether `bytes` matches `pattern`, which must be the same length.
+fn matches(bytes: &[u8], pattern: &[Option<u8>]) -> bool {
+    bytes
+        .iter()
+        .zip(pattern)
+        .all(|(byte, expected)| expected.is_none_or(|expected| *byte == expected))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Pid, ProcessHandleExt, TryIntoProcessHandle};
+
+    #[test]
+    fn parse_ida_pattern() {
+        assert_eq!(
+            parse_pattern("48 8B ?? c3 ?").unwrap(),
+            vec![Some(0x48), Some(0x8B), None, Some(0xC3), None]
+        );
+        assert_eq!(
+            parse_pattern("48 8").unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            parse_pattern("48 zz").unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn find_patterns_across_pages() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        // Place a match straddling every page boundary in the buffer.
+        let page_size = handle.page_size();
+        let mut memory = vec![0_u8; page_size * 5];
+        let start = memory.as_ptr() as usize;
+        let pattern = parse_pattern("DE AD ?? EF").unwrap();
+        let mut expected = Vec::new();
+        let mut offset = (page_size - start % page_size) + page_size - 2;
+        while offset + 4 <= memory.len() {
+            memory[offset..offset + 4].copy_from_slice(&[0xDE, 0xAD, 0x00, 0xEF]);
+            expected.push(start + offset);
+            offset += page_size;
+        }
+        let region = start..start + memory.len();
+
+        assert_eq!(
+            find_pattern(&handle, region.clone(), &pattern).unwrap(),
+            expected.first().copied()
+        );
+        assert_eq!(
+            find_all_patterns(&handle, region, &pattern).unwrap(),
+            expected
+        );
+        assert_eq!(
+            find_pattern(&handle, start..start + 1, &pattern).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn find_patterns_in_parallel() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        // Build the marker at runtime so that the only copies of it are in these buffers.
+        let marker: Vec<u8> = [0x3C_u8, 0x5A, 0x96, 0xA5, 0xC3]
+            .iter()
+            .map(|byte| byte ^ 0xFF)
+            .collect();
+        let buffers: Vec<Vec<u8>> = (0..8)
+            .map(|i| {
+                let mut buffer = vec![0_u8; 0x1000 * (i + 1)];
+                let end = buffer.len();
+                buffer[end - marker.len()..].copy_from_slice(&marker);
+                buffer
+            })
+            .collect();
+        let mut expected: Vec<usize> = buffers
+            .iter()
+            .map(|buffer| buffer.as_ptr() as usize + buffer.len() - marker.len())
+            .collect();
+        expected.sort_unstable();
+        let pattern: Vec<_> = marker.iter().copied().map(Some).collect();
+
+        let found = find_pattern_parallel(&handle, &pattern, 4).unwrap();
+        assert!(found.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(expected.iter().all(|addr| found.contains(addr)));
+        let found_alone = find_pattern_parallel(&handle, &pattern, 1).unwrap();
+        assert!(expected.iter().all(|addr| found_alone.contains(addr)));
+        assert_eq!(
+            find_pattern_parallel(&handle, &[], 0).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn find_pointers_to_object() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let object = [0_u64; 4];
+        let start = object.as_ptr() as usize;
+        // Pointers to the object and into it, spread across a page boundary.
+        let mut table = vec![0_usize; handle.page_size() / std::mem::size_of::<usize>() * 2];
+        let pointers = [
+            (3, start),
+            (10, start + 8),
+            (table.len() - 1, start),
+            (20, start + 32),
+        ];
+        for &(index, pointer) in &pointers {
+            table[index] = pointer;
+        }
+        let search = table.as_ptr() as usize
+            ..table.as_ptr() as usize + std::mem::size_of_val(table.as_slice());
+        let addr_of = |index: usize| &raw const table[index] as usize;
+
+        assert_eq!(
+            find_pointers_to(&handle, start, search.clone()).unwrap(),
+            vec![addr_of(3), addr_of(table.len() - 1)]
+        );
+        assert_eq!(
+            find_pointers_into(&handle, start..start + 32, search.clone()).unwrap(),
+            vec![addr_of(3), addr_of(10), addr_of(table.len() - 1)]
+        );
+        // A search that starts partway through a pointer skips it.
+        assert_eq!(
+            find_pointers_to(&handle, start, addr_of(3) + 1..search.end).unwrap(),
+            vec![addr_of(table.len() - 1)]
+        );
+        assert_eq!(
+            find_pointers_to(&handle, start, usize::MAX - 1..usize::MAX).unwrap(),
+            vec![]
+        );
+    }
+    #[test]
+    fn scan_and_rescan_value() {
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let mut health = Box::new(0x5EED_CAFE_u32);
+        let addr = std::ptr::from_ref(&*health) as usize;
+        let candidates = scan_value(&handle, *health).unwrap();
+        assert!(candidates.contains(&addr));
+
+        *health = 0x0BAD_F00D;
+        let candidates = rescan_value(&handle, &candidates, *health).unwrap();
+        assert!(candidates.contains(&addr));
+        assert!(candidates.iter().all(|&candidate| {
+            let mut value = [0_u8; 4];
+            handle.copy_address(candidate, &mut value).is_ok()
+                && u32::from_ne_bytes(value) == 0x0BAD_F00D
+        }));
+        assert_eq!(
+            scan_value(&handle, ()).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+    #[test]
+    fn scan_value_in_short_and_split_regions() {
+        /// A handle whose regions are the ones given rather than those of the process.
+        struct Listed(crate::ProcessHandle, Vec<crate::Region>);
+        impl CopyAddress for Listed {
+            fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+                self.0.copy_address(addr, buf)
+            }
+        }
+        impl EnumerateRegions for Listed {
+            fn regions(&self) -> std::io::Result<Vec<crate::Region>> {
+                Ok(self.1.clone())
+            }
+        }
+
+        #[allow(clippy::cast_possible_wrap)]
+        let handle = (std::process::id() as Pid)
+            .try_into_process_handle()
+            .unwrap();
+        let region = |base: usize, size: usize| crate::Region {
+            base,
+            size,
+            protection: Protection::READ | Protection::WRITE,
+            is_private: true,
+        };
+        let value = [0x5E_u8, 0xED, 0xCA, 0xFE];
+        // A region too short to hold the value.
+        let short = [0x5E_u8, 0xED];
+        // The value split across a page boundary, so between two chunks.
+        let page_size = handle.page_size();
+        let mut memory = vec![0_u8; page_size * 2];
+        let start = memory.as_ptr() as usize;
+        let split = (start + 2).next_multiple_of(page_size) - start - 2;
+        memory[split..split + 4].copy_from_slice(&value);
+        let listed = Listed(
+            handle,
+            vec![
+                region(short.as_ptr() as usize, short.len()),
+                region(start, memory.len()),
+            ],
+        );
+        assert_eq!(scan_value(&listed, value).unwrap(), vec![start + split]);
+    }
+}
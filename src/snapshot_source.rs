@@ -0,0 +1,303 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::memory_error::{classified_error, ErrorClass};
+use crate::{Architecture, CopyAddress, DumpManifest, PutAddress};
+
+/// Memory read back from a snapshot written by [`EnumerateRegions::dump_to`], for analysing a
+/// process after the fact.
+///
+/// A `SnapshotSource` implements [`CopyAddress`], translating each address into an offset in the
+/// snapshot with the snapshot's [`DumpManifest`], so anything that reads through
+/// [`CopyAddress`], such as following a chain of offsets with [`CopyAddress::get_offset`] or
+/// [`resolve_chain`], or searching with [`scan::find_pattern`], works the same on a snapshot as on
+/// a live process. Memory that wasn't written to the snapshot can't be read, and nothing can be
+/// written, so [`PutAddress`] always fails with a `std::io::ErrorKind` of `Unsupported`.
+///
+/// ```rust
+/// # use process_memory::{CopyAddress, EnumerateRegions, Pid, SnapshotSource, TryIntoProcessHandle};
+/// # let dir = std::env::temp_dir().join(format!("snapshot-doctest-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// # let path = dir.join("memory.bin");
+/// let value = 0x1234_5678_u32;
+/// let handle = (std::process::id() as Pid).try_into_process_handle().unwrap();
+/// let manifest = handle
+///     .dump_to(&mut std::io::BufWriter::new(std::fs::File::create(&path).unwrap()))
+///     .unwrap();
+///
+/// let snapshot = SnapshotSource::open(&path, manifest).unwrap();
+/// let mut bytes = [0_u8; 4];
+/// snapshot.copy_address(&value as *const u32 as usize, &mut bytes).unwrap();
+/// assert_eq!(u32::from_ne_bytes(bytes), value);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+///
+/// [`EnumerateRegions::dump_to`]: trait.EnumerateRegions.html#method.dump_to
+/// [`CopyAddress`]: trait.CopyAddress.html
+/// [`CopyAddress::get_offset`]: trait.CopyAddress.html#method.get_offset
+/// [`DumpManifest`]: struct.DumpManifest.html
+/// [`resolve_chain`]: fn.resolve_chain.html
+/// [`scan::find_pattern`]: scan/fn.find_pattern.html
+/// [`PutAddress`]: trait.PutAddress.html
+#[derive(Debug)]
+pub struct SnapshotSource<R = std::fs::File> {
+    snapshot: std::sync::Mutex<R>,
+    manifest: DumpManifest,
+    arch: Architecture,
+}
+
+impl SnapshotSource {
+    /// Open the snapshot at `path`, which was written along with `manifest`.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be opened.
+    pub fn open(
+        path: impl AsRef<std::path::Path>,
+        manifest: DumpManifest,
+    ) -> std::io::Result<Self> {
+        Ok(Self::new(std::fs::File::open(path)?, manifest))
+    }
+}
+
+impl<R: Read + Seek> SnapshotSource<R> {
+    /// Read the snapshot from `snapshot`, which was written along with `manifest`. Pointers are
+    /// assumed to have the width of those in this process, which can be changed with
+    /// [`SnapshotSource::set_arch`].
+    ///
+    /// [`SnapshotSource::set_arch`]: struct.SnapshotSource.html#method.set_arch
+    #[must_use]
+    pub fn new(snapshot: R, manifest: DumpManifest) -> Self {
+        Self {
+            snapshot: std::sync::Mutex::new(snapshot),
+            manifest,
+            arch: Architecture::from_native(),
+        }
+    }
+
+    /// Set the `Architecture` of the process that the snapshot was taken of, which is used as the
+    /// size of the pointers read while following a chain of offsets.
+    #[must_use]
+    pub fn set_arch(mut self, arch: Architecture) -> Self {
+        self.arch = arch;
+        self
+    }
+
+    /// Get the manifest that describes where each region is in the snapshot.
+    #[must_use]
+    pub fn manifest(&self) -> &DumpManifest {
+        &self.manifest
+    }
+
+    /// Copy as much of `buf` as possible from the snapshot, stopping at the first address that
+    /// isn't in it.
+    fn copy_available(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut snapshot = self
+            .snapshot
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut copied = 0;
+        while copied < buf.len() {
+            let Some((offset, available)) = self.manifest.locate(addr.wrapping_add(copied)) else {
+                break;
+            };
+            let len = available.min(buf.len() - copied);
+            let _ = snapshot.seek(SeekFrom::Start(offset))?;
+            snapshot.read_exact(&mut buf[copied..copied + len])?;
+            copied += len;
+        }
+        Ok(copied)
+    }
+}
+
+impl<R: Read + Seek> CopyAddress for SnapshotSource<R> {
+    fn get_pointer_width(&self) -> Architecture {
+        self.arch
+    }
+
+    /// A read can span several regions, as long as there are no gaps between them.
+    fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+        let copied = self.copy_available(addr, buf)?;
+        if copied == buf.len() {
+            Ok(())
+        } else {
+            Err(not_in_snapshot(addr.wrapping_add(copied)))
+        }
+    }
+
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.copy_available(addr, buf)? {
+            0 if !buf.is_empty() => Err(not_in_snapshot(addr)),
+            copied => Ok(copied),
+        }
+    }
+}
+
+/// A snapshot can't be written to.
+impl<R> PutAddress for SnapshotSource<R> {
+    fn put_address(&self, _addr: usize, _buf: &[u8]) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "A snapshot can't be written to",
+        ))
+    }
+}
+
+/// Compare two snapshots byte by byte, returning the address and length of each range of memory
+/// that differs between them, in order of address.
+///
+/// Only memory that is in both snapshots is compared, so regions that were only written to one of
+/// them, such as memory that was mapped or unmapped between the two, aren't included. Differing
+/// bytes that are next to each other are combined into one range, even when they span two
+/// adjacent regions.
+///
+/// # Errors
+/// Returns an error if either snapshot can't be read.
+pub fn diff_snapshots<A: Read + Seek, B: Read + Seek>(
+    a: &SnapshotSource<A>,
+    b: &SnapshotSource<B>,
+) -> std::io::Result<Vec<(usize, usize)>> {
+    const CHUNK_SIZE: usize = 0x1_0000;
+
+    let mut overlaps: Vec<(usize, usize)> = a
+        .manifest
+        .regions
+        .iter()
+        .flat_map(|&(a_base, a_size)| {
+            b.manifest
+                .regions
+                .iter()
+                .filter_map(move |&(b_base, b_size)| {
+                    let start = a_base.max(b_base);
+                    let end = (a_base + a_size).min(b_base + b_size);
+                    (start < end).then_some((start, end))
+                })
+        })
+        .collect();
+    overlaps.sort_unstable();
+
+    let mut diffs: Vec<(usize, usize)> = Vec::new();
+    let mut left = vec![0_u8; CHUNK_SIZE];
+    let mut right = vec![0_u8; CHUNK_SIZE];
+    for (start, end) in overlaps {
+        let mut addr = start;
+        while addr < end {
+            let len = CHUNK_SIZE.min(end - addr);
+            a.copy_address(addr, &mut left[..len])?;
+            b.copy_address(addr, &mut right[..len])?;
+            for (i, _) in left[..len]
+                .iter()
+                .zip(&right[..len])
+                .enumerate()
+                .filter(|(_, (x, y))| x != y)
+            {
+                match diffs.last_mut() {
+                    Some((base, size)) if *base + *size == addr + i => *size += 1,
+                    _ => diffs.push((addr + i, 1)),
+                }
+            }
+            addr += len;
+        }
+    }
+    Ok(diffs)
+}
+
+/// Make the error for reading `addr`, which isn't in the snapshot.
+fn not_in_snapshot(addr: usize) -> std::io::Error {
+    classified_error(
+        std::io::ErrorKind::InvalidInput,
+        ErrorClass::AddressUnreadable,
+        format!("{addr:#x} isn't in the snapshot"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A snapshot of two adjacent regions and one further away, where each byte is the low byte of
+    /// its address.
+    fn snapshot() -> SnapshotSource<std::io::Cursor<Vec<u8>>> {
+        let manifest = DumpManifest {
+            regions: vec![(0x1000, 0x10), (0x1010, 0x8), (0x2000, 0x10)],
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let bytes = manifest
+            .regions
+            .iter()
+            .flat_map(|&(base, size)| (base..base + size).map(|addr| addr as u8))
+            .collect();
+        SnapshotSource::new(std::io::Cursor::new(bytes), manifest)
+    }
+
+    #[test]
+    fn read_across_regions() {
+        let snapshot = snapshot();
+        let mut buf = [0_u8; 4];
+        snapshot.copy_address(0x2004, &mut buf).unwrap();
+        assert_eq!(buf, [0x04, 0x05, 0x06, 0x07]);
+        snapshot.copy_address(0x100e, &mut buf).unwrap();
+        assert_eq!(buf, [0x0e, 0x0f, 0x10, 0x11]);
+
+        let mut buf = [0_u8; 8];
+        assert_eq!(
+            snapshot.copy_address(0x1014, &mut buf).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(snapshot.copy_address_partial(0x1014, &mut buf).unwrap(), 4);
+        assert_eq!(buf[..4], [0x14, 0x15, 0x16, 0x17]);
+        assert!(snapshot.copy_address_partial(0x1800, &mut buf).is_err());
+        assert_eq!(
+            snapshot.put_address(0x1000, &buf).unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn diff_two_snapshots() {
+        let before = snapshot();
+        let mut bytes = before.snapshot.lock().unwrap().get_ref().clone();
+        let mut manifest = before.manifest().clone();
+        // Change the last byte of the first region and the first of the second, a byte in the
+        // middle of the third, and add a region that isn't in the first snapshot.
+        bytes[0x0f] = 0;
+        bytes[0x10] = 0;
+        bytes[0x18 + 0x8] = 0xff;
+        bytes[0x18 + 0x9] = 0xff;
+        manifest.regions.push((0x3000, 0x4));
+        bytes.extend_from_slice(&[1, 2, 3, 4]);
+        let after = SnapshotSource::new(std::io::Cursor::new(bytes), manifest);
+
+        assert_eq!(
+            diff_snapshots(&before, &after).unwrap(),
+            vec![(0x100f, 2), (0x2008, 2)]
+        );
+        assert_eq!(
+            diff_snapshots(&after, &before).unwrap(),
+            vec![(0x100f, 2), (0x2008, 2)]
+        );
+        assert!(diff_snapshots(&before, &before).unwrap().is_empty());
+    }
+
+    #[test]
+    fn follow_pointers_in_snapshot() {
+        // A pointer at 0x10 in the first region, to 0x2000.
+        let mut manifest = DumpManifest::default();
+        let mut bytes = vec![0_u8; 0x20];
+        bytes[0x10..0x14].copy_from_slice(&0x2000_u32.to_ne_bytes());
+        manifest.regions.push((0x1000, 0x20));
+        bytes.extend_from_slice(&[0xaa; 0x10]);
+        manifest.regions.push((0x2000, 0x10));
+        let snapshot = SnapshotSource::new(std::io::Cursor::new(bytes), manifest)
+            .set_arch(Architecture::Arch32Bit);
+        assert_eq!(
+            snapshot
+                .get_offset(&[0x1010, 0x8], snapshot.get_pointer_width())
+                .unwrap(),
+            0x2008
+        );
+        assert_eq!(
+            crate::scan::find_pattern(&snapshot, 0x2000..0x2010, &[Some(0xaa), Some(0xaa)])
+                .unwrap(),
+            Some(0x2000)
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use crate::RawThreadContext;
+
+/// The registers of a thread, as read by [`EnumerateThreads::thread_context`].
+///
+/// The instruction pointer and the stack pointer can be read in the same way on every platform.
+/// Every other register is only available from [`ThreadContext::raw`], in the platform's own
+/// format.
+///
+/// [`EnumerateThreads::thread_context`]: trait.EnumerateThreads.html#tymethod.thread_context
+/// [`ThreadContext::raw`]: struct.ThreadContext.html#method.raw
+#[derive(Clone)]
+pub struct ThreadContext {
+    pub(crate) instruction_pointer: usize,
+    pub(crate) stack_pointer: usize,
+    pub(crate) raw: RawThreadContext,
+}
+
+impl ThreadContext {
+    /// Get the address of the next instruction the thread will execute.
+    #[must_use]
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    /// Get the address of the top of the thread's stack.
+    #[must_use]
+    pub fn stack_pointer(&self) -> usize {
+        self.stack_pointer
+    }
+
+    /// Get every register of the thread, as the platform represents them. See
+    /// [`RawThreadContext`] for what this is on each platform.
+    ///
+    /// [`RawThreadContext`]: type.RawThreadContext.html
+    #[must_use]
+    pub fn raw(&self) -> &RawThreadContext {
+        &self.raw
+    }
+}
+
+/// Only the instruction pointer and stack pointer are shown, as not every platform's registers
+/// can be formatted.
+impl std::fmt::Debug for ThreadContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ThreadContext")
+            .field(
+                "instruction_pointer",
+                &format_args!("{:#x}", self.instruction_pointer),
+            )
+            .field("stack_pointer", &format_args!("{:#x}", self.stack_pointer))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,11 @@
+use crate::Tid;
+
+/// A thread of a process.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ThreadInfo {
+    /// The id of the thread, which is only unique for as long as the thread is running.
+    pub tid: Tid,
+    /// Whether the thread is the main thread of the process, which is the one it was started
+    /// with.
+    pub is_main: bool,
+}
@@ -0,0 +1,58 @@
+//! Logging of every read and write when the `logging` feature is enabled, through the `log`
+//! crate. Without the feature these functions do nothing, and are optimised away entirely.
+
+/// Log a read of `len` bytes at `addr` from `handle`, and its result.
+#[cfg(feature = "logging")]
+pub(crate) fn trace_read<T>(
+    handle: &impl std::fmt::Debug,
+    addr: usize,
+    len: usize,
+    result: &std::io::Result<T>,
+) {
+    match result {
+        Ok(_) => log::trace!("read {len} bytes at {addr:#x} from {handle:?}"),
+        Err(err) => log::trace!(
+            "failed to read {len} bytes at {addr:#x} from {handle:?} (error code {:?}): {err}",
+            err.raw_os_error()
+        ),
+    }
+}
+
+/// Log a write of `len` bytes to `addr` in `handle`, and its result.
+#[cfg(feature = "logging")]
+pub(crate) fn trace_write<T>(
+    handle: &impl std::fmt::Debug,
+    addr: usize,
+    len: usize,
+    result: &std::io::Result<T>,
+) {
+    match result {
+        Ok(_) => log::trace!("wrote {len} bytes to {addr:#x} in {handle:?}"),
+        Err(err) => log::trace!(
+            "failed to write {len} bytes to {addr:#x} in {handle:?} (error code {:?}): {err}",
+            err.raw_os_error()
+        ),
+    }
+}
+
+/// Log a read of `len` bytes at `addr` from `handle`, and its result.
+#[cfg(not(feature = "logging"))]
+#[inline(always)]
+pub(crate) fn trace_read<T>(
+    _handle: &impl std::fmt::Debug,
+    _addr: usize,
+    _len: usize,
+    _result: &std::io::Result<T>,
+) {
+}
+
+/// Log a write of `len` bytes to `addr` in `handle`, and its result.
+#[cfg(not(feature = "logging"))]
+#[inline(always)]
+pub(crate) fn trace_write<T>(
+    _handle: &impl std::fmt::Debug,
+    _addr: usize,
+    _len: usize,
+    _result: &std::io::Result<T>,
+) {
+}
@@ -0,0 +1,544 @@
+use super::{ptrace_error, thread_registers, Pid, ProcessHandle, ThreadContext, Tid};
+
+/// How many bytes a watchpoint covers, starting from its address, which must be aligned to the
+/// size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WatchSize {
+    /// A single byte. This is the only size an execute watchpoint can have.
+    Byte,
+    /// Two bytes.
+    Word,
+    /// Four bytes.
+    DWord,
+    /// Eight bytes, which is only supported on x86-64.
+    QWord,
+}
+
+impl WatchSize {
+    /// The number of bytes covered.
+    fn len(self) -> usize {
+        match self {
+            WatchSize::Byte => 1,
+            WatchSize::Word => 2,
+            WatchSize::DWord => 4,
+            WatchSize::QWord => 8,
+        }
+    }
+
+    /// The `LEN` field of `DR7` for this size.
+    fn bits(self) -> usize {
+        match self {
+            WatchSize::Byte => 0b00,
+            WatchSize::Word => 0b01,
+            WatchSize::DWord => 0b11,
+            WatchSize::QWord => 0b10,
+        }
+    }
+}
+
+/// What a watchpoint is triggered by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WatchKind {
+    /// Executing the instruction at the address. The thread stops before the instruction runs.
+    Execute,
+    /// Writing to any of the bytes covered. The thread stops after the instruction that wrote.
+    Write,
+    /// Reading or writing any of the bytes covered. The thread stops after the instruction that
+    /// accessed them.
+    ReadWrite,
+}
+
+impl WatchKind {
+    /// The `R/W` field of `DR7` for this kind.
+    fn bits(self) -> usize {
+        match self {
+            WatchKind::Execute => 0b00,
+            WatchKind::Write => 0b01,
+            WatchKind::ReadWrite => 0b11,
+        }
+    }
+}
+
+/// Identifies a watchpoint set by [`ProcessHandle::set_watchpoint`], by the debug register that
+/// holds its address.
+///
+/// [`ProcessHandle::set_watchpoint`]: struct.ProcessHandle.html#method.set_watchpoint
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchId(usize);
+
+/// A thread that triggered a watchpoint, as returned by [`ProcessHandle::watchpoint_hits`].
+///
+/// [`ProcessHandle::watchpoint_hits`]: struct.ProcessHandle.html#method.watchpoint_hits
+#[derive(Clone, Debug)]
+pub struct WatchHit {
+    /// The watchpoint that was triggered.
+    pub id: WatchId,
+    /// The thread that triggered it.
+    pub tid: Tid,
+    /// The registers of the thread when it stopped. For a write or read-write watchpoint, the
+    /// instruction pointer is just after the instruction that accessed the memory.
+    pub context: ThreadContext,
+}
+
+/// A watchpoint, as it is held in a debug register.
+#[derive(Clone, Copy, Debug)]
+struct Watch {
+    addr: usize,
+    size: WatchSize,
+    kind: WatchKind,
+}
+
+/// The watchpoints set in one process, along with the threads of the process that we trace to
+/// set them and to see them being hit.
+#[derive(Debug, Default)]
+struct Watchpoints {
+    slots: [Option<Watch>; 4],
+    /// The thread that traces the process. The kernel only accepts `ptrace` requests from it.
+    tracer: Option<std::thread::ThreadId>,
+    threads: Vec<Tid>,
+    /// Hits seen while setting or clearing watchpoints, to be returned by the next poll.
+    hits: Vec<WatchHit>,
+}
+
+/// How to let a stopped thread carry on.
+#[derive(Clone, Copy, Debug)]
+enum Resume {
+    /// Continue, delivering the given signal (or none if it is 0).
+    Continue(libc::c_int),
+    /// Stay stopped, as the whole process has been stopped (such as by `SIGSTOP`), but carry on
+    /// once it is continued.
+    Listen,
+}
+
+/// The watchpoints of every process, by pid. Debug registers belong to the threads of a process
+/// rather than to a handle, so every handle to the same process shares them.
+static WATCHPOINTS: std::sync::Mutex<std::collections::BTreeMap<Pid, Watchpoints>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Hardware watchpoints are set with the x86 debug registers `DR0` to `DR3`, which are written
+/// with `ptrace(PTRACE_POKEUSER)`. This needs the same access as reading registers, and only works
+/// on x86 and x86-64.
+///
+/// To set a watchpoint we trace every thread of the process with `PTRACE_SEIZE`, and we keep
+/// tracing them until the last watchpoint is cleared. While traced, a thread that hits a
+/// watchpoint stops until [`ProcessHandle::watchpoint_hits`] is called. Because of this:
+///
+/// * Every watchpoint must be set, polled and cleared from the same thread, as the kernel only
+///   takes `ptrace` requests from the thread that is tracing, and nothing else (including
+///   [`EnumerateThreads::thread_context`]) can attach to the process in the meantime.
+/// * Threads that start after a watchpoint is set don't have it until the next watchpoint is set
+///   or cleared.
+/// * Every watchpoint should be cleared before the tracing thread exits. Otherwise the process is
+///   no longer traced, but its debug registers are still set, and the signal from a hit kills it.
+///
+/// [`ProcessHandle::watchpoint_hits`]: struct.ProcessHandle.html#method.watchpoint_hits
+/// [`EnumerateThreads::thread_context`]: trait.EnumerateThreads.html#tymethod.thread_context
+impl ProcessHandle {
+    /// Set a hardware watchpoint on `size` bytes at `addr` in every thread of the process, so
+    /// that threads stop when `kind` of access is made. Hits are found by polling with
+    /// [`ProcessHandle::watchpoint_hits`]. Up to four watchpoints can be set in a process at once.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if `addr` isn't aligned to
+    /// `size`, if `size` isn't supported for `kind` or on this architecture, or if called from a
+    /// different thread to the one that set the process's other watchpoints. Returns an error if
+    /// all four debug registers are already in use, or if the threads of the process can't be
+    /// traced or their registers can't be written.
+    ///
+    /// [`ProcessHandle::watchpoint_hits`]: struct.ProcessHandle.html#method.watchpoint_hits
+    pub fn set_watchpoint(
+        &self,
+        addr: usize,
+        size: WatchSize,
+        kind: WatchKind,
+    ) -> std::io::Result<WatchId> {
+        let invalid = |message: String| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ))
+        };
+        if kind == WatchKind::Execute && size != WatchSize::Byte {
+            return invalid(format!(
+                "An execute watchpoint must be a single byte, not {size:?}"
+            ));
+        } else if size.len() > std::mem::size_of::<usize>() {
+            return invalid(format!("{size:?} watchpoints need a 64-bit processor"));
+        } else if addr % size.len() != 0 {
+            return invalid(format!(
+                "A {size:?} watchpoint must be aligned to {} bytes, but {addr:#x} isn't",
+                size.len()
+            ));
+        }
+        let mut all = lock();
+        let watchpoints = all.entry(self.pid).or_default();
+        watchpoints.check_tracer()?;
+        let Some(index) = watchpoints.slots.iter().position(Option::is_none) else {
+            return Err(std::io::Error::other(format!(
+                "All four debug registers of process {} are already in use",
+                self.pid
+            )));
+        };
+        watchpoints.slots[index] = Some(Watch { addr, size, kind });
+        if let Err(err) = watchpoints.apply(self.pid) {
+            watchpoints.slots[index] = None;
+            let _ = watchpoints.apply(self.pid);
+            release_if_unused(&mut all, self.pid);
+            return Err(err);
+        }
+        Ok(WatchId(index))
+    }
+
+    /// Clear the watchpoint `id` from every thread of the process. Once the last one is cleared,
+    /// the process is no longer traced.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `NotFound` if the watchpoint isn't set,
+    /// or of `InvalidInput` if called from a different thread to the one that set it. Returns an
+    /// error if the registers of a thread can't be written, in which case the watchpoint is still
+    /// forgotten.
+    pub fn clear_watchpoint(&self, id: WatchId) -> std::io::Result<()> {
+        let mut all = lock();
+        let watchpoints = all
+            .get_mut(&self.pid)
+            .filter(|watchpoints| watchpoints.slots[id.0].is_some())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("Process {} has no watchpoint {id:?}", self.pid),
+                )
+            })?;
+        watchpoints.check_tracer()?;
+        watchpoints.slots[id.0] = None;
+        let result = watchpoints.apply(self.pid);
+        release_if_unused(&mut all, self.pid);
+        result
+    }
+
+    /// Get every watchpoint hit since the last call, without waiting. Each thread that hit a
+    /// watchpoint stays stopped until this is called, so memory can be inspected as it was at the
+    /// hit, and carries on afterwards. Any other signal that a traced thread receives is passed
+    /// on to it, as it would be without tracing.
+    ///
+    /// # Errors
+    /// Returns an error with a `std::io::ErrorKind` of `InvalidInput` if called from a different
+    /// thread to the one that set the watchpoints, or an error if the state of a stopped thread
+    /// can't be read.
+    pub fn watchpoint_hits(&self) -> std::io::Result<Vec<WatchHit>> {
+        let mut all = lock();
+        let Some(watchpoints) = all.get_mut(&self.pid) else {
+            return Ok(Vec::new());
+        };
+        watchpoints.check_tracer()?;
+        let mut result = Ok(());
+        for tid in watchpoints.threads.clone() {
+            let mut status = 0;
+            let stopped = match unsafe {
+                libc::waitpid(tid, &raw mut status, libc::WNOHANG | libc::__WALL)
+            } {
+                0 => continue,
+                -1 => Ok(None),
+                _ => watchpoints.stopped(tid, status),
+            };
+            match stopped {
+                Ok(Some(resume)) => result = result.and(resume_thread(tid, resume)),
+                Ok(None) => watchpoints.threads.retain(|&other| other != tid),
+                Err(err) => {
+                    let _ = resume_thread(tid, Resume::Continue(0));
+                    result = result.and(Err(err));
+                }
+            }
+        }
+        result?;
+        Ok(std::mem::take(&mut watchpoints.hits))
+    }
+}
+
+impl Watchpoints {
+    /// Check that `ptrace` requests are being made from the thread tracing the process, making
+    /// this the tracing thread if nothing traces it yet.
+    fn check_tracer(&mut self) -> std::io::Result<()> {
+        let current = std::thread::current().id();
+        match self.tracer {
+            Some(tracer) if tracer != current => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Watchpoints must be set, polled and cleared from the thread that set the first \
+                 one",
+            )),
+            _ => {
+                self.tracer = Some(current);
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the watchpoints to the debug registers of every thread of the process `pid`,
+    /// tracing any threads that aren't traced yet, or detach from every thread if there are no
+    /// watchpoints left.
+    fn apply(&mut self, pid: Pid) -> std::io::Result<()> {
+        let detach = self.slots.iter().all(Option::is_none);
+        if !detach {
+            self.seize_threads(pid)?;
+        }
+        let mut result = Ok(());
+        for tid in std::mem::take(&mut self.threads) {
+            let resume = match self.interrupt(tid) {
+                Ok(Some(resume)) => resume,
+                Ok(None) => continue,
+                Err(err) => {
+                    let _ = resume_thread(tid, Resume::Continue(0));
+                    result = result.and(Err(err));
+                    self.threads.push(tid);
+                    continue;
+                }
+            };
+            result = result.and(self.write_registers(tid));
+            if detach {
+                let signal = match resume {
+                    Resume::Continue(signal) => signal,
+                    Resume::Listen => 0,
+                };
+                let _ = unsafe { libc::ptrace(libc::PTRACE_DETACH, tid, 0, signal) };
+            } else {
+                result = result.and(resume_thread(tid, resume));
+                self.threads.push(tid);
+            }
+        }
+        result
+    }
+
+    /// Trace every thread of the process `pid` that isn't already traced.
+    fn seize_threads(&mut self, pid: Pid) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(format!("/proc/{pid}/task"))? {
+            let Some(tid) = entry?
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse().ok())
+            else {
+                continue;
+            };
+            if self.threads.contains(&tid) {
+                continue;
+            }
+            if unsafe { libc::ptrace(libc::PTRACE_SEIZE, tid, 0, 0) } == -1 {
+                let err = std::io::Error::last_os_error();
+                // The thread has exited since the directory was read.
+                if err.raw_os_error() == Some(libc::ESRCH) {
+                    continue;
+                }
+                return Err(ptrace_error(err, pid));
+            }
+            self.threads.push(tid);
+        }
+        Ok(())
+    }
+
+    /// Stop the traced thread `tid` and wait for it to stop, returning how to resume it, or
+    /// `None` if it has exited.
+    fn interrupt(&mut self, tid: Tid) -> std::io::Result<Option<Resume>> {
+        if unsafe { libc::ptrace(libc::PTRACE_INTERRUPT, tid, 0, 0) } == -1 {
+            let err = std::io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(libc::ESRCH) => Ok(None),
+                _ => Err(err),
+            };
+        }
+        let mut status = 0;
+        if unsafe { libc::waitpid(tid, &raw mut status, libc::__WALL) } == -1 {
+            return Ok(None);
+        }
+        // The thread may have stopped for a signal before it saw the interrupt, in which case it
+        // will stop again for the interrupt once it is resumed.
+        self.stopped(tid, status)
+    }
+
+    /// Handle the thread `tid` having stopped (or exited) with the `waitpid` `status`, recording
+    /// a hit if it stopped for a watchpoint, and returning how to resume it, or `None` if it has
+    /// exited.
+    fn stopped(&mut self, tid: Tid, status: libc::c_int) -> std::io::Result<Option<Resume>> {
+        if !libc::WIFSTOPPED(status) {
+            return Ok(None);
+        }
+        let signal = libc::WSTOPSIG(status);
+        if status >> 16 == libc::PTRACE_EVENT_STOP {
+            // Stopped by an interrupt, or because the whole process was stopped.
+            return Ok(Some(if signal == libc::SIGTRAP {
+                Resume::Continue(0)
+            } else {
+                Resume::Listen
+            }));
+        } else if signal != libc::SIGTRAP {
+            return Ok(Some(Resume::Continue(signal)));
+        }
+        let enabled = (0..self.slots.len())
+            .filter(|&index| self.slots[index].is_some())
+            .fold(0, |mask, index| mask | 1 << index);
+        let triggered = peek_debug_register(tid, 6)? & enabled;
+        if triggered == 0 {
+            return Ok(Some(Resume::Continue(signal)));
+        }
+        poke_debug_register(tid, 6, 0)?;
+        let context = thread_registers(tid)?;
+        for index in (0..self.slots.len()).filter(|index| triggered & 1 << index != 0) {
+            self.hits.push(WatchHit {
+                id: WatchId(index),
+                tid,
+                context: context.clone(),
+            });
+        }
+        Ok(Some(Resume::Continue(0)))
+    }
+
+    /// Write the watchpoints to the debug registers of the stopped thread `tid`. `DR7`, which
+    /// enables them, is cleared first, so that no watchpoint is ever enabled with the address of
+    /// a different one.
+    fn write_registers(&self, tid: Tid) -> std::io::Result<()> {
+        poke_debug_register(tid, 7, 0)?;
+        let mut dr7 = 0;
+        for (index, watch) in self.slots.iter().enumerate() {
+            if let Some(watch) = watch {
+                poke_debug_register(tid, index, watch.addr)?;
+                dr7 |= 1 << (index * 2)
+                    | watch.kind.bits() << (16 + index * 4)
+                    | watch.size.bits() << (18 + index * 4);
+            }
+        }
+        if dr7 != 0 {
+            poke_debug_register(tid, 7, dr7)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lock the watchpoints of every process. A panic while they were locked can at worst have left
+/// a thread stopped, so a poisoned lock is still used.
+fn lock() -> std::sync::MutexGuard<'static, std::collections::BTreeMap<Pid, Watchpoints>> {
+    WATCHPOINTS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Forget the process `pid` once it has no watchpoints left, as we have detached from it.
+fn release_if_unused(all: &mut std::collections::BTreeMap<Pid, Watchpoints>, pid: Pid) {
+    if all
+        .get(&pid)
+        .is_some_and(|watchpoints| watchpoints.slots.iter().all(Option::is_none))
+    {
+        let _ = all.remove(&pid);
+    }
+}
+
+/// Let the stopped thread `tid` carry on.
+fn resume_thread(tid: Tid, resume: Resume) -> std::io::Result<()> {
+    let result = match resume {
+        Resume::Continue(signal) => unsafe { libc::ptrace(libc::PTRACE_CONT, tid, 0, signal) },
+        Resume::Listen => unsafe { libc::ptrace(libc::PTRACE_LISTEN, tid, 0, 0) },
+    };
+    if result == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// The offset of the debug register `DR<index>` in the `user` area read and written by
+/// `PTRACE_PEEKUSER` and `PTRACE_POKEUSER`.
+fn debug_register_offset(index: usize) -> usize {
+    std::mem::offset_of!(libc::user, u_debugreg) + index * std::mem::size_of::<usize>()
+}
+
+/// Read the debug register `DR<index>` of the stopped thread `tid`.
+fn peek_debug_register(tid: Tid, index: usize) -> std::io::Result<usize> {
+    let offset = debug_register_offset(index);
+    unsafe {
+        *libc::__errno_location() = 0;
+        let value = libc::ptrace(libc::PTRACE_PEEKUSER, tid, offset, 0);
+        let err = std::io::Error::last_os_error();
+        if value == -1 && err.raw_os_error() != Some(0) {
+            Err(err)
+        } else {
+            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+            Ok(value as usize)
+        }
+    }
+}
+
+/// Write `value` to the debug register `DR<index>` of the stopped thread `tid`.
+fn poke_debug_register(tid: Tid, index: usize, value: usize) -> std::io::Result<()> {
+    let offset = debug_register_offset(index);
+    if unsafe { libc::ptrace(libc::PTRACE_POKEUSER, tid, offset, value) } == -1 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EnumerateThreads, TryIntoProcessHandle};
+
+    #[test]
+    fn watch_child_instructions() {
+        let mut child = std::process::Command::new("yes")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let handle = child.try_into_process_handle().unwrap();
+        #[allow(clippy::cast_possible_wrap)]
+        let tid = child.id() as Tid;
+        // Once it has started up, `yes` writes in a loop, so it will soon run the instruction it
+        // is stopped at again.
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let addr = handle.thread_context(tid).unwrap().instruction_pointer();
+
+        assert_eq!(
+            handle
+                .set_watchpoint(addr, WatchSize::DWord, WatchKind::Execute)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            handle
+                .set_watchpoint(addr | 1, WatchSize::Word, WatchKind::Write)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        let id = handle
+            .set_watchpoint(addr, WatchSize::Byte, WatchKind::Execute)
+            .unwrap();
+        let other = handle.clone();
+        assert_eq!(
+            std::thread::spawn(move || other.watchpoint_hits().unwrap_err().kind())
+                .join()
+                .unwrap(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        let start = std::time::Instant::now();
+        let hit = loop {
+            if let Some(hit) = handle.watchpoint_hits().unwrap().pop() {
+                break hit;
+            }
+            assert!(start.elapsed() < std::time::Duration::from_secs(10));
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        };
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.tid, tid);
+        assert_eq!(hit.context.instruction_pointer(), addr);
+
+        handle.clear_watchpoint(id).unwrap();
+        assert_eq!(
+            handle.clear_watchpoint(id).unwrap_err().kind(),
+            std::io::ErrorKind::NotFound
+        );
+        // Once detached, the registers can be read by attaching again.
+        let _ = handle.thread_context(tid).unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        let _ = child.wait().unwrap();
+    }
+}
@@ -1,92 +1,787 @@
 use winapi::shared::minwindef;
+use winapi::shared::ntdef::NTSTATUS;
+use winapi::um::winnt;
 
-use std::os::windows::io::AsRawHandle;
+use std::os::windows::io::{AsRawHandle, FromRawHandle, IntoRawHandle, RawHandle};
 use std::process::Child;
 use std::ptr;
 
-use super::{CopyAddress, HandleChecker, PutAddress, TryIntoProcessHandle};
+use crate::memory_error::{classified_os_error, ErrorClass};
+use crate::trace::{trace_read, trace_write};
+
+use super::{
+    AccessMode, Allocate, Architecture, ControlProcess, CopyAddress, EnumerateRegions,
+    EnumerateThreads, FindModule, ProcessHandleExt, ProtectMemory, Protection, PutAddress, Region,
+    ThreadContext, ThreadInfo, TryIntoProcessHandle,
+};
 
 /// On Windows a `Pid` is a `DWORD`.
 pub type Pid = minwindef::DWORD;
-/// On Windows a `ProcessHandle` is a `HANDLE`.
-pub type ProcessHandle = winapi::um::winnt::HANDLE;
+/// On Windows a `Tid` is also a `DWORD`.
+pub type Tid = minwindef::DWORD;
+/// On Windows the raw form of a `ProcessHandle` is the process `HANDLE`.
+pub type RawProcessHandle = winnt::HANDLE;
+/// On Windows the registers of a thread are a `CONTEXT`, as read with `GetThreadContext`.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+pub type RawThreadContext = winnt::CONTEXT;
+/// Registers can't be read on this architecture, so there are none.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+pub type RawThreadContext = ();
+/// On Windows a `ProcessHandle` is an [`OwnedProcessHandle`], along with the `Architecture` of the
+/// process.
+///
+/// The `HANDLE` is shared between a `ProcessHandle` and all of its clones, and is closed once the
+/// last of them is dropped. A `ProcessHandle` is `Send` and `Sync`, so it can be shared between
+/// threads.
+///
+/// [`OwnedProcessHandle`]: struct.OwnedProcessHandle.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProcessHandle {
+    handle: std::sync::Arc<OwnedProcessHandle>,
+    arch: Architecture,
+    nt_apis: bool,
+}
 
-impl HandleChecker for ProcessHandle {
-    fn check_handle(&self) -> bool {
-        self.is_null()
+impl ProcessHandle {
+    /// Take ownership of the process `HANDLE` `handle`. If the process is a 32-bit process running
+    /// under WOW64, its `Architecture` is set to `Arch32Bit`, so that pointers are read with the
+    /// right width, otherwise it is that of the host process.
+    fn from_owned(handle: winnt::HANDLE) -> ProcessHandle {
+        let handle = ProcessHandle {
+            handle: std::sync::Arc::new(OwnedProcessHandle(handle)),
+            arch: Architecture::from_native(),
+            nt_apis: false,
+        };
+        match handle.is_wow64() {
+            Ok(true) => handle.set_arch(Architecture::Arch32Bit),
+            _ => handle,
+        }
     }
+
+    /// Use `IsWow64Process` to check whether the process is a 32-bit process running under WOW64
+    /// on 64-bit Windows.
+    ///
+    /// This is checked when a `ProcessHandle` is created, and the `Architecture` of WOW64
+    /// processes is set to `Arch32Bit`, so there is normally no need to call this yourself.
+    ///
+    /// ```no_run
+    /// # use process_memory::{Architecture, Pid, ProcessHandleExt, TryIntoProcessHandle};
+    /// # fn get_pid(process_name: &str) -> Pid {
+    /// #     std::process::id() as Pid
+    /// # }
+    /// let handle = get_pid("32Bit.exe").try_into_process_handle().unwrap();
+    /// assert!(handle.is_wow64().unwrap());
+    /// assert_eq!(handle.get_arch(), Architecture::Arch32Bit);
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the handle doesn't have the `PROCESS_QUERY_LIMITED_INFORMATION` access
+    /// right.
+    pub fn is_wow64(&self) -> std::io::Result<bool> {
+        let mut wow64 = minwindef::FALSE;
+        if unsafe { winapi::um::wow64apiset::IsWow64Process(self.as_raw(), &raw mut wow64) }
+            == minwindef::FALSE
+        {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(wow64 != minwindef::FALSE)
+        }
+    }
+
+    /// Get the address of the process environment block (PEB) of the process, which holds such
+    /// things as its loaded modules and process parameters, from `NtQueryInformationProcess`.
+    ///
+    /// A 32-bit process running under WOW64 has a 64-bit PEB as well as a 32-bit one. This
+    /// returns the address of the 32-bit PEB, which is laid out with 32-bit pointers to match the
+    /// `Architecture` of the handle.
+    ///
+    /// # Errors
+    /// Returns an error if the handle doesn't have the `PROCESS_QUERY_LIMITED_INFORMATION` access
+    /// right.
+    pub fn peb_base(&self) -> std::io::Result<usize> {
+        peb(self).map(|(peb, _)| peb)
+    }
+
+    /// Read and write memory with `NtReadVirtualMemory` and `NtWriteVirtualMemory`, which are
+    /// looked up in `ntdll` when first used, instead of with `ReadProcessMemory` and
+    /// `WriteProcessMemory` from `kernel32`. This skips over anything that hooks the `kernel32`
+    /// functions but not the system calls underneath them.
+    ///
+    /// The `ntdll` functions are undocumented, so by default they aren't used. If they can't be
+    /// found, memory is read and written with `ReadProcessMemory` and `WriteProcessMemory` anyway,
+    /// which can be checked with [`ProcessHandle::uses_nt_apis`].
+    ///
+    /// ```no_run
+    /// # use process_memory::{CopyAddress, Pid, TryIntoProcessHandle};
+    /// # let pid = std::process::id() as Pid;
+    /// let handle = pid.try_into_process_handle().unwrap().use_nt_apis(true);
+    /// let mut bytes = [0_u8; 4];
+    /// handle.copy_address(0x1000_0000, &mut bytes).unwrap();
+    /// ```
+    ///
+    /// [`ProcessHandle::uses_nt_apis`]: struct.ProcessHandle.html#method.uses_nt_apis
     #[must_use]
+    pub fn use_nt_apis(mut self, enable: bool) -> Self {
+        self.nt_apis = enable;
+        self
+    }
+
+    /// Check whether memory is read and written with the `ntdll` functions, which is only the
+    /// case if they were asked for with [`ProcessHandle::use_nt_apis`] and could be found.
+    ///
+    /// [`ProcessHandle::use_nt_apis`]: struct.ProcessHandle.html#method.use_nt_apis
+    #[must_use]
+    pub fn uses_nt_apis(&self) -> bool {
+        self.nt_apis().is_some()
+    }
+
+    /// Replace `err`, from failing to read or write memory, with an error classified as
+    /// `MemoryError::ProcessGone` if the process has exited.
+    fn gone(&self, err: std::io::Error) -> std::io::Error {
+        if self.is_alive() {
+            return err;
+        }
+        let message = format!("The process has exited ({err})");
+        // Only the error code is kept, which may be behind a more descriptive error already.
+        let code = crate::MemoryError::from(err).raw_os_error().map_or_else(
+            || std::io::Error::from(std::io::ErrorKind::NotFound),
+            std::io::Error::from_raw_os_error,
+        );
+        classified_os_error(
+            &code,
+            std::io::ErrorKind::NotFound,
+            ErrorClass::ProcessGone,
+            message,
+        )
+    }
+
+    /// Get the `ntdll` functions to read and write memory with, if they should be used.
+    fn nt_apis(&self) -> Option<&'static NtMemoryApi> {
+        if self.nt_apis {
+            nt_memory_api()
+        } else {
+            None
+        }
+    }
+
+    /// Take ownership of a process `HANDLE` that was obtained elsewhere, such as from your own
+    /// call to `OpenProcess`. Like a `ProcessHandle` from [`try_into_process_handle`], its
+    /// `Architecture` is set to `Arch32Bit` if the process is running under WOW64.
+    ///
+    /// The `HANDLE` is closed with `CloseHandle` once the `ProcessHandle` and all of its clones
+    /// have been dropped, unless it is taken back with [`ProcessHandle::into_raw_handle`].
+    ///
+    /// # Safety
+    /// `raw` must be a valid process `HANDLE` (or null) that is owned by the caller, and must not
+    /// be closed or used by anything else once it has been handed over.
+    ///
+    /// [`try_into_process_handle`]: trait.TryIntoProcessHandle.html#tymethod.try_into_process_handle
+    /// [`ProcessHandle::into_raw_handle`]: struct.ProcessHandle.html#method.into_raw_handle
+    #[must_use]
+    pub unsafe fn from_raw_handle(raw: RawProcessHandle) -> ProcessHandle {
+        Self::from_owned(raw)
+    }
+
+    /// Give up ownership of the `HANDLE` of the process, which the caller must close with
+    /// `CloseHandle` once it is done with it.
+    ///
+    /// If other clones of the `ProcessHandle` still share the `HANDLE`, they keep it, and a
+    /// duplicate of it, with the same access rights, is returned instead.
+    ///
+    /// # Panics
+    /// Panics if the `HANDLE` is shared and can't be duplicated, which only happens if the system
+    /// is out of resources.
+    #[must_use]
+    pub fn into_raw_handle(self) -> RawProcessHandle {
+        match std::sync::Arc::try_unwrap(self.handle) {
+            Ok(owned) => {
+                let raw = owned.0;
+                std::mem::forget(owned);
+                raw
+            }
+            Err(shared) => {
+                use winapi::um::processthreadsapi::GetCurrentProcess;
+
+                let mut duplicate = ptr::null_mut();
+                if unsafe {
+                    winapi::um::handleapi::DuplicateHandle(
+                        GetCurrentProcess(),
+                        shared.as_raw(),
+                        GetCurrentProcess(),
+                        &raw mut duplicate,
+                        0,
+                        minwindef::FALSE,
+                        winnt::DUPLICATE_SAME_ACCESS,
+                    )
+                } == minwindef::FALSE
+                {
+                    panic!(
+                        "Failed to duplicate the process handle: {}",
+                        std::io::Error::last_os_error()
+                    );
+                }
+                duplicate
+            }
+        }
+    }
+
+    /// Get the raw `HANDLE` of the process, for use with other Windows API functions. The
+    /// `HANDLE` is still owned by the `ProcessHandle`, so it must not be closed, and must not be
+    /// used once the `ProcessHandle` and all of its clones have been dropped.
+    #[must_use]
+    pub fn as_raw(&self) -> winnt::HANDLE {
+        self.handle.as_raw()
+    }
+}
+
+/// The same as [`ProcessHandle::from_raw_handle`].
+///
+/// [`ProcessHandle::from_raw_handle`]: struct.ProcessHandle.html#method.from_raw_handle
+impl FromRawHandle for ProcessHandle {
+    unsafe fn from_raw_handle(handle: RawHandle) -> Self {
+        ProcessHandle::from_raw_handle(handle.cast())
+    }
+}
+
+/// The same as [`ProcessHandle::into_raw_handle`].
+///
+/// [`ProcessHandle::into_raw_handle`]: struct.ProcessHandle.html#method.into_raw_handle
+impl IntoRawHandle for ProcessHandle {
+    fn into_raw_handle(self) -> RawHandle {
+        ProcessHandle::into_raw_handle(self).cast()
+    }
+}
+
+/// Borrow the `OwnedProcessHandle` that a `ProcessHandle` shares with its clones.
+impl AsRef<OwnedProcessHandle> for ProcessHandle {
+    fn as_ref(&self) -> &OwnedProcessHandle {
+        &self.handle
+    }
+}
+
+impl AsRawHandle for ProcessHandle {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.as_raw().cast()
+    }
+}
+
+/// A process `HANDLE` that is closed with `CloseHandle` when it is dropped.
+///
+/// Every `ProcessHandle` holds its `HANDLE` in one of these, so handles opened by
+/// [`try_into_process_handle`] no longer leak. It can also be used to read and write memory by
+/// itself, in which case pointers are assumed to be the width of those in this process.
+///
+/// [`try_into_process_handle`]: trait.TryIntoProcessHandle.html#tymethod.try_into_process_handle
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct OwnedProcessHandle(winnt::HANDLE);
+
+// SAFETY: A `HANDLE` is only a pointer because it is an opaque value, not because it points to
+// memory in this process. A process handle isn't tied to the thread that opened it, and every
+// function that we call with it (such as `ReadProcessMemory` and `WriteProcessMemory`) may be
+// called on the same handle from several threads at once.
+unsafe impl Send for OwnedProcessHandle {}
+// SAFETY: See above. Nothing is ever written through a `&OwnedProcessHandle`.
+unsafe impl Sync for OwnedProcessHandle {}
+
+impl OwnedProcessHandle {
+    /// Get the raw `HANDLE`, for use with other Windows API functions. The `HANDLE` is still owned
+    /// by the `OwnedProcessHandle`, so it must not be closed, and must not be used once the
+    /// `OwnedProcessHandle` has been dropped.
+    #[must_use]
+    pub fn as_raw(&self) -> winnt::HANDLE {
+        self.0
+    }
+}
+
+impl AsRawHandle for OwnedProcessHandle {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.0.cast()
+    }
+}
+
+impl Drop for OwnedProcessHandle {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe {
+                let _ = winapi::um::handleapi::CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+impl ProcessHandleExt for ProcessHandle {
+    fn check_handle(&self) -> bool {
+        !self.as_raw().is_null()
+    }
     fn null_type() -> ProcessHandle {
-        ptr::null_mut()
+        ProcessHandle {
+            handle: std::sync::Arc::new(OwnedProcessHandle(ptr::null_mut())),
+            arch: Architecture::from_native(),
+            nt_apis: false,
+        }
+    }
+    /// Use `GetProcessId` to find the `Pid` a `HANDLE` refers to.
+    fn get_pid(&self) -> std::io::Result<Pid> {
+        match unsafe { winapi::um::processthreadsapi::GetProcessId(self.as_raw()) } {
+            0 => Err(std::io::Error::last_os_error()),
+            pid => Ok(pid),
+        }
+    }
+    fn set_arch(mut self, arch: Architecture) -> Self {
+        self.arch = arch;
+        self
+    }
+    fn get_arch(&self) -> Architecture {
+        self.arch
+    }
+    fn page_size(&self) -> usize {
+        page_size()
+    }
+    /// A process handle is signalled once the process has exited, which is checked with
+    /// `WaitForSingleObject` without waiting. This needs the handle to have `SYNCHRONIZE` access,
+    /// which is asked for when opening a `Pid`.
+    ///
+    /// For a handle without it, `GetExitCodeProcess` is used to check whether the process has an
+    /// exit code yet instead. A process that is still running reports `STILL_ACTIVE`, so a process
+    /// that exited with an exit code of `STILL_ACTIVE` (259) can't be told apart from a running
+    /// one. This needs the handle to have `PROCESS_QUERY_LIMITED_INFORMATION` access, which
+    /// [`open_process`] always asks for.
+    ///
+    /// [`open_process`]: fn.open_process.html
+    fn is_alive(&self) -> bool {
+        if !self.check_handle() {
+            return false;
+        }
+        match unsafe { winapi::um::synchapi::WaitForSingleObject(self.as_raw(), 0) } {
+            winapi::shared::winerror::WAIT_TIMEOUT => return true,
+            winapi::um::winbase::WAIT_OBJECT_0 => return false,
+            _ => {}
+        }
+        let mut code: minwindef::DWORD = 0;
+        (unsafe { winapi::um::processthreadsapi::GetExitCodeProcess(self.as_raw(), &raw mut code) })
+            != minwindef::FALSE
+            && code == winapi::um::minwinbase::STILL_ACTIVE
+    }
+    /// The path comes from `QueryFullProcessImageNameW`, which needs the handle to have
+    /// `PROCESS_QUERY_LIMITED_INFORMATION` access.
+    fn exe_path(&self) -> std::io::Result<std::path::PathBuf> {
+        use std::os::windows::ffi::OsStringExt;
+
+        // The longest path that Windows supports.
+        let mut path = vec![0_u16; 32_768];
+        #[allow(clippy::cast_possible_truncation)]
+        let mut len = path.len() as minwindef::DWORD;
+        if unsafe {
+            winapi::um::winbase::QueryFullProcessImageNameW(
+                self.as_raw(),
+                0,
+                path.as_mut_ptr(),
+                &raw mut len,
+            )
+        } == minwindef::FALSE
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        path.truncate(len as usize);
+        Ok(std::ffi::OsString::from_wide(&path).into())
+    }
+    /// The command line is read from the `ProcessParameters` of the process's PEB, which needs
+    /// the handle to have `PROCESS_QUERY_INFORMATION` and `PROCESS_VM_READ` access, and is split
+    /// into arguments with `CommandLineToArgvW`. A 64-bit process can't be inspected from a
+    /// 32-bit one.
+    fn cmdline(&self) -> std::io::Result<Vec<String>> {
+        split_command_line(&read_command_line(self)?)
     }
 }
 
-/// A `Pid` can be turned into a `ProcessHandle` with `OpenProcess`.
+/// A `Pid` can be turned into a `ProcessHandle` with `OpenProcess`, asking for enough access for
+/// everything a `ProcessHandle` can do.
 impl TryIntoProcessHandle for minwindef::DWORD {
     fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
-        let handle = unsafe {
-            winapi::um::processthreadsapi::OpenProcess(
-                winapi::um::winnt::PROCESS_CREATE_THREAD
-                    | winapi::um::winnt::PROCESS_QUERY_INFORMATION
-                    | winapi::um::winnt::PROCESS_VM_READ
-                    | winapi::um::winnt::PROCESS_VM_WRITE
-                    | winapi::um::winnt::PROCESS_VM_OPERATION,
-                winapi::shared::minwindef::FALSE,
-                *self,
+        open(
+            *self,
+            winnt::PROCESS_CREATE_THREAD
+                | winnt::PROCESS_QUERY_INFORMATION
+                | winnt::PROCESS_VM_READ
+                | winnt::PROCESS_VM_WRITE
+                | winnt::PROCESS_VM_OPERATION
+                | winnt::PROCESS_SUSPEND_RESUME
+                | winnt::SYNCHRONIZE,
+        )
+    }
+}
+
+/// `access` is turned into the access rights passed to `OpenProcess`. As it is needed to find the
+/// `Pid` of a `ProcessHandle`, such as when finding modules, `PROCESS_QUERY_LIMITED_INFORMATION` is
+/// always requested, which is granted to anything that can be granted any other access, along with
+/// `SYNCHRONIZE` to tell when the process has exited.
+pub(crate) fn open_process(pid: Pid, access: AccessMode) -> std::io::Result<ProcessHandle> {
+    let mut rights = winnt::PROCESS_QUERY_LIMITED_INFORMATION | winnt::SYNCHRONIZE;
+    if access.contains(AccessMode::READ) {
+        rights |= winnt::PROCESS_VM_READ;
+    }
+    if access.contains(AccessMode::WRITE) {
+        rights |= winnt::PROCESS_VM_WRITE | winnt::PROCESS_VM_OPERATION;
+    }
+    if access.contains(AccessMode::QUERY) {
+        rights |= winnt::PROCESS_QUERY_INFORMATION;
+    }
+    open(pid, rights)
+}
+
+/// The size of a page of memory on this system, from `GetSystemInfo`.
+pub(crate) fn page_size() -> usize {
+    let mut info: winapi::um::sysinfoapi::SYSTEM_INFO = unsafe { std::mem::zeroed() };
+    unsafe { winapi::um::sysinfoapi::GetSystemInfo(&raw mut info) };
+    info.dwPageSize as usize
+}
+
+/// Open `pid` with `OpenProcess`, asking for the access `rights`.
+fn open(pid: Pid, rights: minwindef::DWORD) -> std::io::Result<ProcessHandle> {
+    let handle =
+        unsafe { winapi::um::processthreadsapi::OpenProcess(rights, minwindef::FALSE, pid) };
+    if handle.is_null() {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(ProcessHandle::from_owned(handle))
+    }
+}
+
+/// A `std::process::Child` has a `HANDLE` from calling `CreateProcess`. As that `HANDLE` is owned
+/// (and closed) by the `Child`, it is duplicated with `DuplicateHandle` so that the
+/// `ProcessHandle` can close its own copy.
+impl TryIntoProcessHandle for Child {
+    fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
+        let mut handle = ptr::null_mut();
+        if unsafe {
+            let current = winapi::um::processthreadsapi::GetCurrentProcess();
+            winapi::um::handleapi::DuplicateHandle(
+                current,
+                self.as_raw_handle().cast(),
+                current,
+                &raw mut handle,
+                0,
+                minwindef::FALSE,
+                winnt::DUPLICATE_SAME_ACCESS,
             )
-        };
-        if handle == (0 as ProcessHandle) {
+        } == minwindef::FALSE
+        {
             Err(std::io::Error::last_os_error())
         } else {
-            Ok(handle)
+            Ok(ProcessHandle::from_owned(handle))
         }
     }
 }
 
-/// A `std::process::Child` has a `HANDLE` from calling `CreateProcess`.
-impl TryIntoProcessHandle for Child {
-    fn try_into_process_handle(&self) -> std::io::Result<ProcessHandle> {
-        Ok(self.as_raw_handle() as ProcessHandle)
+/// Reads are made the same way as with the `OwnedProcessHandle`, using the `Architecture` of the
+/// `ProcessHandle` for pointers, unless they should go through `ntdll`.
+///
+/// Once the process has exited, its handle stays valid, but reads fail with whatever error the
+/// empty address space gives, such as `ERROR_PARTIAL_COPY`, `ERROR_ACCESS_DENIED` or
+/// `ERROR_INVALID_HANDLE`. Any read that fails after the process has exited is therefore
+/// classified as `MemoryError::ProcessGone`, keeping the original error code.
+impl CopyAddress for ProcessHandle {
+    fn get_pointer_width(&self) -> Architecture {
+        self.arch
+    }
+
+    fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
+        let result =
+            copy_address(self.as_raw(), self.nt_apis(), addr, buf).map_err(|err| self.gone(err));
+        trace_read(self, addr, buf.len(), &result);
+        result
+    }
+
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.copy_address_uninit(addr, crate::as_uninit_mut(buf))
+    }
+
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        copy_address_uninit(self.as_raw(), self.nt_apis(), addr, buf).map_err(|err| self.gone(err))
+    }
+}
+
+/// Writes are made the same way as with the `OwnedProcessHandle`, unless they should go through
+/// `ntdll`. As with reads, a write that fails after the process has exited is classified as
+/// `MemoryError::ProcessGone`.
+impl PutAddress for ProcessHandle {
+    fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        let result =
+            put_address(self.as_raw(), self.nt_apis(), addr, buf).map_err(|err| self.gone(err));
+        trace_write(self, addr, buf.len(), &result);
+        result
     }
 }
 
 /// Use `ReadProcessMemory` to read memory from another process on Windows.
-impl CopyAddress for ProcessHandle {
+impl CopyAddress for OwnedProcessHandle {
+    /// Reading only part of `buf` is an error. If `ReadProcessMemory` fails with
+    /// `ERROR_PARTIAL_COPY` after reading some of it, the error says where reading stopped, and
+    /// `MemoryError::raw_os_error` still gives `ERROR_PARTIAL_COPY`.
     fn copy_address(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<()> {
-        if buf.is_empty() {
-            return Ok(());
-        }
+        copy_address(self.0, None, addr, buf)
+    }
 
-        if unsafe {
-            winapi::um::memoryapi::ReadProcessMemory(
-                *self,
+    /// `ReadProcessMemory` fails with `ERROR_PARTIAL_COPY` when it reaches an unreadable page,
+    /// but still tells us how many bytes it read before that.
+    fn copy_address_partial(&self, addr: usize, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.copy_address_uninit(addr, crate::as_uninit_mut(buf))
+    }
+
+    /// `ReadProcessMemory` copies straight into `buf`, so it doesn't need to be zeroed first.
+    fn copy_address_uninit(
+        &self,
+        addr: usize,
+        buf: &mut [std::mem::MaybeUninit<u8>],
+    ) -> std::io::Result<usize> {
+        copy_address_uninit(self.0, None, addr, buf)
+    }
+}
+
+/// Use `WriteProcessMemory` to write memory from another process on Windows.
+impl PutAddress for OwnedProcessHandle {
+    fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
+        put_address(self.0, None, addr, buf)
+    }
+}
+
+/// Read all of `buf` at `addr` from `process`, with `nt` if it is given, or `ReadProcessMemory`
+/// otherwise.
+fn copy_address(
+    process: winnt::HANDLE,
+    nt: Option<&NtMemoryApi>,
+    addr: usize,
+    buf: &mut [u8],
+) -> std::io::Result<()> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    let (read, err) = read_process_memory(process, nt, addr, crate::as_uninit_mut(buf));
+    match err {
+        Some(err) if read > 0 => Err(classified_os_error(
+            &err,
+            std::io::ErrorKind::InvalidInput,
+            ErrorClass::AddressUnreadable,
+            format!(
+                "Unable to read {} bytes at {:#x} (only the first {read} of {} bytes at {addr:#x} \
+                     could be read)",
+                buf.len() - read,
+                addr + read,
+                buf.len()
+            ),
+        )),
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Read as much of `buf` as possible at `addr` from `process`, with `nt` if it is given, or
+/// `ReadProcessMemory` otherwise.
+fn copy_address_uninit(
+    process: winnt::HANDLE,
+    nt: Option<&NtMemoryApi>,
+    addr: usize,
+    buf: &mut [std::mem::MaybeUninit<u8>],
+) -> std::io::Result<usize> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+
+    match read_process_memory(process, nt, addr, buf) {
+        (0, Some(err)) => Err(err),
+        (read, _) => Ok(read),
+    }
+}
+
+/// Read `buf.len()` bytes at `addr` from `process` with `NtReadVirtualMemory` from `nt`, or
+/// `ReadProcessMemory` if there is no `nt`, returning the number of bytes that were read (and so
+/// initialised), and the error if it failed. The count is meaningful even when it failed, as both
+/// fail with a partial copy error after reading as much as they could.
+fn read_process_memory(
+    process: winnt::HANDLE,
+    nt: Option<&NtMemoryApi>,
+    addr: usize,
+    buf: &mut [std::mem::MaybeUninit<u8>],
+) -> (usize, Option<std::io::Error>) {
+    let mut read: winapi::shared::basetsd::SIZE_T = 0;
+    if let Some(nt) = nt {
+        let status = unsafe {
+            (nt.read)(
+                process,
                 addr as minwindef::LPVOID,
                 buf.as_mut_ptr() as minwindef::LPVOID,
                 buf.len() as winapi::shared::basetsd::SIZE_T,
+                &raw mut read,
+            )
+        };
+        return (read, nt_result(status).err());
+    }
+    if unsafe {
+        winapi::um::memoryapi::ReadProcessMemory(
+            process,
+            addr as minwindef::LPVOID,
+            buf.as_mut_ptr() as minwindef::LPVOID,
+            buf.len() as winapi::shared::basetsd::SIZE_T,
+            &raw mut read,
+        )
+    } == winapi::shared::minwindef::FALSE
+    {
+        (read, Some(std::io::Error::last_os_error()))
+    } else {
+        (read, None)
+    }
+}
+
+/// Write all of `buf` to `addr` in `process` with `NtWriteVirtualMemory` from `nt`, or
+/// `WriteProcessMemory` if there is no `nt`.
+fn put_address(
+    process: winnt::HANDLE,
+    nt: Option<&NtMemoryApi>,
+    addr: usize,
+    buf: &[u8],
+) -> std::io::Result<()> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+    if let Some(nt) = nt {
+        return nt_result(unsafe {
+            (nt.write)(
+                process,
+                addr as minwindef::LPVOID,
+                buf.as_ptr() as minwindef::LPCVOID,
+                buf.len() as winapi::shared::basetsd::SIZE_T,
                 ptr::null_mut(),
             )
+        });
+    }
+    if unsafe {
+        winapi::um::memoryapi::WriteProcessMemory(
+            process,
+            addr as minwindef::LPVOID,
+            buf.as_ptr() as minwindef::LPCVOID,
+            buf.len() as winapi::shared::basetsd::SIZE_T,
+            ptr::null_mut(),
+        )
+    } == winapi::shared::minwindef::FALSE
+    {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// The signature of `NtReadVirtualMemory`.
+type NtReadVirtualMemory = unsafe extern "system" fn(
+    winnt::HANDLE,
+    minwindef::LPVOID,
+    minwindef::LPVOID,
+    winapi::shared::basetsd::SIZE_T,
+    *mut winapi::shared::basetsd::SIZE_T,
+) -> NTSTATUS;
+/// The signature of `NtWriteVirtualMemory`.
+type NtWriteVirtualMemory = unsafe extern "system" fn(
+    winnt::HANDLE,
+    minwindef::LPVOID,
+    minwindef::LPCVOID,
+    winapi::shared::basetsd::SIZE_T,
+    *mut winapi::shared::basetsd::SIZE_T,
+) -> NTSTATUS;
+
+/// `NtReadVirtualMemory` and `NtWriteVirtualMemory`, which `ReadProcessMemory` and
+/// `WriteProcessMemory` call into.
+#[derive(Debug)]
+struct NtMemoryApi {
+    read: NtReadVirtualMemory,
+    write: NtWriteVirtualMemory,
+}
+
+/// Look up `NtReadVirtualMemory` and `NtWriteVirtualMemory` in `ntdll` the first time they are
+/// needed, giving `None` if either of them can't be found.
+fn nt_memory_api() -> Option<&'static NtMemoryApi> {
+    static API: std::sync::OnceLock<Option<NtMemoryApi>> = std::sync::OnceLock::new();
+
+    API.get_or_init(|| {
+        use winapi::um::libloaderapi::{GetModuleHandleW, GetProcAddress};
+
+        let name: Vec<u16> = "ntdll.dll".encode_utf16().chain(Some(0)).collect();
+        let ntdll = unsafe { GetModuleHandleW(name.as_ptr()) };
+        if ntdll.is_null() {
+            return None;
+        }
+        let read = unsafe { GetProcAddress(ntdll, b"NtReadVirtualMemory\0".as_ptr().cast()) };
+        let write = unsafe { GetProcAddress(ntdll, b"NtWriteVirtualMemory\0".as_ptr().cast()) };
+        if read.is_null() || write.is_null() {
+            return None;
+        }
+        // Both are exported with the signatures above.
+        Some(unsafe {
+            NtMemoryApi {
+                read: std::mem::transmute::<minwindef::FARPROC, NtReadVirtualMemory>(read),
+                write: std::mem::transmute::<minwindef::FARPROC, NtWriteVirtualMemory>(write),
+            }
+        })
+    })
+    .as_ref()
+}
+
+/// Use `VirtualProtectEx` to change the protection of memory in another process on Windows.
+impl ProtectMemory for ProcessHandle {
+    fn set_protection(
+        &self,
+        addr: usize,
+        size: usize,
+        prot: Protection,
+    ) -> std::io::Result<Protection> {
+        let mut old: minwindef::DWORD = 0;
+        if unsafe {
+            winapi::um::memoryapi::VirtualProtectEx(
+                self.as_raw(),
+                addr as minwindef::LPVOID,
+                size as winapi::shared::basetsd::SIZE_T,
+                to_page_protection(prot),
+                &raw mut old,
+            )
         } == winapi::shared::minwindef::FALSE
         {
             Err(std::io::Error::last_os_error())
         } else {
-            Ok(())
+            Ok(from_page_protection(old))
         }
     }
 }
 
-/// Use `WriteProcessMemory` to write memory from another process on Windows.
-impl PutAddress for ProcessHandle {
-    fn put_address(&self, addr: usize, buf: &[u8]) -> std::io::Result<()> {
-        if buf.is_empty() {
-            return Ok(());
+/// Use `VirtualAllocEx` and `VirtualFreeEx` to manage memory in another process on Windows.
+impl Allocate for ProcessHandle {
+    fn allocate(&self, size: usize, prot: Protection) -> std::io::Result<usize> {
+        let address = unsafe {
+            winapi::um::memoryapi::VirtualAllocEx(
+                self.as_raw(),
+                ptr::null_mut(),
+                size as winapi::shared::basetsd::SIZE_T,
+                winapi::um::winnt::MEM_COMMIT | winapi::um::winnt::MEM_RESERVE,
+                to_page_protection(prot),
+            )
+        };
+        if address.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(address as usize)
         }
+    }
+
+    /// `MEM_RELEASE` always frees the entire allocation, so `size` is unused on Windows.
+    fn free(&self, addr: usize, _size: usize) -> std::io::Result<()> {
         if unsafe {
-            winapi::um::memoryapi::WriteProcessMemory(
-                *self,
+            winapi::um::memoryapi::VirtualFreeEx(
+                self.as_raw(),
                 addr as minwindef::LPVOID,
-                buf.as_ptr() as minwindef::LPCVOID,
-                buf.len() as winapi::shared::basetsd::SIZE_T,
-                ptr::null_mut(),
+                0,
+                winapi::um::winnt::MEM_RELEASE,
             )
         } == winapi::shared::minwindef::FALSE
         {
@@ -96,3 +791,522 @@ impl PutAddress for ProcessHandle {
         }
     }
 }
+
+/// Use `VirtualQueryEx` to walk the memory map of another process on Windows.
+///
+/// `VirtualQueryEx` also describes reserved and free parts of the address space, which we skip
+/// as they can't be accessed. It fails once we walk past the end of user space, which is how we
+/// know we are finished.
+impl EnumerateRegions for ProcessHandle {
+    fn regions(&self) -> std::io::Result<Vec<Region>> {
+        self.regions_iter()?.collect()
+    }
+
+    /// Each region is queried with `VirtualQueryEx` as the iterator is advanced. The iterator ends
+    /// once `VirtualQueryEx` fails, which it does when the address is past the end of the address
+    /// space.
+    fn regions_iter(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<Region>> + '_> {
+        let mut address = Some(0_usize);
+        Ok(std::iter::from_fn(move || loop {
+            let current = address?;
+            let info = query_region(self, current)?;
+            let base = info.BaseAddress as usize;
+            address = base
+                .checked_add(info.RegionSize)
+                .filter(|&next| next > current);
+            if let Some(region) = to_region(&info) {
+                return Some(Ok(region));
+            }
+        }))
+    }
+
+    /// `VirtualQueryEx` can look up the region containing an address directly.
+    fn region_containing(&self, addr: usize) -> std::io::Result<Option<Region>> {
+        Ok(query_region(self, addr).and_then(|info| to_region(&info)))
+    }
+
+    /// Regions aren't labelled, so this is the region holding the headers of the module called
+    /// `name`. There is nothing like `[heap]`, as a heap is made of many regions.
+    fn find_region_by_name(&self, name: &str) -> std::io::Result<Option<Region>> {
+        crate::module_region(self, name)
+    }
+}
+
+/// Use `VirtualQueryEx` to get the information of the region containing `addr`, or `None` if
+/// `addr` is past the end of the address space.
+fn query_region(
+    handle: &ProcessHandle,
+    addr: usize,
+) -> Option<winapi::um::winnt::MEMORY_BASIC_INFORMATION> {
+    let mut info: winapi::um::winnt::MEMORY_BASIC_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe {
+        winapi::um::memoryapi::VirtualQueryEx(
+            handle.as_raw(),
+            addr as minwindef::LPCVOID,
+            &raw mut info,
+            std::mem::size_of_val(&info),
+        )
+    } == 0
+    {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+/// Turn the information from `VirtualQueryEx` into a `Region`, or `None` if the memory isn't
+/// committed.
+fn to_region(info: &winapi::um::winnt::MEMORY_BASIC_INFORMATION) -> Option<Region> {
+    (info.State == winapi::um::winnt::MEM_COMMIT).then(|| Region {
+        base: info.BaseAddress as usize,
+        size: info.RegionSize,
+        // Touching a guard page raises an exception, so we treat them as inaccessible.
+        protection: if info.Protect & winapi::um::winnt::PAGE_GUARD == 0 {
+            from_page_protection(info.Protect)
+        } else {
+            Protection::empty()
+        },
+        is_private: info.Type == winapi::um::winnt::MEM_PRIVATE,
+    })
+}
+
+#[link(name = "ntdll")]
+extern "system" {
+    /// `NtSuspendProcess`, `NtResumeProcess` and `RtlNtStatusToDosError` are exported by `ntdll`,
+    /// but aren't exposed by `winapi`, so we declare them ourselves.
+    fn NtSuspendProcess(process: winnt::HANDLE) -> NTSTATUS;
+    fn NtResumeProcess(process: winnt::HANDLE) -> NTSTATUS;
+    fn RtlNtStatusToDosError(status: NTSTATUS) -> minwindef::ULONG;
+    /// `NtQueryInformationProcess` is documented, but isn't exposed by `winapi` either.
+    fn NtQueryInformationProcess(
+        process: winnt::HANDLE,
+        class: minwindef::UINT,
+        info: minwindef::LPVOID,
+        len: minwindef::ULONG,
+        return_len: minwindef::PULONG,
+    ) -> NTSTATUS;
+}
+
+/// Processes are suspended and resumed with the undocumented, but long-standing,
+/// `NtSuspendProcess` and `NtResumeProcess`, which suspend and resume every thread at once. Like
+/// `SuspendThread`, these keep a count, so the process only runs again once it has been resumed as
+/// many times as it was suspended. The handle must have `PROCESS_SUSPEND_RESUME` access.
+impl ControlProcess for ProcessHandle {
+    fn suspend(&self) -> std::io::Result<()> {
+        nt_result(unsafe { NtSuspendProcess(self.as_raw()) })
+    }
+
+    fn resume(&self) -> std::io::Result<()> {
+        nt_result(unsafe { NtResumeProcess(self.as_raw()) })
+    }
+}
+
+/// The `PROCESS_BASIC_INFORMATION` that `NtQueryInformationProcess` returns for
+/// `ProcessBasicInformation`.
+#[repr(C)]
+struct ProcessBasicInformation {
+    exit_status: NTSTATUS,
+    peb_base_address: usize,
+    affinity_mask: usize,
+    base_priority: i32,
+    unique_process_id: usize,
+    inherited_from_unique_process_id: usize,
+}
+
+/// The `PROCESSINFOCLASS` for the `PROCESS_BASIC_INFORMATION` of a process.
+const PROCESS_BASIC_INFORMATION_CLASS: minwindef::UINT = 0;
+/// The `PROCESSINFOCLASS` for the address of the 32-bit PEB of a WOW64 process.
+const PROCESS_WOW64_INFORMATION_CLASS: minwindef::UINT = 26;
+
+/// Fill in `info` with the information of class `class` about the process.
+fn query_information<T>(
+    handle: &ProcessHandle,
+    class: minwindef::UINT,
+    info: &mut T,
+) -> std::io::Result<()> {
+    #[allow(clippy::cast_possible_truncation)]
+    nt_result(unsafe {
+        NtQueryInformationProcess(
+            handle.as_raw(),
+            class,
+            std::ptr::from_mut(info).cast(),
+            std::mem::size_of::<T>() as minwindef::ULONG,
+            ptr::null_mut(),
+        )
+    })
+}
+
+/// Get the address of the PEB of the process, along with the `Architecture` of the pointers in
+/// it.
+fn peb(handle: &ProcessHandle) -> std::io::Result<(usize, Architecture)> {
+    // A WOW64 process has a separate 32-bit PEB, which is laid out with 32-bit pointers.
+    if cfg!(target_pointer_width = "64") && handle.is_wow64()? {
+        let mut peb = 0_usize;
+        query_information(handle, PROCESS_WOW64_INFORMATION_CLASS, &mut peb)?;
+        Ok((peb, Architecture::Arch32Bit))
+    } else {
+        let mut info: ProcessBasicInformation = unsafe { std::mem::zeroed() };
+        query_information(handle, PROCESS_BASIC_INFORMATION_CLASS, &mut info)?;
+        Ok((info.peb_base_address, Architecture::from_native()))
+    }
+}
+
+/// Read the command line of the process from the `CommandLine` of the `ProcessParameters` of its
+/// PEB.
+fn read_command_line(handle: &ProcessHandle) -> std::io::Result<Vec<u16>> {
+    let (peb, arch) = peb(handle)?;
+    // The offsets of `PEB::ProcessParameters` and `RTL_USER_PROCESS_PARAMETERS::CommandLine`.
+    let (parameters_offset, command_line_offset) = if arch == Architecture::Arch32Bit {
+        (0x10, 0x40)
+    } else {
+        (0x20, 0x70)
+    };
+    let mut pointer = vec![0_u8; arch as usize];
+    handle.copy_address(peb + parameters_offset, &mut pointer)?;
+    let command_line = arch.pointer_from_ne_bytes(&pointer) + command_line_offset;
+    // `CommandLine` is a `UNICODE_STRING`, whose length in bytes comes first, and whose buffer
+    // comes after it, aligned to a pointer.
+    let mut len = [0_u8; 2];
+    handle.copy_address(command_line, &mut len)?;
+    handle.copy_address(command_line + arch as usize, &mut pointer)?;
+    let mut bytes = vec![0_u8; u16::from_ne_bytes(len).into()];
+    handle.copy_address(arch.pointer_from_ne_bytes(&pointer), &mut bytes)?;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_ne_bytes([unit[0], unit[1]]))
+        .collect())
+}
+
+/// Split a command line into arguments the way that the C runtime does, with
+/// `CommandLineToArgvW`.
+fn split_command_line(command_line: &[u16]) -> std::io::Result<Vec<String>> {
+    // An empty command line would be taken to mean the command line of this process.
+    if command_line.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut command_line = command_line.to_vec();
+    command_line.push(0);
+    let mut count = 0;
+    let list =
+        unsafe { winapi::um::shellapi::CommandLineToArgvW(command_line.as_ptr(), &raw mut count) };
+    if list.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::cast_sign_loss)]
+    let args = unsafe { std::slice::from_raw_parts(list, count as usize) }
+        .iter()
+        .map(|&arg| {
+            let mut len = 0;
+            while unsafe { *arg.add(len) } != 0 {
+                len += 1;
+            }
+            String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(arg, len) })
+        })
+        .collect();
+    let _ = unsafe { winapi::um::winbase::LocalFree(list.cast()) };
+    Ok(args)
+}
+
+/// Turn an `NTSTATUS` into a `std::io::Result`, using the Win32 error code it corresponds to.
+fn nt_result(status: NTSTATUS) -> std::io::Result<()> {
+    if status >= 0 {
+        Ok(())
+    } else {
+        #[allow(clippy::cast_possible_wrap)]
+        let code = unsafe { RtlNtStatusToDosError(status) } as i32;
+        Err(std::io::Error::from_raw_os_error(code))
+    }
+}
+
+/// Modules are found by taking a toolhelp snapshot of the modules of the process.
+impl FindModule for ProcessHandle {
+    fn get_module_base(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self, name).map(|module| module.modBaseAddr as usize)
+    }
+
+    fn get_module_size(&self, name: &str) -> std::io::Result<usize> {
+        find_module(self, name).map(|module| module.modBaseSize as usize)
+    }
+
+    /// The executable is always the first module in a toolhelp snapshot.
+    fn main_module_base(&self) -> std::io::Result<usize> {
+        use winapi::um::tlhelp32::{Module32FirstW, MODULEENTRY32W};
+
+        let snapshot = Snapshot::new(
+            winapi::um::tlhelp32::TH32CS_SNAPMODULE | winapi::um::tlhelp32::TH32CS_SNAPMODULE32,
+            self.get_pid()?,
+        )?;
+        let mut entry: MODULEENTRY32W = unsafe { std::mem::zeroed() };
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            entry.dwSize = std::mem::size_of::<MODULEENTRY32W>() as minwindef::DWORD;
+        }
+        if unsafe { Module32FirstW(snapshot.0, &raw mut entry) } == minwindef::TRUE {
+            Ok(entry.modBaseAddr as usize)
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    fn to_module_relative(&self, addr: usize) -> std::io::Result<Option<(String, usize)>> {
+        let module = find_module_where(self.get_pid()?, |module| {
+            let base = module.modBaseAddr as usize;
+            base <= addr && addr - base < module.modBaseSize as usize
+        })?;
+        Ok(module.map(|module| {
+            (
+                wide_to_string(&module.szModule),
+                addr - module.modBaseAddr as usize,
+            )
+        }))
+    }
+}
+
+/// A toolhelp snapshot, which is closed when dropped.
+struct Snapshot(winnt::HANDLE);
+
+impl Snapshot {
+    /// Take a snapshot of `flags` (one or more `TH32CS_*` constants) for the process `pid`.
+    fn new(flags: minwindef::DWORD, pid: Pid) -> std::io::Result<Self> {
+        let snapshot = unsafe { winapi::um::tlhelp32::CreateToolhelp32Snapshot(flags, pid) };
+        if snapshot == winapi::um::handleapi::INVALID_HANDLE_VALUE {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(Self(snapshot))
+        }
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = winapi::um::handleapi::CloseHandle(self.0);
+        }
+    }
+}
+
+/// Threads are found by taking a toolhelp snapshot of the threads of every process, and keeping
+/// those owned by this process.
+///
+/// Windows doesn't keep track of which thread a process was started with, so the first thread of
+/// the process in the snapshot is taken to be the main thread, as threads are listed in the order
+/// they were created.
+impl EnumerateThreads for ProcessHandle {
+    fn threads(&self) -> std::io::Result<Vec<ThreadInfo>> {
+        use winapi::um::tlhelp32::{Thread32First, Thread32Next, THREADENTRY32};
+
+        let pid = self.get_pid()?;
+        // Thread snapshots always include every thread of every process, so the pid is ignored.
+        let snapshot = Snapshot::new(winapi::um::tlhelp32::TH32CS_SNAPTHREAD, 0)?;
+        let mut entry: THREADENTRY32 = unsafe { std::mem::zeroed() };
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            entry.dwSize = std::mem::size_of::<THREADENTRY32>() as minwindef::DWORD;
+        }
+        let mut threads = Vec::new();
+        let mut found = unsafe { Thread32First(snapshot.0, &raw mut entry) };
+        while found == minwindef::TRUE {
+            if entry.th32OwnerProcessID == pid {
+                threads.push(ThreadInfo {
+                    tid: entry.th32ThreadID,
+                    is_main: threads.is_empty(),
+                });
+            }
+            found = unsafe { Thread32Next(snapshot.0, &raw mut entry) };
+        }
+        Ok(threads)
+    }
+
+    /// The thread is suspended with `SuspendThread` while `GetThreadContext` reads its
+    /// registers, as the registers of a running thread can't be read reliably. For a 32-bit
+    /// process running under WOW64, these are the registers of the 64-bit WOW64 layer rather than
+    /// those of the 32-bit code.
+    fn thread_context(&self, tid: Tid) -> std::io::Result<ThreadContext> {
+        use winapi::um::processthreadsapi::{GetProcessIdOfThread, OpenThread};
+
+        let thread = unsafe {
+            OpenThread(
+                winnt::THREAD_GET_CONTEXT
+                    | winnt::THREAD_SUSPEND_RESUME
+                    | winnt::THREAD_QUERY_LIMITED_INFORMATION,
+                minwindef::FALSE,
+                tid,
+            )
+        };
+        if thread.is_null() {
+            return Err(std::io::Error::last_os_error());
+        }
+        // Closes the thread handle when we are done with it.
+        let thread = unsafe { std::os::windows::io::OwnedHandle::from_raw_handle(thread.cast()) };
+        let thread = thread.as_raw_handle().cast();
+        if unsafe { GetProcessIdOfThread(thread) } != self.get_pid()? {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("The process has no thread {tid}"),
+            ));
+        }
+        thread_registers(thread)
+    }
+}
+
+/// A `CONTEXT` aligned to 16 bytes, as `GetThreadContext` requires (and `winapi` doesn't
+/// declare).
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+#[repr(C, align(16))]
+struct AlignedContext(winnt::CONTEXT);
+
+/// Read the registers of `thread`, suspending it while we do.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))]
+fn thread_registers(thread: winnt::HANDLE) -> std::io::Result<ThreadContext> {
+    use winapi::um::processthreadsapi::{GetThreadContext, ResumeThread, SuspendThread};
+
+    if unsafe { SuspendThread(thread) } == minwindef::DWORD::MAX {
+        return Err(std::io::Error::last_os_error());
+    }
+    let mut context = AlignedContext(unsafe { std::mem::zeroed() });
+    context.0.ContextFlags = winnt::CONTEXT_FULL;
+    let result = unsafe { GetThreadContext(thread, &raw mut context.0) };
+    let err = std::io::Error::last_os_error();
+    unsafe {
+        let _ = ResumeThread(thread);
+    }
+    if result == minwindef::FALSE {
+        return Err(err);
+    }
+    let context = context.0;
+    #[cfg(target_arch = "x86")]
+    let (instruction_pointer, stack_pointer) = (context.Eip, context.Esp);
+    #[cfg(target_arch = "x86_64")]
+    let (instruction_pointer, stack_pointer) = (context.Rip, context.Rsp);
+    #[cfg(target_arch = "aarch64")]
+    let (instruction_pointer, stack_pointer) = (context.Pc, context.Sp);
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(ThreadContext {
+        instruction_pointer: instruction_pointer as usize,
+        stack_pointer: stack_pointer as usize,
+        raw: context,
+    })
+}
+
+/// Registers can't be read on this architecture.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+fn thread_registers(_thread: winnt::HANDLE) -> std::io::Result<ThreadContext> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Reading the registers of a thread isn't supported on this architecture",
+    ))
+}
+
+/// Find the pid of every process whose executable is called `name` (ignoring case), from a
+/// toolhelp snapshot of every process.
+pub(crate) fn processes_named(name: &str) -> std::io::Result<Vec<Pid>> {
+    use winapi::um::tlhelp32::{Process32FirstW, Process32NextW, PROCESSENTRY32W};
+
+    let snapshot = Snapshot::new(winapi::um::tlhelp32::TH32CS_SNAPPROCESS, 0)?;
+    let mut entry: PROCESSENTRY32W = unsafe { std::mem::zeroed() };
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as minwindef::DWORD;
+    }
+    let name = name.to_lowercase();
+    let mut pids = Vec::new();
+    let mut found = unsafe { Process32FirstW(snapshot.0, &raw mut entry) };
+    while found == minwindef::TRUE {
+        let exe = wide_to_string(&entry.szExeFile).to_lowercase();
+        if exe == name
+            || std::path::Path::new(&exe)
+                .file_stem()
+                .is_some_and(|stem| stem == name.as_str())
+        {
+            pids.push(entry.th32ProcessID);
+        }
+        found = unsafe { Process32NextW(snapshot.0, &raw mut entry) };
+    }
+    Ok(pids)
+}
+
+/// Turn a null-terminated `WCHAR` buffer into a `String`.
+fn wide_to_string(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+/// Find the module called `name` (ignoring case) in a toolhelp snapshot of the process.
+fn find_module(
+    handle: &ProcessHandle,
+    name: &str,
+) -> std::io::Result<winapi::um::tlhelp32::MODULEENTRY32W> {
+    let pid = handle.get_pid()?;
+    let name = name.to_lowercase();
+    find_module_where(pid, |module| {
+        wide_to_string(&module.szModule).to_lowercase() == name
+    })?
+    .ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("No module named {name:?} is loaded in process {pid}"),
+        )
+    })
+}
+
+/// Find the first module loaded in the process `pid` for which `predicate` returns `true`.
+fn find_module_where(
+    pid: Pid,
+    mut predicate: impl FnMut(&winapi::um::tlhelp32::MODULEENTRY32W) -> bool,
+) -> std::io::Result<Option<winapi::um::tlhelp32::MODULEENTRY32W>> {
+    use winapi::um::tlhelp32::{Module32FirstW, Module32NextW, MODULEENTRY32W};
+
+    let snapshot = Snapshot::new(
+        winapi::um::tlhelp32::TH32CS_SNAPMODULE | winapi::um::tlhelp32::TH32CS_SNAPMODULE32,
+        pid,
+    )?;
+    let mut entry: MODULEENTRY32W = unsafe { std::mem::zeroed() };
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        entry.dwSize = std::mem::size_of::<MODULEENTRY32W>() as minwindef::DWORD;
+    }
+    let mut found = unsafe { Module32FirstW(snapshot.0, &raw mut entry) };
+    while found == minwindef::TRUE {
+        if predicate(&entry) {
+            return Ok(Some(entry));
+        }
+        found = unsafe { Module32NextW(snapshot.0, &raw mut entry) };
+    }
+    Ok(None)
+}
+
+/// Turn a `Protection` into the closest `PAGE_*` constant. Windows can't represent memory that can
+/// be written to but not read, so `WRITE` always implies `READ`.
+fn to_page_protection(prot: Protection) -> minwindef::DWORD {
+    use winapi::um::winnt::{
+        PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_NOACCESS, PAGE_READONLY,
+        PAGE_READWRITE,
+    };
+    let write = prot.contains(Protection::WRITE);
+    let read = write || prot.contains(Protection::READ);
+    match (prot.contains(Protection::EXECUTE), read, write) {
+        (false, false, _) => PAGE_NOACCESS,
+        (false, true, false) => PAGE_READONLY,
+        (false, true, true) => PAGE_READWRITE,
+        (true, false, _) => PAGE_EXECUTE,
+        (true, true, false) => PAGE_EXECUTE_READ,
+        (true, true, true) => PAGE_EXECUTE_READWRITE,
+    }
+}
+
+/// Turn a `PAGE_*` constant into the equivalent `Protection`, ignoring modifiers such as
+/// `PAGE_GUARD`. Copy-on-write pages are treated as writable.
+fn from_page_protection(page_protection: minwindef::DWORD) -> Protection {
+    use winapi::um::winnt::{
+        PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY,
+        PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY,
+    };
+    match page_protection & 0xff {
+        PAGE_READONLY => Protection::READ,
+        PAGE_READWRITE | PAGE_WRITECOPY => Protection::READ | Protection::WRITE,
+        PAGE_EXECUTE => Protection::EXECUTE,
+        PAGE_EXECUTE_READ => Protection::READ | Protection::EXECUTE,
+        PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY => Protection::all(),
+        _ => Protection::empty(),
+    }
+}